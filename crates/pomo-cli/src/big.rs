@@ -0,0 +1,134 @@
+use std::io::{stdout, Write};
+
+use pomo_core::pomo::{CurrentPomoState, PomodoroState};
+
+const GLYPH_HEIGHT: usize = 5;
+
+/// Glyphs of the big digits, `#` is a filled cell
+fn glyph(c: char) -> [&'static str; GLYPH_HEIGHT] {
+    match c {
+        '0' => ["#####", "#   #", "#   #", "#   #", "#####"],
+        '1' => ["  #  ", " ##  ", "  #  ", "  #  ", " ### "],
+        '2' => ["#####", "    #", "#####", "#    ", "#####"],
+        '3' => ["#####", "    #", " ####", "    #", "#####"],
+        '4' => ["#   #", "#   #", "#####", "    #", "    #"],
+        '5' => ["#####", "#    ", "#####", "    #", "#####"],
+        '6' => ["#####", "#    ", "#####", "#   #", "#####"],
+        '7' => ["#####", "    #", "   # ", "  #  ", "  #  "],
+        '8' => ["#####", "#   #", "#####", "#   #", "#####"],
+        '9' => ["#####", "#   #", "#####", "    #", "#####"],
+        ':' => ["   ", " # ", "   ", " # ", "   "],
+        '+' => ["     ", "  #  ", "#####", "  #  ", "     "],
+        _ => ["   ", "   ", "   ", "   ", "   "],
+    }
+}
+
+/// The time shown by the big clock, e.g. `24:59` or `+03:10` in open-ended sections and overtime
+fn clock_text(state: &CurrentPomoState) -> String {
+    if state.current_state == PomodoroState::Done {
+        return "00:00".to_string();
+    }
+    let sign = if state.open_ended || state.overtime || state.waiting {
+        "+"
+    } else {
+        ""
+    };
+    format!(
+        "{}{:02}:{:02}",
+        sign,
+        state.duration.num_minutes(),
+        state.duration.num_seconds() % 60
+    )
+}
+
+/// Renders `text` in big digits, every cell of a glyph is `sx` columns wide and `sy` rows high
+fn big_text(text: &str, sx: usize, sy: usize) -> Vec<String> {
+    let glyphs: Vec<_> = text.chars().map(glyph).collect();
+    (0..GLYPH_HEIGHT * sy)
+        .map(|row| {
+            glyphs
+                .iter()
+                .map(|g| {
+                    g[row / sy]
+                        .chars()
+                        .map(|c| if c == '#' { "█" } else { " " }.repeat(sx))
+                        .collect::<String>()
+                })
+                .collect::<Vec<_>>()
+                .join(&" ".repeat(sx))
+        })
+        .collect()
+}
+
+/// Columns and rows of the terminal
+#[cfg(unix)]
+fn terminal_size() -> (usize, usize) {
+    let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+    if unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0
+        && size.ws_col > 0
+    {
+        return (size.ws_col as usize, size.ws_row as usize);
+    }
+    terminal_size_from_env()
+}
+
+#[cfg(not(unix))]
+fn terminal_size() -> (usize, usize) {
+    terminal_size_from_env()
+}
+
+fn terminal_size_from_env() -> (usize, usize) {
+    let var = |name, default| {
+        std::env::var(name)
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+    (var("COLUMNS", 80), var("LINES", 24))
+}
+
+fn center(text: &str, width: usize) -> String {
+    let len = text.chars().count();
+    format!("{}{}", " ".repeat(width.saturating_sub(len) / 2), text)
+}
+
+/// The full-screen clock of `pomo watch --big`. It draws on the alternate screen of the terminal,
+/// which is left again when the clock is dropped.
+pub struct BigClock;
+
+impl BigClock {
+    pub fn new() -> BigClock {
+        // alternate screen, hide the cursor
+        print!("\x1b[?1049h\x1b[?25l");
+        BigClock
+    }
+
+    /// Draws the time left in `state` as big as the terminal allows, with `line` below it
+    pub fn draw(&self, state: &CurrentPomoState, line: &str) {
+        let (width, height) = terminal_size();
+        let text = clock_text(state);
+        let text_width: usize = text.chars().map(|c| glyph(c)[0].len() + 1).sum::<usize>() - 1;
+        // terminal cells are about twice as high as wide
+        let sy = (height.saturating_sub(2) / GLYPH_HEIGHT)
+            .min(width / (2 * text_width))
+            .max(1);
+        let sx = if width >= 2 * text_width { 2 * sy } else { 1 };
+        let digits = big_text(&text, sx, sy);
+        let top = height.saturating_sub(digits.len() + 2) / 2;
+        let mut lines = vec![String::new(); top];
+        lines.extend(digits.iter().map(|d| center(d, width)));
+        lines.push(String::new());
+        lines.push(center(line, width));
+        // redraw in place instead of clearing the screen, which flickers
+        let mut out = stdout().lock();
+        let _ = write!(out, "\x1b[H{}\x1b[J", lines.join("\x1b[K\r\n"));
+        let _ = out.flush();
+    }
+}
+
+impl Drop for BigClock {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = stdout().flush();
+    }
+}
@@ -0,0 +1,344 @@
+use chrono::{Local, Utc};
+use pomo_core::config::{config_file, load_config, Config};
+use pomo_core::storage::{
+    current_file, current_pomo, history_store, read_history, state_path, try_lock_notifier,
+};
+use pomo_core::util::{boot_clock, ErrKind, FixMeLaterError};
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+#[derive(PartialEq)]
+enum Status {
+    Ok,
+    Warn,
+    Fail,
+}
+
+/// The result of one check, `fix` says what to do about a warning or a failure
+struct Check {
+    name: &'static str,
+    status: Status,
+    detail: String,
+    fix: Option<String>,
+}
+
+impl Check {
+    fn ok(name: &'static str, detail: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+    fn warn(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Warn,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+    fn fail(name: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Check {
+        Check {
+            name,
+            status: Status::Fail,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Checks the files, services and clock pomo depends on and prints what to do about the
+/// problems. Fails if any check failed, warnings don't count.
+pub fn doctor() -> Result<(), FixMeLaterError> {
+    let config = load_config();
+    // the other checks would only repeat the error of a broken config
+    let mut checks = vec![check_config(&config), check_state_folder()];
+    if let Ok(config) = &config {
+        checks.push(check_state());
+        checks.push(check_history(config));
+    }
+    checks.push(check_notifications());
+    if let Ok(config) = &config {
+        checks.push(check_sound(config));
+        checks.push(check_daemon());
+    }
+    checks.push(check_clock());
+
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    for check in checks.iter() {
+        let status = match check.status {
+            Status::Ok => "ok",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+        };
+        println!(
+            "{:4}  {:width$}  {}",
+            status,
+            check.name,
+            check.detail,
+            width = width
+        );
+        if let Some(fix) = &check.fix {
+            println!("{:4}  {:width$}  -> {}", "", "", fix, width = width);
+        }
+    }
+    let failed = checks.iter().filter(|c| c.status == Status::Fail).count();
+    if failed > 0 {
+        return Err(FixMeLaterError::K(
+            ErrKind::InvalidState,
+            format!("{} of {} checks failed", failed, checks.len()),
+        ));
+    }
+    Ok(())
+}
+
+fn check_config(config: &Result<Config, FixMeLaterError>) -> Check {
+    let file = config_file();
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            return Check::fail(
+                "config",
+                e.message(),
+                format!("Fix the error or move {} away to use the defaults", file),
+            )
+        }
+    };
+    if !Path::new(&file).exists() {
+        return Check::ok(
+            "config",
+            format!("{} doesn't exist, using the defaults", file),
+        );
+    }
+    let mut problems = vec![];
+    if let Err(e) = config.notification.quiet_hours() {
+        problems.push(e.message().to_string());
+    }
+    for entry in config.schedule.iter() {
+        if let Err(e) = entry.weekdays().and(entry.time()) {
+            problems.push(e.message().to_string());
+        }
+    }
+    if problems.is_empty() {
+        Check::ok("config", file)
+    } else {
+        Check::fail(
+            "config",
+            problems.join("; "),
+            format!("Fix these values in {}", file),
+        )
+    }
+}
+
+fn check_state_folder() -> Check {
+    let probe = state_path(".doctor");
+    let written = probe
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&probe, b""));
+    let _ = fs::remove_file(&probe);
+    let dir = probe
+        .parent()
+        .unwrap_or(Path::new(""))
+        .display()
+        .to_string();
+    match written {
+        Ok(()) => Check::ok("state folder", dir),
+        Err(e) => Check::fail(
+            "state folder",
+            format!("Can't write to {}: {}", dir, e),
+            "Check the permissions of the folder and that the disk isn't full",
+        ),
+    }
+}
+
+fn check_state() -> Check {
+    let file = current_file().unwrap_or_default();
+    match current_pomo() {
+        Ok(pomo) => {
+            let now = Utc::now();
+            let state = if pomo.active {
+                pomo.state(now).current_state.to_string()
+            } else {
+                "inactive".to_string()
+            };
+            Check::ok("current pomo", format!("{}, {}", state, file))
+        }
+        Err(FixMeLaterError::K(ErrKind::NoActivePomo, _)) => {
+            Check::ok("current pomo", "no pomo started yet")
+        }
+        Err(e) => Check::fail(
+            "current pomo",
+            e.message(),
+            format!(
+                "Run 'pomo undo' to go back to the previous state or delete {} to start over",
+                file
+            ),
+        ),
+    }
+}
+
+fn check_history(config: &Config) -> Check {
+    let location = history_store(config.history.backend)
+        .map(|s| s.location())
+        .unwrap_or_default();
+    match read_history() {
+        Ok(entries) => Check::ok(
+            "history",
+            format!("{} entries in {}", entries.len(), location),
+        ),
+        Err(FixMeLaterError::K(ErrKind::Config, message)) => Check::fail(
+            "history",
+            message,
+            "Change 'backend' in the [history] section of the config file",
+        ),
+        Err(e) => Check::fail(
+            "history",
+            e.message(),
+            format!(
+                "Run 'pomo backup', then fix or delete the broken entry in {}",
+                location
+            ),
+        ),
+    }
+}
+
+#[cfg(all(feature = "notifications", unix, not(target_os = "macos")))]
+fn check_notifications() -> Check {
+    match notify_rust::get_server_information() {
+        Ok(info) => Check::ok(
+            "notifications",
+            format!("{} {} by {}", info.name, info.version, info.vendor),
+        ),
+        Err(e) => Check::fail(
+            "notifications",
+            format!("No notification service: {}", e),
+            "Start a notification daemon like dunst or mako, or run pomo inside your desktop session",
+        ),
+    }
+}
+
+#[cfg(all(feature = "notifications", not(all(unix, not(target_os = "macos")))))]
+fn check_notifications() -> Check {
+    Check::ok("notifications", "shown by the system")
+}
+
+#[cfg(not(feature = "notifications"))]
+fn check_notifications() -> Check {
+    let program = if cfg!(target_os = "macos") {
+        "osascript"
+    } else {
+        "notify-send"
+    };
+    if find_program(program) {
+        return Check::ok("notifications", format!("shown with {}", program));
+    }
+    Check::fail(
+        "notifications",
+        format!(
+            "pomo was built without the notifications feature and {} is missing",
+            program
+        ),
+        "Install libnotify for notify-send or build pomo with the notifications feature",
+    )
+}
+
+/// pomo has no sounds of its own, it rings the terminal bell and runs the `[music]` commands
+fn check_sound(config: &Config) -> Check {
+    let music = &config.music;
+    let commands = [&music.warmup, &music.work, &music.break_, &music.done];
+    let missing: Vec<&str> = commands
+        .iter()
+        .filter_map(|c| c.as_deref()?.split_whitespace().next())
+        .filter(|program| !find_program(program))
+        .collect();
+    if !missing.is_empty() {
+        return Check::warn(
+            "sound",
+            format!("{} of the [music] section not found", missing.join(", ")),
+            "Install it or change the commands in the [music] section of the config file",
+        );
+    }
+    let configured = commands.iter().any(|c| c.is_some());
+    match (config.bell, configured) {
+        (false, false) => Check::ok(
+            "sound",
+            "none configured, set bell = true or commands in the [music] section",
+        ),
+        (true, false) => Check::ok("sound", "terminal bell of pomo watch"),
+        (_, true) => Check::ok("sound", "commands of the [music] section"),
+    }
+}
+
+/// Whether `program` is a path to a file or can be found in `$PATH`
+fn find_program(program: &str) -> bool {
+    if program.contains(std::path::MAIN_SEPARATOR) {
+        return Path::new(program).is_file();
+    }
+    let Some(paths) = env::var_os("PATH") else {
+        return false;
+    };
+    env::split_paths(&paths).any(|dir| {
+        dir.join(program).is_file()
+            || (cfg!(windows) && dir.join(format!("{}.exe", program)).is_file())
+    })
+}
+
+fn check_daemon() -> Check {
+    match try_lock_notifier() {
+        Ok(None) => Check::ok("watch", "pomo watch or pomo daemon is running"),
+        Ok(Some(_)) => Check::warn(
+            "watch",
+            "Neither pomo watch nor pomo daemon is running, so there are no notifications",
+            "Run 'pomo daemon' in the background, e.g. as a systemd user service",
+        ),
+        Err(e) => Check::fail(
+            "watch",
+            e.message(),
+            "Check the permissions of the state folder",
+        ),
+    }
+}
+
+fn check_clock() -> Check {
+    if let Ok(pomo) = current_pomo() {
+        if let Some(modified) = pomo.modified.filter(|m| *m > Utc::now()) {
+            return Check::warn(
+                "clock",
+                format!(
+                    "The current pomo was changed at {}, which is in the future",
+                    modified.to_rfc3339()
+                ),
+                "Set the date and time of the computer right, then restart the pomo",
+            );
+        }
+    }
+    // only systemd knows whether the clock is synchronized
+    let synchronized = Command::new("timedatectl")
+        .args(["show", "-p", "NTPSynchronized", "--value"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim() == "yes");
+    if synchronized == Some(false) {
+        return Check::warn(
+            "clock",
+            "The clock is not synchronized with a time server",
+            "Turn on network time with 'timedatectl set-ntp true'",
+        );
+    }
+    if boot_clock().is_none() {
+        return Check::ok(
+            "clock",
+            "changes of the clock can't be told apart from passing time on this system",
+        );
+    }
+    Check::ok(
+        "clock",
+        Local::now().format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+    )
+}
@@ -0,0 +1,64 @@
+use std::fs;
+use std::io::{self, IsTerminal};
+use tracing::level_filters::LevelFilter;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::prelude::*;
+
+/// Number of daily log files kept in the log folder
+const LOG_FILES: usize = 7;
+
+/// Logs to stderr, only errors if `quiet`, warnings by default and more with every `verbose`
+/// step. With `log_dir` the messages are also written to daily rotated files in that folder.
+pub fn init(verbose: u8, quiet: bool, log_dir: Option<&str>) {
+    let level = match verbose {
+        _ if quiet => LevelFilter::ERROR,
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    };
+    let stderr = tracing_subscriber::fmt::layer()
+        .with_ansi(io::stderr().is_terminal())
+        .with_writer(io::stderr)
+        .with_filter(targets(level));
+
+    let file = log_dir.and_then(|dir| {
+        if let Err(e) = fs::create_dir_all(dir) {
+            eprintln!("Could not create log folder {}: {}", dir, e);
+            return None;
+        }
+        let appender = RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("pomo")
+            .filename_suffix("log")
+            .max_log_files(LOG_FILES)
+            .build(dir);
+        match appender {
+            Ok(appender) => Some(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(appender)
+                    .with_filter(targets(level.max(LevelFilter::INFO))),
+            ),
+            Err(e) => {
+                eprintln!("Could not write logs to {}: {}", dir, e);
+                None
+            }
+        }
+    });
+
+    tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .init();
+}
+
+/// Only the messages of pomo itself, libraries only log warnings
+fn targets(level: LevelFilter) -> Targets {
+    Targets::new()
+        .with_target("pomo", level)
+        .with_target("pomo_core", level)
+        .with_target("pomo_integrations", level)
+        .with_default(level.min(LevelFilter::WARN))
+}
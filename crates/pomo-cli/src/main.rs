@@ -0,0 +1,2734 @@
+#[cfg(feature = "http")]
+mod serve;
+mod big;
+mod doctor;
+mod signals;
+mod logging;
+mod notification;
+mod sink;
+
+use crate::big::BigClock;
+use crate::signals::SignalAction;
+use crate::notification::{notify, notify_reminder, notify_state};
+use crate::sink::Sink;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, SecondsFormat, Utc};
+use notify::Event;
+use pomo_core::config::{
+    is_quiet, load_config, HistoryBackend, IdleResume, LongPauseAction, PastTime, ReportConfig,
+    ScheduleEntry, StartWhen, SuspendAction,
+};
+use pomo_core::backup::{backup, restore};
+use pomo_core::format::{format_time, render_template, short_status, status_line, FormatOptions};
+use pomo_core::history::{
+    sessions, with_tags, HistoryEntry, HistoryEvent, InterruptionKind, Session,
+};
+use pomo_core::i18n::tr;
+use pomo_core::ical::{calendar, pomo_event};
+use pomo_core::import::{import_sessions, ImportFormat};
+use pomo_core::pomo::{
+    format_duration_human, CurrentPomoState, CurrentSection, PomodoroSection, PomodoroState,
+};
+use pomo_core::query::{query, GroupBy};
+use pomo_core::report::{
+    daily_summary, daily_summary_line, report_due, send_report, weekly_report,
+};
+use pomo_core::stats::{
+    estimates, estimates_report, heatmap, points_report, sparkline, stats_json, GoalProgress, Stats,
+};
+use pomo_core::util::{
+    boot_clock, parse_duration, parse_time_string, shell_command, ErrKind, FixMeLaterError,
+};
+use pomo_core::{
+    pomo::Goal, pomo::MicroBreak, pomo::Note, pomo::PauseMode, pomo::Pomodoro,
+    pomo::PomodoroSetting, pomo::UntilMode, pomo::EYE_CARE, storage::write_current_pomo,
+};
+use pomo_integrations::idle::IdleDetector;
+
+use clap::{command, Arg, ArgAction, ArgGroup, ArgMatches, Command};
+use core::time;
+use std::io::{stdin, stdout, IsTerminal, Write};
+use std::path::Path;
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::time::Instant;
+use std::{env, thread};
+use tracing::{debug, error, info, warn};
+use pomo_core::storage::{
+    append_history, changes_current_pomo, current_pomo, history_store, prune_history,
+    query_history, read_history, set_state_file, subscribe_current_pomo, try_lock_notifier,
+    undo_current_pomo, HistoryFilter,
+};
+type CmdResult = Result<(), FixMeLaterError>;
+
+/// A difference between the wall clock and the monotonic clock that counts as the computer being asleep
+const SUSPEND_GAP: Duration = Duration::minutes(1);
+
+fn main() {
+    let matches = cli().get_matches();
+    let log_dir = match matches.subcommand() {
+        Some(("daemon", sub)) => sub.get_one::<String>("log-dir"),
+        _ => None,
+    };
+    logging::init(
+        matches.get_count("verbose"),
+        matches.get_flag("quiet"),
+        log_dir.map(|d| d.as_str()),
+    );
+
+    if let Some(path) = matches.get_one::<String>("state-file") {
+        set_state_file(path);
+    }
+
+    if let Err(e) = run(&matches) {
+        if matches.get_one::<String>("error-format").unwrap() == "json" {
+            eprintln!("{}", error_json(&e));
+        } else {
+            println!("Cought error: {}", e.message());
+        }
+        process::exit(e.kind().exit_code());
+    }
+}
+
+fn cli() -> Command {
+    let cmd = command!()
+        .name("pomo")
+        .propagate_version(true)
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new("state-file")
+                .long("state-file")
+                .value_name("path")
+                .global(true)
+                .help("file that stores the current pomo, also set with $POMO_STATE_FILE"),
+        )
+        .arg(
+            Arg::new("error-format")
+                .long("error-format")
+                .value_name("format")
+                .value_parser(["text", "json"])
+                .default_value("text")
+                .global(true)
+                .help("how errors are printed"),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .action(ArgAction::Count)
+                .global(true)
+                .help("log more to stderr, -v for info and -vv for debug messages"),
+        )
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .action(ArgAction::SetTrue)
+                .global(true)
+                .conflicts_with("verbose")
+                .help("only log errors"),
+        )
+        .subcommand(
+            Command::new("start")
+                .arg_required_else_help(false)
+                .about("Start a new pomodoro")
+                .arg(
+                    Arg::new("pom")
+                        .required(false)
+                        .help("pomodoro definition like 4p45b10, or a preset name like @deepwork"),
+                )
+                .arg(
+                    Arg::new("until")
+                        .short('u')
+                        .long("until")
+                        .value_name("time")
+                        .help(
+                            "time like 17:30, +2h, noon or 2024-05-01 17:30, adjusts the repetition and work duration to match the provided end time",
+                        )
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("until-mode")
+                        .long("until-mode")
+                        .value_parser(["reps", "work", "break", "proportional"])
+                        .requires("until")
+                        .help(
+                            "what --until changes: the repetitions and work time, only the work time, only the breaks or everything proportionally",
+                        ),
+                )
+                .arg(
+                    Arg::new("align")
+                        .long("align")
+                        .value_name("duration")
+                        .conflicts_with("until")
+                        .help("start the first work section at the next round mark, e.g. 5m, and end the sections on round marks"),
+                )
+                .arg(
+                    Arg::new("warmup")
+                        .long("warmup")
+                        .value_name("duration")
+                        .help("get ready for this long before the first work section, e.g. 3m, 0 for none"),
+                )
+                .arg(
+                    Arg::new("micro-breaks")
+                        .long("micro-breaks")
+                        .value_name("every[/length]")
+                        .help("remind to look away and stretch during work sections, e.g. 10m/30s"),
+                )
+                .arg(
+                    Arg::new("no-block")
+                        .long("no-block")
+                        .action(ArgAction::SetTrue)
+                        .help("don't block the sites of the config in this pomo"),
+                )
+                .arg(
+                    Arg::new("no-eye-care")
+                        .long("no-eye-care")
+                        .action(ArgAction::SetTrue)
+                        .help("no 20-20-20 eye-care reminders in this pomo"),
+                )
+                .arg(
+                    Arg::new("start-with-break")
+                        .long("start-with-break")
+                        .action(ArgAction::SetTrue)
+                        .help("begin with a break before the first work section"),
+                )
+                .arg(
+                    Arg::new("at")
+                        .long("at")
+                        .value_name("time")
+                        .conflicts_with_all(["when", "delay"])
+                        .help("start at a time like 9:00, +10m, noon or 2024-05-01 09:00"),
+                )
+                .arg(
+                    Arg::new("delay")
+                        .long("delay")
+                        .value_name("duration")
+                        .conflicts_with("when")
+                        .help("start after a duration like 10m, the same as --at +10m"),
+                )
+                .arg(
+                    Arg::new("when")
+                        .long("when")
+                        .value_parser(["now", "boundary", "after"])
+                        .help(
+                            "if a pomo is active, start now, at the end of its current section or after all its sections",
+                        )
+                        .required(false),
+                )
+                .arg(
+                    Arg::new("strict")
+                        .long("strict")
+                        .action(ArgAction::SetTrue)
+                        .help("stop, skip and extend need --force during work sections"),
+                )
+                .arg(
+                    Arg::new("overtime")
+                        .long("overtime")
+                        .action(ArgAction::SetTrue)
+                        .help("work sections continue as overtime until you run 'break' or 'skip'"),
+                )
+                .arg(
+                    Arg::new("task")
+                        .long("task")
+                        .value_name("name")
+                        .help("what you are working on"),
+                )
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .action(ArgAction::Append)
+                        .help("tag the pomo, can be given multiple times"),
+                )
+                .arg(
+                    Arg::new("estimate")
+                        .long("estimate")
+                        .value_name("n")
+                        .requires("task")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .help("number of work sections you expect the task to take"),
+                )
+                .arg(
+                    Arg::new("goal")
+                        .long("goal")
+                        .action(ArgAction::Append)
+                        .help("something to achieve in this pomo, can be given multiple times"),
+                )
+                .arg(
+                    Arg::new("manual")
+                        .long("manual")
+                        .action(ArgAction::SetTrue)
+                        .help("wait at the end of every section until you run 'next'"),
+                ),
+        )
+        .subcommand(
+            Command::new("flow")
+                .about("Start an open-ended flowtime work session, end it with 'break'"),
+        )
+        .subcommand(
+            Command::new("break")
+                .about("Ends the current work section and starts the break")
+                .arg(force_arg()),
+        )
+        .subcommand(Command::new("presets").about("Print list of presets from the config file"))
+        .subcommand(
+            Command::new("status")
+                .about("Prints the current pomo")
+                .long_about("Prints the current pomo. The exit code is 0 for work, 10 break, 11 paused, 12 done, 13 not started yet, 14 warmup and 3 if there is no active pomo")
+                .arg(human_arg())
+                .arg(
+                    Arg::new("short")
+                        .long("short")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("human")
+                        .help("print only the state and the time left, e.g. 'W 17:32', for shell prompts"),
+                ),
+        )
+        .subcommand(
+            Command::new("prompt")
+                .about("Prints the current pomo briefly for shell prompts, nothing without an active pomo")
+                .arg(
+                    Arg::new("max-width")
+                        .long("max-width")
+                        .value_name("chars")
+                        .default_value("30")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("cut the output to this many characters"),
+                )
+                .arg(
+                    Arg::new("no-task")
+                        .long("no-task")
+                        .action(ArgAction::SetTrue)
+                        .help("leave out the task"),
+                ),
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Watch current pomo and print current state every second")
+                .arg_required_else_help(false)
+                .arg(
+                    Arg::new("file")
+                        .required(false)
+                        .num_args(0..)
+                        .help("if specified, writes the status text to these files or named pipes"),
+                )
+                .arg(
+                    Arg::new("privacy")
+                        .long("privacy")
+                        .action(ArgAction::SetTrue)
+                        .help("hide the task name and tags, e.g. for screen sharing"),
+                )
+                .arg(human_arg())
+                .arg(
+                    Arg::new("interval")
+                        .long("interval")
+                        .value_name("secs")
+                        .default_value("1")
+                        .value_parser(clap::value_parser!(u64).range(1..))
+                        .help("seconds between two updates"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("template")
+                        .help("template for the status line, e.g. '{state} {minutes}m'. Placeholders: {state}, {icon}, {remaining}, {minutes}, {next}, {completed}, {total}, {paused}, {task}, {tags}"),
+                )
+                .arg(
+                    Arg::new("exit-on-done")
+                        .long("exit-on-done")
+                        .action(ArgAction::SetTrue)
+                        .help("exit once the pomo is done or stopped"),
+                )
+                .arg(
+                    Arg::new("bell")
+                        .long("bell")
+                        .action(ArgAction::SetTrue)
+                        .help("ring the terminal bell when a section changes"),
+                )
+                .arg(
+                    Arg::new("big")
+                        .long("big")
+                        .action(ArgAction::SetTrue)
+                        .help("show the time left in big digits that fill the terminal"),
+                )
+                .arg(
+                    Arg::new("once")
+                        .long("once")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["interval", "exit-on-done", "bell", "big"])
+                        .help("print and write the status line once and exit, without notifications"),
+                )
+                .arg(
+                    Arg::new("display-only")
+                        .long("display-only")
+                        .action(ArgAction::SetTrue)
+                        .help("never send notifications or run integrations, leave that to another watch or the daemon"),
+                ),
+        )
+        .subcommand(
+            Command::new("daemon")
+                .about("Watch current pomo in the background, sending notifications and reports")
+                .arg(
+                    Arg::new("log-dir")
+                        .long("log-dir")
+                        .value_name("folder")
+                        .help("also write the log to daily rotated files in this folder"),
+                ),
+        )
+        .subcommand(Command::new("stop").about("Stops the pomo.").arg(force_arg()))
+        .subcommand(
+            Command::new("skip")
+                .visible_alias("next")
+                .about("Ends the current section and starts the next one")
+                .arg(force_arg()),
+        )
+        .subcommand(
+            Command::new("extend")
+                .about("Makes the current section longer")
+                .arg(
+                    Arg::new("minutes")
+                        .required(false)
+                        .default_value("5")
+                        .value_parser(clap::value_parser!(i64).range(1..=1440))
+                        .help("number of minutes to add, at most a day"),
+                )
+                .arg(force_arg()),
+        )
+        .subcommand(
+            Command::new("back-to-work")
+                .about("Ends the current break early")
+                .arg(
+                    Arg::new("rebalance")
+                        .long("rebalance")
+                        .action(ArgAction::SetTrue)
+                        .help("add the rest of the break to the later breaks, the pomo ends at the same time"),
+                ),
+        )
+        .subcommand(
+            Command::new("restart")
+                .about("Starts the current section over from now")
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .action(ArgAction::SetTrue)
+                        .help("start the whole pomo over from now with its original settings"),
+                )
+                .arg(force_arg()),
+        )
+        .subcommand(
+            Command::new("edit")
+                .about("Changes the sections of the current pomo that have not started yet")
+                .arg(
+                    Arg::new("work")
+                        .long("work")
+                        .value_name("duration")
+                        .help("set the length of the upcoming work sections, e.g. 30m"),
+                )
+                .arg(
+                    Arg::new("breaks")
+                        .long("breaks")
+                        .value_name("duration")
+                        .help("set the length of the upcoming breaks, e.g. 15m"),
+                )
+                .arg(
+                    Arg::new("drop-last")
+                        .long("drop-last")
+                        .value_name("n")
+                        .value_parser(clap::value_parser!(usize))
+                        .help("remove the last n sections"),
+                )
+                .arg(
+                    Arg::new("editor")
+                        .long("editor")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["work", "breaks", "drop-last"])
+                        .help("edit the upcoming sections as JSON in $EDITOR"),
+                )
+                .group(
+                    ArgGroup::new("changes")
+                        .args(["work", "breaks", "drop-last", "editor"])
+                        .multiple(true)
+                        .required(true),
+                ),
+        )
+        .subcommand(Command::new("undo").about("Reverts the last change of the current pomo"))
+        .subcommand(
+            Command::new("summary")
+                .about("Print a summary of the day")
+                .arg(
+                    Arg::new("date")
+                        .long("date")
+                        .value_name("YYYY-MM-DD")
+                        .value_parser(parse_date)
+                        .help("day to summarize, today by default"),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Adds the sessions exported from another timer to the history")
+                .arg(
+                    Arg::new("file")
+                        .required(true)
+                        .help("CSV file with a header or JSON list of objects"),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["csv", "flow", "pomotroid"])
+                        .default_value("csv")
+                        .help("timer the file was exported from"),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .action(ArgAction::SetTrue)
+                        .help("only print what would be imported"),
+                ),
+        )
+        .subcommand(
+            Command::new("backup")
+                .about("Write the config, the current pomo and the history to one archive")
+                .arg(
+                    Arg::new("path")
+                        .required(true)
+                        .help("file to write, e.g. pomo-backup.tar.gz"),
+                ),
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Check the config, the state, the notifications, the clock and print how to fix problems"),
+        )
+        .subcommand(
+            Command::new("restore")
+                .about("Replace the config, the current pomo and the history with a backup")
+                .arg(
+                    Arg::new("path")
+                        .required(true)
+                        .help("archive written by 'pomo backup'"),
+                ),
+        )
+        .subcommand(
+            Command::new("note")
+                .about("Adds a note to the current pomo, e.g. what you got done")
+                .arg(Arg::new("text").required(true)),
+        )
+        .subcommand(
+            Command::new("interrupt")
+                .about("Records an interruption of the current work section")
+                .arg(Arg::new("reason").required(false).help("what interrupted you"))
+                .arg(
+                    Arg::new("kind")
+                        .long("kind")
+                        .value_parser(["internal", "external"])
+                        .default_value("internal")
+                        .help("'internal' for your own distractions, 'external' for other people"),
+                )
+                .arg(
+                    Arg::new("pause")
+                        .long("pause")
+                        .action(ArgAction::SetTrue)
+                        .help("also pause the pomo"),
+                ),
+        )
+        .subcommand(Command::new("pause").about("Pauses the pomo, can be resumed with 'unpause'"))
+        .subcommand(
+            Command::new("toggle")
+                .about("Starts a pomo if none is running, pauses it if it runs and unpauses it if it is paused")
+                .arg(
+                    Arg::new("pom")
+                        .required(false)
+                        .help("pomodoro definition or preset to start, e.g. @deepwork, the defaults if not given"),
+                ),
+        )
+        .subcommand(
+            Command::new("unpause")
+                .alias("continue")
+                .about("Unpauses the pomo")
+                .arg(
+                    Arg::new("mode")
+                        .long("mode")
+                        .value_parser(["splice", "shift"])
+                        .help("'splice' turns the pause into a break, 'shift' moves the rest of the pomo by the pause"),
+                ),
+        )
+        .subcommand(
+            Command::new("report")
+                .about("Print the focus time of the last seven days")
+                .arg(
+                    Arg::new("email")
+                        .long("email")
+                        .action(ArgAction::SetTrue)
+                        .help("send the report to the email address from the config file"),
+                ),
+        )
+        .subcommand(
+            Command::new("wait")
+                .about("Waits until the current section ends or the pomo reaches the given state")
+                .arg(
+                    Arg::new("state")
+                        .required(false)
+                        .value_parser(["work", "break", "done"]),
+                ),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Print totals over the whole history")
+                .arg(
+                    Arg::new("points")
+                        .long("points")
+                        .action(ArgAction::SetTrue)
+                        .help("show the points and level earned with finished sections and breaks"),
+                )
+                .arg(tag_filter_arg())
+                .arg(task_filter_arg())
+                .arg(
+                    Arg::new("by-tag")
+                        .long("by-tag")
+                        .action(ArgAction::SetTrue)
+                        .help("also print the focus time of every tag"),
+                )
+                .arg(
+                    Arg::new("heatmap")
+                        .long("heatmap")
+                        .action(ArgAction::SetTrue)
+                        .help("draw the focus time of every day as a calendar"),
+                )
+                .arg(
+                    Arg::new("weeks")
+                        .long("weeks")
+                        .value_name("n")
+                        .default_value("12")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .help("number of weeks in the heatmap"),
+                )
+                .arg(
+                    Arg::new("spark")
+                        .long("spark")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("heatmap")
+                        .help("print the focus time of the last days as a one line sparkline"),
+                )
+                .arg(
+                    Arg::new("days")
+                        .long("days")
+                        .value_name("n")
+                        .default_value("14")
+                        .value_parser(clap::value_parser!(u32).range(1..))
+                        .help("number of days in the sparkline"),
+                )
+                .arg(
+                    Arg::new("json")
+                        .long("json")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with_all(["heatmap", "spark", "points"])
+                        .help("print the totals and the focus time per day, tag and task as JSON"),
+                ),
+        )
+        .subcommand(
+            Command::new("history")
+                .about("Query the recorded sessions")
+                .arg_required_else_help(true)
+                .arg(
+                    Arg::new("show-notes")
+                        .long("show-notes")
+                        .action(ArgAction::SetTrue)
+                        .help("print the notes of every session that has some"),
+                )
+                .subcommand(
+                    Command::new("prune")
+                        .about("Apply the retention of the [history] config section now")
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .action(ArgAction::SetTrue)
+                                .help("only print what would be removed and compacted"),
+                        ),
+                )
+                .subcommand(
+                    Command::new("migrate")
+                        .about("Copy the history to another backend, see 'backend' in the [history] config section")
+                        .arg(
+                            Arg::new("to")
+                                .long("to")
+                                .required(true)
+                                .value_parser(["sqlite", "file"]),
+                        ),
+                )
+                .subcommand(
+                    Command::new("query")
+                        .about("Print the focus time of the sessions in a range of days")
+                        .arg(
+                            Arg::new("from")
+                                .long("from")
+                                .value_name("YYYY-MM-DD")
+                                .value_parser(parse_date)
+                                .help("first day to include"),
+                        )
+                        .arg(
+                            Arg::new("to")
+                                .long("to")
+                                .value_name("YYYY-MM-DD")
+                                .value_parser(parse_date)
+                                .help("last day to include"),
+                        )
+                        .arg(
+                            Arg::new("group-by")
+                                .long("group-by")
+                                .value_parser(["session", "day", "tag", "task"])
+                                .default_value("session"),
+                        )
+                        .arg(tag_filter_arg())
+                        .arg(task_filter_arg())
+                        .arg(
+                            Arg::new("format")
+                                .long("format")
+                                .value_parser(["text", "json", "ics"])
+                                .default_value("text")
+                                .help("ics exports the sessions as iCalendar events"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("goal")
+                .about("List the goals of the current pomo and check them off")
+                .subcommand(
+                    Command::new("add")
+                        .about("Add a goal")
+                        .arg(Arg::new("text").required(true)),
+                )
+                .subcommand(
+                    Command::new("done")
+                        .about("Mark a goal as done")
+                        .arg(goal_number_arg()),
+                )
+                .subcommand(
+                    Command::new("undone")
+                        .about("Mark a goal as not done")
+                        .arg(goal_number_arg()),
+                ),
+        )
+        .subcommand(
+            Command::new("info")
+                .about("Print list of current pomos")
+                .arg(
+                    Arg::new("transitions")
+                        .long("transitions")
+                        .action(ArgAction::SetTrue)
+                        .help("print the state transitions that happened in this pomo instead"),
+                )
+                .arg(
+                    Arg::new("next")
+                        .long("next")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with("transitions")
+                        .help("print only the next transition and its time, e.g. 'break_start 2024-05-01T14:25:00+02:00'"),
+                )
+                .arg(
+                    Arg::new("utc")
+                        .long("utc")
+                        .action(ArgAction::SetTrue)
+                        .help("print times in UTC instead of the local timezone"),
+                )
+                .arg(human_arg()),
+        );
+    #[cfg(feature = "http")]
+    let cmd = cmd.subcommand(
+        Command::new("serve")
+            .about("Serve a JSON API to read and control the pomo over HTTP")
+            .arg(
+                Arg::new("listen")
+                    .long("listen")
+                    .value_name("address")
+                    .default_value("127.0.0.1:7272")
+                    .help("address and port to listen on"),
+            ),
+    );
+    #[cfg(feature = "plugins")]
+    let cmd = cmd
+        .subcommand(Command::new("plugins").about("List the plugins and the events they receive"));
+    #[cfg(feature = "team")]
+    let cmd =
+        cmd.subcommand(Command::new("team").about("Show who of the team is focusing right now"));
+    #[cfg(feature = "google")]
+    let cmd = cmd.subcommand(
+        Command::new("google-login")
+            .about("Allow pomo to create events in Google Calendar")
+            .arg(
+                Arg::new("client-id")
+                    .long("client-id")
+                    .required(true)
+                    .help("client id of an OAuth client for desktop apps"),
+            )
+            .arg(
+                Arg::new("client-secret")
+                    .long("client-secret")
+                    .required(true)
+                    .help("client secret of the OAuth client"),
+            ),
+    );
+    cmd
+}
+
+/// Runs the subcommand in `matches`
+fn run(matches: &ArgMatches) -> CmdResult {
+    match matches.subcommand() {
+        Some(("start", sub)) => start_cmd(sub),
+        Some(("flow", _)) => flow_cmd(),
+        Some(("break", sub)) => break_cmd(sub),
+        Some(("presets", _)) => presets_cmd(),
+        Some(("status", sub)) => status_cmd(sub),
+        Some(("prompt", sub)) => prompt_cmd(sub),
+        Some(("watch", sub)) => watch_cmd(sub),
+        Some(("daemon", _)) => {
+            let icons = load_config()?.icons();
+            let options = FormatOptions {
+                icons: icons.as_ref(),
+                ..Default::default()
+            };
+            let options = WatchOptions {
+                format: options,
+                template: None,
+                interval: 1,
+                exit_on_done: false,
+                bell: false,
+                display_only: false,
+            };
+            watch_loop(&[], WatchOutput::Quiet, options)
+        }
+        Some(("report", sub)) => report_cmd(sub),
+        Some(("stop", sub)) => stop_cmd(sub),
+        Some(("skip", sub)) => skip_cmd(sub),
+        Some(("extend", sub)) => extend_cmd(sub),
+        Some(("back-to-work", sub)) => back_to_work_cmd(sub),
+        Some(("restart", sub)) => restart_cmd(sub),
+        Some(("edit", sub)) => edit_cmd(sub),
+        Some(("undo", _)) => undo_cmd(),
+        Some(("summary", sub)) => summary_cmd(sub),
+        Some(("import", sub)) => import_cmd(sub),
+        Some(("backup", sub)) => {
+            let path = sub.get_one::<String>("path").unwrap();
+            let files = backup(Path::new(path))?;
+            println!("backed up {} files to {}", files, path);
+            Ok(())
+        }
+        Some(("restore", sub)) => restore_cmd(sub),
+        Some(("doctor", _)) => doctor::doctor(),
+        Some(("note", sub)) => note_cmd(sub),
+        Some(("interrupt", sub)) => interrupt_cmd(sub),
+        Some(("pause", _)) => pause_cmd(),
+        Some(("toggle", sub)) => toggle_cmd(sub),
+        Some(("unpause", sub)) => unpause_cmd(sub),
+        Some(("wait", sub)) => wait_cmd(sub),
+        Some(("stats", sub)) => stats_cmd(sub),
+        Some(("history", sub)) => history_cmd(sub),
+        Some(("goal", sub)) => goal_cmd(sub),
+        Some(("info", sub)) => info_cmd(sub),
+        #[cfg(feature = "http")]
+        Some(("serve", sub)) => serve::serve(sub.get_one::<String>("listen").unwrap()),
+        #[cfg(feature = "plugins")]
+        Some(("plugins", _)) => {
+            plugins_cmd();
+            Ok(())
+        }
+        #[cfg(feature = "team")]
+        Some(("team", _)) => team_cmd(),
+        #[cfg(feature = "google")]
+        Some(("google-login", sub)) => pomo_integrations::google::login(
+            sub.get_one::<String>("client-id").unwrap(),
+            sub.get_one::<String>("client-secret").unwrap(),
+        )
+        .map_err(FixMeLaterError::S),
+        _ => unreachable!(""),
+    }
+}
+
+#[cfg(feature = "plugins")]
+fn plugins_cmd() {
+    let plugins = pomo_integrations::plugin::discover();
+    if plugins.is_empty() {
+        println!(
+            "No plugins in {}",
+            pomo_core::config::config_path("plugins").display()
+        );
+    }
+    for plugin in plugins {
+        println!(
+            "{}: {} ({})",
+            plugin.name,
+            plugin.events.join(", "),
+            plugin.path.display()
+        );
+    }
+}
+
+#[cfg(feature = "team")]
+fn team_cmd() -> CmdResult {
+    let config = load_config()?;
+    let members = pomo_integrations::team::members(&config.team).map_err(FixMeLaterError::S)?;
+    if members.is_empty() {
+        println!("Nobody of the team is sharing their status yet");
+    }
+    print!("{}", pomo_core::team::render_members(&members, Utc::now()));
+    Ok(())
+}
+
+fn error_json(e: &FixMeLaterError) -> serde_json::Value {
+    serde_json::json!({
+        "error": { "kind": format!("{:?}", e.kind()), "message": e.message() }
+    })
+}
+
+fn parse_date(s: &str) -> Result<NaiveDate, chrono::ParseError> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+}
+
+fn history_cmd(args: &ArgMatches) -> CmdResult {
+    match args.subcommand() {
+        Some(("prune", sub)) => return prune_cmd(sub),
+        Some(("migrate", sub)) => return migrate_cmd(sub),
+        _ => (),
+    }
+    let Some(("query", sub)) = args.subcommand() else {
+        let history = read_history()?;
+        let current = current_pomo().ok();
+        for session in sessions(&history, current.as_ref(), Utc::now()) {
+            let pomo = session.pomodoro;
+            if pomo.notes.is_empty() {
+                continue;
+            }
+            let start = pomo.start.with_timezone(&Local);
+            println!(
+                "{} {}",
+                start.format("%Y-%m-%d %H:%M"),
+                pomo.task.as_deref().unwrap_or("")
+            );
+            print_notes(pomo);
+        }
+        return Ok(());
+    };
+    let filter = history_filter(sub);
+    let history = query_history(&filter)?;
+    let current = current_pomo().ok();
+    let sessions = with_tags(
+        sessions(&history, current.as_ref(), Utc::now()),
+        &tags_arg(sub),
+    );
+    let sessions = with_task(sessions, filter.task.as_deref());
+    let from = sub.get_one::<NaiveDate>("from").copied();
+    let to = sub.get_one::<NaiveDate>("to").copied();
+    let format = sub.get_one::<String>("format").unwrap();
+    if format == "ics" {
+        let events: Vec<String> = sessions
+            .iter()
+            .filter(|s| {
+                let day = s.pomodoro.start.with_timezone(&Local).date_naive();
+                from.is_none_or(|f| day >= f) && to.is_none_or(|t| day <= t)
+            })
+            .map(|s| pomo_event(s.pomodoro, s.end))
+            .collect();
+        print!("{}", calendar(&events));
+        return Ok(());
+    }
+    let result = query(
+        &sessions,
+        from,
+        to,
+        GroupBy::from_arg(sub.get_one::<String>("group-by").unwrap()),
+    );
+    if format == "json" {
+        println!("{}", serde_json::to_string_pretty(&result)?);
+    } else {
+        print!("{}", result);
+    }
+    Ok(())
+}
+
+fn tag_filter_arg() -> Arg {
+    Arg::new("tag")
+        .long("tag")
+        .action(ArgAction::Append)
+        .help("only count sessions with this tag, can be given multiple times")
+}
+
+fn task_filter_arg() -> Arg {
+    Arg::new("task")
+        .long("task")
+        .help("only count the sessions of this task")
+}
+
+/// The entries of the history that `--from`, `--to` and `--task` of `args` can select, so the
+/// SQLite backend doesn't have to load the whole history
+fn history_filter(args: &ArgMatches) -> HistoryFilter {
+    let day_start = |day: NaiveDate| {
+        day.and_time(NaiveTime::MIN)
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|t| t.with_timezone(&Utc))
+    };
+    let date = |id: &str| args.try_get_one::<NaiveDate>(id).ok().flatten().copied();
+    HistoryFilter {
+        from: date("from").and_then(day_start),
+        to: date("to").and_then(|d| d.succ_opt()).and_then(day_start),
+        task: args.get_one::<String>("task").cloned(),
+    }
+}
+
+/// The sessions of `task`, all sessions if it is `None`
+fn with_task<'a>(sessions: Vec<Session<'a>>, task: Option<&str>) -> Vec<Session<'a>> {
+    sessions
+        .into_iter()
+        .filter(|s| task.is_none_or(|t| s.pomodoro.task.as_deref() == Some(t)))
+        .collect()
+}
+
+fn tags_arg(args: &ArgMatches) -> Vec<String> {
+    args.get_many::<String>("tag")
+        .map(|t| t.cloned().collect())
+        .unwrap_or_default()
+}
+
+fn goal_number_arg() -> Arg {
+    Arg::new("number")
+        .required(true)
+        .value_parser(clap::value_parser!(usize))
+        .help("number of the goal as shown by 'pomo goal'")
+}
+
+fn goal_cmd(args: &ArgMatches) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    match args.subcommand() {
+        Some(("add", sub)) => pomo.goals.push(Goal {
+            text: sub.get_one::<String>("text").unwrap().clone(),
+            done: false,
+        }),
+        Some((done @ ("done" | "undone"), sub)) => {
+            let number = *sub.get_one::<usize>("number").unwrap();
+            match pomo.goals.get_mut(number.wrapping_sub(1)) {
+                Some(goal) => goal.done = done == "done",
+                None => {
+                    return Err(FixMeLaterError::K(
+                        ErrKind::InvalidInput,
+                        format!("There is no goal {}, run 'pomo goal' to list them", number),
+                    ))
+                }
+            }
+        }
+        _ => (),
+    }
+    print_goals(&pomo);
+    if args.subcommand().is_some() {
+        write_current_pomo(pomo)?;
+    }
+    Ok(())
+}
+
+fn print_goals(pomo: &Pomodoro) {
+    for (i, goal) in pomo.goals.iter().enumerate() {
+        let check = if goal.done { "x" } else { " " };
+        println!("{}. [{}] {}", i + 1, check, goal.text);
+    }
+}
+
+fn print_notes(pomo: &Pomodoro) {
+    for note in pomo.notes.iter() {
+        println!("  {} {}", format_time(note.time, false), note.text);
+    }
+}
+
+fn note_cmd(args: &ArgMatches) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    if !pomo.active {
+        return Err(FixMeLaterError::K(
+            ErrKind::NoActivePomo,
+            "No active pomo to add the note to".to_string(),
+        ));
+    }
+    pomo.notes.push(Note {
+        time: Utc::now(),
+        text: args.get_one::<String>("text").unwrap().clone(),
+    });
+    print_notes(&pomo);
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn info_cmd(args: &ArgMatches) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    let utc = args.get_flag("utc");
+    let human = args.get_flag("human");
+    if args.get_flag("transitions") {
+        pomo.record_timer_transitions(Utc::now());
+        for t in pomo.transitions.iter() {
+            println!(
+                "{} -- {} -> {} ({})",
+                format_time(t.time, utc),
+                t.from,
+                t.to,
+                t.trigger
+            );
+        }
+        return Ok(());
+    }
+    if args.get_flag("next") {
+        match pomo.next_transition(Utc::now()) {
+            Some((time, state)) => {
+                let kind = match state {
+                    PomodoroState::Done => "done".to_string(),
+                    s => format!("{}_start", s),
+                };
+                let time = if utc {
+                    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+                } else {
+                    time.with_timezone(&Local)
+                        .to_rfc3339_opts(SecondsFormat::Secs, false)
+                };
+                println!("{} {}", kind, time);
+            }
+            None => println!("none"),
+        }
+        return Ok(());
+    }
+    if !pomo.active {
+        println!("inactive");
+        return Ok(());
+    }
+    if let Some(pause) = pomo.pause_started {
+        println!("paused at {}", format_time(pause, utc));
+    }
+    let running = pomo.current_section(Utc::now());
+    for (i, (sec, (start, end))) in pomo.sections.iter().zip(pomo.section_bounds()).enumerate() {
+        let current = if running == CurrentSection::Section(i) {
+            "(Current)"
+        } else {
+            ""
+        };
+        if sec.open {
+            println!(
+                "{}{} -- from {} (open-ended)",
+                current,
+                sec.state,
+                format_time(start, utc),
+            );
+        } else {
+            let length = if human {
+                format!(" ({})", format_duration_human(sec.duration))
+            } else {
+                "".to_string()
+            };
+            println!(
+                "{}{} -- from {} until {}{}",
+                current,
+                sec.state,
+                format_time(start, utc),
+                format_time(end, utc),
+                length
+            );
+        }
+    }
+    print_goals(&pomo);
+    print_notes(&pomo);
+
+    Ok(())
+}
+
+fn pause_cmd() -> CmdResult {
+    let mut pomo = current_pomo()?;
+    pomo.set_pause(Utc::now());
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn toggle_cmd(args: &ArgMatches) -> CmdResult {
+    let now = Utc::now();
+    let running = current_pomo()
+        .ok()
+        .filter(|p| p.active && p.state(now).current_state != PomodoroState::Done);
+    match running {
+        None => {
+            debug!("Toggling by starting a pomo");
+            let queued = start(StartOptions {
+                pom: args.get_one::<String>("pom").map(String::as_str),
+                ..Default::default()
+            })?;
+            print_started(queued)
+        }
+        Some(pomo) if pomo.pause_started.is_some() => {
+            debug!("Toggling by unpausing");
+            unpause(None)
+        }
+        Some(_) => {
+            debug!("Toggling by pausing");
+            pause_cmd()
+        }
+    }
+}
+
+fn unpause_cmd(args: &ArgMatches) -> CmdResult {
+    unpause(args.get_one::<String>("mode").map(String::as_str))
+}
+
+/// Ends the pause, `mode` overrides the pause mode of the pomo
+fn unpause(mode: Option<&str>) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    if let Some(mode) = mode {
+        pomo.pause_mode = PauseMode::from_arg(mode);
+    }
+    let now = Utc::now();
+    let config = load_config()?;
+    let pause = pomo.pause_started.map(|p| now - p);
+    let long_pause = match (pause, config.pause.long_after) {
+        (Some(pause), Some(after)) if pause > Duration::minutes(after) => Some(pause),
+        _ => None,
+    };
+    let mut restart = false;
+    if let Some(pause) = long_pause {
+        restart = match config.pause.long_action {
+            LongPauseAction::Restart => true,
+            LongPauseAction::Split => false,
+            LongPauseAction::Ask => ask(&format!(
+                "The pause took {} minutes, start the interrupted section over?",
+                pause.num_minutes()
+            ))?,
+        };
+        append_history(HistoryEntry::new(
+            now,
+            HistoryEvent::LongPause {
+                duration: pause,
+                restarted: restart,
+            },
+        ))?;
+    }
+    pomo.set_unpause(now, restart);
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+/// Asks a yes/no question on the terminal, `false` if stdin is not a terminal
+fn ask(question: &str) -> Result<bool, FixMeLaterError> {
+    if !stdin().is_terminal() {
+        return Ok(false);
+    }
+    print!("{} [y/N] ", question);
+    stdout().flush()?;
+    let mut answer = String::new();
+    stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn human_arg() -> Arg {
+    Arg::new("human")
+        .long("human")
+        .action(ArgAction::SetTrue)
+        .help("print durations like '23 min left' instead of 00:23:00")
+}
+
+fn force_arg() -> Arg {
+    Arg::new("force")
+        .long("force")
+        .action(ArgAction::SetTrue)
+        .help("interrupt a work section of a strict pomo")
+}
+
+/// Refuses commands that interrupt a work section of a strict pomo unless `--force` is given.
+/// Both refusals and overrides are recorded in the history.
+fn guard_strict(pomo: &Pomodoro, command: &str, force: bool) -> CmdResult {
+    let state = pomo.state(Utc::now());
+    if !pomo.strict || state.current_state != PomodoroState::Work || state.overtime || state.waiting
+    {
+        return Ok(());
+    }
+    let command = command.to_string();
+    if force {
+        append_history(HistoryEntry::new(
+            Utc::now(),
+            HistoryEvent::StrictOverride { command },
+        ))?;
+        return Ok(());
+    }
+    append_history(HistoryEntry::new(
+        Utc::now(),
+        HistoryEvent::StrictRefused {
+            command: command.clone(),
+        },
+    ))?;
+    Err(FixMeLaterError::K(
+        ErrKind::StrictMode,
+        format!(
+            "Strict mode: refusing to {} during a work section, use --force to do it anyway",
+            command
+        ),
+    ))
+}
+
+/// Records the overtime of the current section in the history before it gets ended
+fn record_overtime(pomo: &Pomodoro) -> CmdResult {
+    let state = pomo.state(Utc::now());
+    if state.overtime {
+        append_history(HistoryEntry::new(
+            Utc::now(),
+            HistoryEvent::Overtime {
+                duration: state.duration,
+            },
+        ))?;
+    }
+    Ok(())
+}
+
+/// Stores a pomo that is stopped or replaced in the history as stopped now. A pomo that was
+/// stopped before is in the history already.
+fn archive(pomo: &Pomodoro) -> CmdResult {
+    let now = Utc::now();
+    if !pomo.active || pomo.start >= now {
+        return Ok(());
+    }
+    let mut session = pomo.clone();
+    session.stop(now);
+    append_history(HistoryEntry::new(
+        now,
+        HistoryEvent::Session {
+            end: now.min(session.end().max(session.start)),
+            pomodoro: Box::new(session),
+        },
+    ))
+}
+
+fn stop_cmd(args: &ArgMatches) -> CmdResult {
+    stop(args.get_flag("force"))
+}
+
+fn stop(force: bool) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    if !pomo.active {
+        return Err(FixMeLaterError::K(
+            ErrKind::NoActivePomo,
+            "The pomo is already stopped".to_string(),
+        ));
+    }
+    guard_strict(&pomo, "stop", force)?;
+    record_overtime(&pomo)?;
+    archive(&pomo)?;
+    pomo.stop(Utc::now());
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn presets_cmd() -> CmdResult {
+    let config = load_config()?;
+    for (name, definition) in config.presets.iter() {
+        println!("@{} = {}", name, definition);
+    }
+    Ok(())
+}
+
+fn skip_cmd(args: &ArgMatches) -> CmdResult {
+    skip(args.get_flag("force"))?;
+    println!("{}", current_pomo()?.state(Utc::now()));
+    Ok(())
+}
+
+fn skip(force: bool) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    guard_strict(&pomo, "skip", force)?;
+    record_overtime(&pomo)?;
+    pomo.skip(Utc::now())?;
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn extend_cmd(args: &ArgMatches) -> CmdResult {
+    extend(
+        *args.get_one::<i64>("minutes").unwrap(),
+        args.get_flag("force"),
+    )
+}
+
+fn extend(minutes: i64, force: bool) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    guard_strict(&pomo, "extend", force)?;
+    let by = Duration::try_minutes(minutes).ok_or_else(|| {
+        FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            format!("Can't extend by {} minutes", minutes),
+        )
+    })?;
+    pomo.extend(Utc::now(), by)?;
+    println!("{}", pomo.state(Utc::now()));
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn back_to_work_cmd(args: &ArgMatches) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    pomo.back_to_work(Utc::now(), args.get_flag("rebalance"))?;
+    println!(
+        "{} end: {}",
+        pomo.state(Utc::now()),
+        pomo.end().with_timezone(&Local)
+    );
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn restart_cmd(args: &ArgMatches) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    guard_strict(&pomo, "restart", args.get_flag("force"))?;
+    let now = Utc::now();
+    if args.get_flag("all") {
+        let restarted = pomo.restarted(now)?;
+        if pomo.active {
+            record_overtime(&pomo)?;
+            archive(&pomo)?;
+        }
+        pomo = restarted;
+    } else {
+        pomo.restart_section(now)?;
+    }
+    println!(
+        "{} end: {}",
+        pomo.state(Utc::now()),
+        pomo.end().with_timezone(&Local)
+    );
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn edit_cmd(args: &ArgMatches) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    if args.get_flag("editor") {
+        let sections = edit_in_editor(pomo.upcoming_sections(Utc::now())?)?;
+        // the pomo moves on while the editor is open, the edit only applies if it didn't
+        let mut current = current_pomo()?;
+        let now = Utc::now();
+        if current.start != pomo.start
+            || current.upcoming_sections(now)?.len() != pomo.upcoming_sections(now)?.len()
+        {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "The pomo changed while editing, run 'pomo edit' again".to_string(),
+            ));
+        }
+        current.replace_upcoming(now, sections)?;
+        pomo = current;
+    }
+    let now = Utc::now();
+    for (arg, state) in [
+        ("work", PomodoroState::Work),
+        ("breaks", PomodoroState::Break),
+    ] {
+        if let Some(duration) = args.get_one::<String>(arg) {
+            let duration = parse_duration(duration)?;
+            check_section_duration(duration)?;
+            pomo.set_upcoming_durations(now, state, duration)?;
+        }
+    }
+    if let Some(n) = args.get_one::<usize>("drop-last") {
+        pomo.drop_last(now, *n)?;
+    }
+    println!("{}", pomo.state(now));
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn undo_cmd() -> CmdResult {
+    let pomo = undo_current_pomo()?;
+    if pomo.active {
+        println!("{}", pomo.state(Utc::now()));
+    } else {
+        println!("No active pomo");
+    }
+    Ok(())
+}
+
+fn interrupt_cmd(args: &ArgMatches) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    let now = Utc::now();
+    let section = match pomo.current_section(now) {
+        CurrentSection::Section(i) if pomo.sections[i].state == PomodoroState::Work => i,
+        CurrentSection::Inactive => {
+            return Err(FixMeLaterError::K(
+                ErrKind::NoActivePomo,
+                "No active pomo".to_string(),
+            ))
+        }
+        _ => {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "Interruptions can only be recorded during work sections".to_string(),
+            ))
+        }
+    };
+    let kind = InterruptionKind::from_arg(args.get_one::<String>("kind").unwrap());
+    append_history(HistoryEntry::new(
+        now,
+        HistoryEvent::Interruption {
+            kind,
+            reason: args.get_one::<String>("reason").cloned(),
+            pomo_start: pomo.start,
+            section,
+        },
+    ))?;
+    if args.get_flag("pause") && pomo.pause_started.is_none() {
+        pomo.set_pause(now);
+        write_current_pomo(pomo.clone())?;
+    }
+    println!("{}", pomo.state(now));
+    Ok(())
+}
+
+fn check_section_duration(d: Duration) -> CmdResult {
+    if d <= Duration::zero() {
+        return Err(FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            "Sections have to be longer than zero".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Lets the user edit `sections` as a JSON list in `$VISUAL` or `$EDITOR` and validates the result
+fn edit_in_editor(sections: &[PomodoroSection]) -> Result<Vec<PomodoroSection>, FixMeLaterError> {
+    let list: Vec<serde_json::Value> = sections
+        .iter()
+        .map(|s| {
+            let duration = if s.duration.num_seconds() % 60 == 0 {
+                format!("{}m", s.duration.num_minutes())
+            } else {
+                format!("{}s", s.duration.num_seconds())
+            };
+            serde_json::json!({ "state": s.state.to_string(), "duration": duration })
+        })
+        .collect();
+    let path = env::temp_dir().join(format!("pomo-edit-{}.json", process::id()));
+    std::fs::write(&path, serde_json::to_string_pretty(&list)? + "\n")?;
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let status = shell_command(&format!("{} \"{}\"", editor, path.display())).status();
+    let content = std::fs::read_to_string(&path);
+    let _ = std::fs::remove_file(&path);
+    if !status?.success() {
+        return Err(FixMeLaterError::K(
+            ErrKind::Other,
+            "The editor exited with an error, the pomo was not changed".to_string(),
+        ));
+    }
+    let list: Vec<serde_json::Value> = serde_json::from_str(&content?)
+        .map_err(|e| FixMeLaterError::K(ErrKind::InvalidInput, format!("Invalid JSON: {}", e)))?;
+    list.iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let invalid = |message: &str| {
+                FixMeLaterError::K(
+                    ErrKind::InvalidInput,
+                    format!("Section {}: {}", i + 1, message),
+                )
+            };
+            let state = match s["state"].as_str() {
+                Some("work") => PomodoroState::Work,
+                Some("break") => PomodoroState::Break,
+                _ => return Err(invalid("state has to be \"work\" or \"break\"")),
+            };
+            let duration = match &s["duration"] {
+                serde_json::Value::String(d) => parse_duration(d)?,
+                serde_json::Value::Number(n) => Duration::minutes(
+                    n.as_i64()
+                        .ok_or_else(|| invalid("invalid number of minutes"))?,
+                ),
+                _ => return Err(invalid("duration missing, e.g. \"25m\"")),
+            };
+            check_section_duration(duration)
+                .map_err(|_| invalid("the duration has to be positive"))?;
+            Ok(PomodoroSection {
+                duration,
+                state,
+                open: false,
+                acknowledged: false,
+                paused: Duration::zero(),
+            })
+        })
+        .collect()
+}
+
+fn status_cmd(args: &ArgMatches) -> CmdResult {
+    let pomo = current_pomo()?;
+    let state = pomo.state(Utc::now());
+    let config = load_config()?;
+    let icons = config.icons();
+    let options = FormatOptions {
+        human: args.get_flag("human"),
+        icons: icons.as_ref(),
+        ..Default::default()
+    };
+    if args.get_flag("short") {
+        println!("{}", short_status(&state, options));
+    } else {
+        let mut line = status_line(&pomo, &state, options);
+        if let Some(goal) = config.daily_goal.filter(|g| *g > 0) {
+            let history = read_history()?;
+            let now = Utc::now();
+            let today = now.with_timezone(&Local).date_naive();
+            let progress = GoalProgress::new(&sessions(&history, Some(&pomo), now), goal, today);
+            line += &format!(" [{}/{}]", progress.today, goal);
+        }
+        println!("{}", line);
+    }
+
+    // codes start at 10 so they don't collide with the exit codes of errors
+    let code = match state.current_state {
+        _ if !pomo.active => ErrKind::NoActivePomo.exit_code(),
+        _ if state.pause => 11,
+        PomodoroState::Work => 0,
+        PomodoroState::Break => 10,
+        PomodoroState::Done => 12,
+        PomodoroState::NotStarted => 13,
+        PomodoroState::Warmup => 14,
+    };
+    if code != 0 {
+        process::exit(code);
+    }
+    Ok(())
+}
+
+/// Like `status --short` followed by the task. Prints nothing and exits with 1 if there is no
+/// active pomo or it can't be read, a prompt should never show an error.
+fn prompt_cmd(args: &ArgMatches) -> CmdResult {
+    let Some(pomo) = current_pomo().ok().filter(|p| p.active) else {
+        process::exit(1);
+    };
+    let icons = load_config().ok().and_then(|c| c.icons());
+    let options = FormatOptions {
+        icons: icons.as_ref(),
+        ..Default::default()
+    };
+    let mut line = short_status(&pomo.state(Utc::now()), options);
+    if let Some(task) = pomo.task.as_ref().filter(|_| !args.get_flag("no-task")) {
+        line += &format!(" {}", task);
+    }
+    let max_width = *args.get_one::<usize>("max-width").unwrap();
+    if line.chars().count() > max_width {
+        line = line
+            .chars()
+            .take(max_width.saturating_sub(1))
+            .collect::<String>()
+            + "…";
+    }
+    println!("{}", line);
+    Ok(())
+}
+
+/// The arguments of `pomo start`, so watch and toggle can start pomos without the command line
+#[derive(Default)]
+struct StartOptions<'a> {
+    /// pomo definition or preset, the defaults if not set
+    pom: Option<&'a str>,
+    until: Option<&'a str>,
+    until_mode: Option<&'a str>,
+    align: Option<&'a str>,
+    warmup: Option<&'a str>,
+    micro_breaks: Option<&'a str>,
+    no_block: bool,
+    no_eye_care: bool,
+    start_with_break: bool,
+    at: Option<&'a str>,
+    delay: Option<&'a str>,
+    when: Option<&'a str>,
+    strict: bool,
+    overtime: bool,
+    manual: bool,
+    task: Option<&'a str>,
+    tags: Vec<&'a str>,
+    estimate: Option<u32>,
+    goals: Vec<&'a str>,
+}
+
+fn start_cmd(args: &ArgMatches) -> CmdResult {
+    let text = |id: &str| args.get_one::<String>(id).map(String::as_str);
+    let texts = |id: &str| {
+        args.get_many::<String>(id)
+            .map(|values| values.map(String::as_str).collect())
+            .unwrap_or_default()
+    };
+    let queued = start(StartOptions {
+        pom: text("pom"),
+        until: text("until"),
+        until_mode: text("until-mode"),
+        align: text("align"),
+        warmup: text("warmup"),
+        micro_breaks: text("micro-breaks"),
+        no_block: args.get_flag("no-block"),
+        no_eye_care: args.get_flag("no-eye-care"),
+        start_with_break: args.get_flag("start-with-break"),
+        at: text("at"),
+        delay: text("delay"),
+        when: text("when"),
+        strict: args.get_flag("strict"),
+        overtime: args.get_flag("overtime"),
+        manual: args.get_flag("manual"),
+        task: text("task"),
+        tags: texts("tag"),
+        estimate: args.get_one::<u32>("estimate").copied(),
+        goals: texts("goal"),
+    })?;
+    print_started(queued)
+}
+
+fn print_started(queued: Option<DateTime<Utc>>) -> CmdResult {
+    if let Some(start) = queued {
+        println!("starts at {}", start.with_timezone(&Local));
+    }
+    let pomo = current_pomo()?;
+    println!("{} end: {}", pomo.state(Utc::now()), pomo.end().with_timezone(&Local));
+    Ok(())
+}
+
+/// Starts a pomo, returns when it starts if it was queued after the running one
+fn start(options: StartOptions) -> Result<Option<DateTime<Utc>>, FixMeLaterError> {
+    let pomodoro_string = options.pom.unwrap_or_default();
+
+    let config = load_config()?;
+    let preset = config.resolve_preset(pomodoro_string)?;
+    let pomodoro_string = preset.definition();
+
+    let when = options.when.map_or(config.start_when, StartWhen::from_arg);
+
+    let now = Utc::now();
+    // a running pomo is continued until the new one starts
+    let previous = current_pomo().ok().filter(|p| p.active);
+    let mut running = previous
+        .as_ref()
+        .filter(|p| p.pause_started.is_none() && p.end() > now)
+        .cloned();
+    let roll_over = config.past_time == PastTime::Tomorrow;
+    let at = match (options.at, options.delay) {
+        (Some(at), _) => Some(parse_time_string(at, now, roll_over)?),
+        (None, Some(delay)) => Some(parse_time_string(&format!("+{}", delay), now, roll_over)?),
+        (None, None) => None,
+    };
+    let start = match (&running, when, at) {
+        (_, _, Some(at)) => at,
+        (Some(p), StartWhen::Boundary, _) => p.next_boundary(now).unwrap_or(now),
+        (Some(p), StartWhen::After, _) => {
+            if p.sections.iter().any(|s| s.open) {
+                now
+            } else {
+                p.end()
+            }
+        }
+        _ => now,
+    };
+
+    let defaults = config
+        .defaults
+        .for_day(start.with_timezone(&Local).weekday())
+        .pomodoro_defaults()?;
+    let mut pomo_settings = PomodoroSetting::from_string(pomodoro_string, start, &defaults)?;
+    if let Some(warmup) = options.warmup {
+        pomo_settings.set_warmup(parse_duration(warmup)?)?;
+    }
+    if options.start_with_break {
+        pomo_settings.start_with_break();
+    }
+    if let Some(until_time) = options.until {
+        let date_time = parse_time_string(until_time, now, roll_over)?;
+        let mode = options
+            .until_mode
+            .map_or(config.until_mode, UntilMode::from_arg);
+        pomo_settings.adjust_end_to(date_time, mode)?;
+    }
+    let mut pomo = pomo_settings.to_pomodoro();
+    if let Some(align) = options.align {
+        let every = parse_duration(align)?;
+        if every < Duration::minutes(1) {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidInput,
+                "--align needs at least a minute, e.g. 5m".to_string(),
+            ));
+        }
+        pomo.align(every);
+    }
+    let start = pomo.start;
+    pomo.strict = options.strict || config.strict;
+    pomo.overtime = options.overtime || config.overtime;
+    pomo.manual = options.manual || config.manual;
+    pomo.max_pause = config.pause.max_pause();
+    pomo.pause_mode = config.pause.mode;
+    pomo.micro_break = options
+        .micro_breaks
+        .or(preset.micro_breaks())
+        .map(MicroBreak::parse)
+        .transpose()?;
+    pomo.eye_care = config.eye_care.enabled && !options.no_eye_care;
+    pomo.unblocked = options.no_block;
+    pomo.task = options.task.map(str::to_string);
+    pomo.tags = options.tags.iter().map(|t| t.to_string()).collect();
+    pomo.goals = options
+        .goals
+        .iter()
+        .map(|text| Goal {
+            text: text.to_string(),
+            done: false,
+        })
+        .collect();
+
+    let mut queued = None;
+    if start > now {
+        if let Some(mut current) = running.take() {
+            queued = Some(start);
+            current.queue(pomo);
+            pomo = current;
+        }
+    } else if let Some(previous) = previous {
+        archive(&previous)?;
+    }
+    if let (Some(task), Some(estimate)) = (options.task, options.estimate) {
+        append_history(HistoryEntry::new(
+            now,
+            HistoryEvent::Estimate {
+                task: task.to_string(),
+                work_sections: estimate,
+            },
+        ))?;
+    }
+
+    write_current_pomo(pomo)?;
+    Ok(queued)
+}
+
+fn flow_cmd() -> CmdResult {
+    if let Some(previous) = current_pomo().ok().filter(|p| p.active) {
+        archive(&previous)?;
+    }
+    let config = load_config()?;
+    let mut pomo = Pomodoro::flow(Utc::now());
+    pomo.strict = config.strict;
+    pomo.max_pause = config.pause.max_pause();
+    pomo.pause_mode = config.pause.mode;
+    pomo.eye_care = config.eye_care.enabled;
+    println!("{}", pomo.state(Utc::now()));
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn break_cmd(args: &ArgMatches) -> CmdResult {
+    take_break(args.get_flag("force"))?;
+    println!("{}", current_pomo()?.state(Utc::now()));
+    Ok(())
+}
+
+fn take_break(force: bool) -> CmdResult {
+    let mut pomo = current_pomo()?;
+    let state = pomo.state(Utc::now());
+    if !pomo.active || state.current_state != PomodoroState::Work {
+        return Err(FixMeLaterError::K(
+            ErrKind::InvalidState,
+            "No work section running".to_string(),
+        ));
+    }
+    if state.open_ended {
+        pomo.start_break(Utc::now())?;
+    } else {
+        // a fixed work section ends early like with skip, strict mode refuses that as well
+        guard_strict(&pomo, "break", force)?;
+        record_overtime(&pomo)?;
+        pomo.skip(Utc::now())?;
+    }
+    write_current_pomo(pomo)?;
+    Ok(())
+}
+
+fn prune_cmd(args: &ArgMatches) -> CmdResult {
+    let config = load_config()?;
+    if !config.history.is_enabled() {
+        return Err(FixMeLaterError::K(
+            ErrKind::Config,
+            "Set keep_months or compact_after_months in the [history] section of the config file"
+                .to_string(),
+        ));
+    }
+    let (remove_before, compact_before) = config.history.cutoffs(Utc::now());
+    let dry_run = args.get_flag("dry-run");
+    let pruned = prune_history(remove_before, compact_before, dry_run)?;
+    println!(
+        "{} {} entries, {} {} sessions",
+        if dry_run { "would remove" } else { "removed" },
+        pruned.removed,
+        if dry_run {
+            "would compact"
+        } else {
+            "compacted"
+        },
+        pruned.compacted
+    );
+    Ok(())
+}
+
+fn migrate_cmd(args: &ArgMatches) -> CmdResult {
+    let (from, to) = match args.get_one::<String>("to").unwrap().as_str() {
+        "sqlite" => (HistoryBackend::File, HistoryBackend::Sqlite),
+        _ => (HistoryBackend::Sqlite, HistoryBackend::File),
+    };
+    let source = history_store(from)?;
+    let target = history_store(to)?;
+    if !target.is_empty()? {
+        return Err(FixMeLaterError::K(
+            ErrKind::InvalidState,
+            format!(
+                "The history in {} is not empty, move it away to migrate again",
+                target.location()
+            ),
+        ));
+    }
+    let entries = source.read(&HistoryFilter::default())?;
+    target.append_all(&entries)?;
+    println!(
+        "copied {} entries from {} to {}",
+        entries.len(),
+        source.location(),
+        target.location()
+    );
+    if load_config()?.history.backend != to {
+        println!(
+            "set backend = \"{}\" in the [history] section of the config file to use it",
+            args.get_one::<String>("to").unwrap()
+        );
+    }
+    Ok(())
+}
+
+fn summary_cmd(args: &ArgMatches) -> CmdResult {
+    let history = read_history()?;
+    let current = current_pomo().ok();
+    let now = Utc::now();
+    let day = args
+        .get_one::<NaiveDate>("date")
+        .copied()
+        .unwrap_or(now.with_timezone(&Local).date_naive());
+    let sessions = sessions(&history, current.as_ref(), now);
+    print!("{}", daily_summary(&history, &sessions, day));
+    Ok(())
+}
+
+fn restore_cmd(args: &ArgMatches) -> CmdResult {
+    // a running watch would write the old state right back, the lock can't be found with a broken
+    // config but then no watch runs either
+    if matches!(try_lock_notifier(), Ok(None)) {
+        return Err(FixMeLaterError::K(
+            ErrKind::InvalidState,
+            "Stop pomo watch and pomo daemon before restoring a backup".to_string(),
+        ));
+    }
+    let path = args.get_one::<String>("path").unwrap();
+    let restored = restore(Path::new(path))?;
+    println!("restored {} files from {}", restored.files, path);
+    for previous in restored.previous {
+        println!("the replaced files are in {}", previous.display());
+    }
+    Ok(())
+}
+
+fn import_cmd(args: &ArgMatches) -> CmdResult {
+    let file = args.get_one::<String>("file").unwrap();
+    let format = ImportFormat::from_arg(args.get_one::<String>("format").unwrap());
+    let data = std::fs::read_to_string(file)
+        .map_err(|e| FixMeLaterError::K(ErrKind::Io, format!("Could not read {}: {}", file, e)))?;
+    let (entries, summary) = import_sessions(&data, format, &read_history()?)?;
+    let dry_run = args.get_flag("dry-run");
+    if !dry_run {
+        for entry in entries {
+            append_history(entry)?;
+        }
+    }
+    println!(
+        "{} {} sessions, skipped {} overlapping sessions and {} breaks",
+        if dry_run { "Would import" } else { "Imported" },
+        summary.imported,
+        summary.duplicates,
+        summary.breaks
+    );
+    Ok(())
+}
+
+/// Whether the count of reminders in a section went up since the previous tick, none are shown
+/// for the ones already due when `watch` starts
+fn reminder_due(due: Option<(usize, i64)>, previous: Option<(usize, i64)>) -> bool {
+    matches!((due, previous), (Some((section, count)), Some((previous_section, previous_count)))
+        if section == previous_section && count > previous_count)
+}
+
+/// Applies the suspend action to a section that was running when the computer went to sleep
+fn handle_suspend(
+    action: SuspendAction,
+    pomo: &mut Pomodoro,
+    asleep: DateTime<Utc>,
+    awake: DateTime<Utc>,
+) -> CmdResult {
+    info!("The computer was asleep from {} to {}", asleep, awake);
+    // another watcher may have handled the same suspend already
+    let handled = read_history()?
+        .iter()
+        .any(|e| matches!(e.event, HistoryEvent::Suspend { .. }) && e.time > asleep);
+    if handled
+        || pomo.pause_started.is_some()
+        || !matches!(pomo.current_section(asleep), CurrentSection::Section(_))
+    {
+        return Ok(());
+    }
+    append_history(HistoryEntry::new(
+        awake,
+        HistoryEvent::Suspend {
+            duration: awake - asleep,
+            abandoned: action == SuspendAction::Abandon,
+        },
+    ))?;
+    match action {
+        SuspendAction::Ignore => return Ok(()),
+        SuspendAction::Pause => {
+            pomo.set_pause(asleep);
+            notify(tr(
+                "Paused while the computer was asleep, run 'pomo unpause' to continue.",
+            ));
+        }
+        SuspendAction::Shift => {
+            pomo.set_pause(asleep);
+            pomo.set_unpause(awake, false);
+        }
+        SuspendAction::Abandon => {
+            pomo.stop(asleep);
+            // the abandoned pomo ends where the computer went to sleep
+            append_history(HistoryEntry::new(
+                awake,
+                HistoryEvent::Session {
+                    end: asleep.min(pomo.end()).max(pomo.start),
+                    pomodoro: Box::new(pomo.clone()),
+                },
+            ))?;
+        }
+    }
+    write_current_pomo(pomo.clone())?;
+    Ok(())
+}
+
+/// Notifies the summary of the day if the pomo is done after the configured time
+fn notify_summary(config: &ReportConfig) -> CmdResult {
+    let Some(after) = &config.summary_after else {
+        return Ok(());
+    };
+    let after = NaiveTime::parse_from_str(after, "%H:%M").map_err(|e| {
+        FixMeLaterError::K(
+            ErrKind::Config,
+            format!("Invalid summary_after '{}': {}", after, e),
+        )
+    })?;
+    let now = Utc::now();
+    let local = now.with_timezone(&Local);
+    if local.time() < after {
+        return Ok(());
+    }
+    let history = read_history()?;
+    let current = current_pomo().ok();
+    let sessions = sessions(&history, current.as_ref(), now);
+    notify(&daily_summary_line(&history, &sessions, local.date_naive()));
+    Ok(())
+}
+
+fn report_cmd(args: &ArgMatches) -> CmdResult {
+    let history = read_history()?;
+    let current = current_pomo().ok();
+    let now = Utc::now();
+    let report = weekly_report(&sessions(&history, current.as_ref(), now), now);
+    if args.get_flag("email") {
+        send_report(&load_config()?.report, &report)?;
+        append_history(HistoryEntry::new(now, HistoryEvent::ReportSent))?;
+    } else {
+        print!("{}", report);
+    }
+    Ok(())
+}
+
+fn wait_cmd(args: &ArgMatches) -> CmdResult {
+    let target = args.get_one::<String>("state").map(|s| match s.as_str() {
+        "work" => PomodoroState::Work,
+        "break" => PomodoroState::Break,
+        _ => PomodoroState::Done,
+    });
+    let mut pomo = current_pomo()?;
+    let section = pomo.current_section(Utc::now());
+    if target.is_none() && matches!(section, CurrentSection::Inactive | CurrentSection::AferEnd) {
+        return Err(FixMeLaterError::K(
+            ErrKind::NoActivePomo,
+            "The pomo is already over".to_string(),
+        ));
+    }
+
+    // needed so it won't be freed until the funcion concludes
+    let _watcher;
+    let rx = match subscribe_current_pomo() {
+        Err(_) => None,
+        Ok((rx, m)) => {
+            _watcher = m;
+            Some(rx)
+        }
+    };
+    loop {
+        let now = Utc::now();
+        let state = pomo.state(now);
+        match target {
+            Some(t) if state.current_state == t && !state.pause => return Ok(()),
+            Some(t) if state.current_state == PomodoroState::Done => {
+                return Err(FixMeLaterError::K(
+                    ErrKind::InvalidState,
+                    format!("The pomo ended without reaching {}", t),
+                ))
+            }
+            None if pomo.current_section(now) != section => return Ok(()),
+            _ => (),
+        }
+        if wait_for_change(rx.as_ref(), until_next_tick(now, 1)) {
+            if let Ok(p) = current_pomo() {
+                pomo = p;
+            }
+        }
+    }
+}
+
+fn stats_cmd(args: &ArgMatches) -> CmdResult {
+    let filter = history_filter(args);
+    let history = query_history(&filter)?;
+    let current = current_pomo().ok();
+    let sessions = with_tags(
+        sessions(&history, current.as_ref(), Utc::now()),
+        &tags_arg(args),
+    );
+    let sessions = with_task(sessions, filter.task.as_deref());
+    let stats = Stats::new(&history, &sessions);
+    let today = Local::now().date_naive();
+    let goal = load_config()?
+        .daily_goal
+        .filter(|g| *g > 0)
+        .map(|goal| GoalProgress::new(&sessions, goal, today));
+    if args.get_flag("json") {
+        let estimates = estimates(&history, &sessions);
+        let json = stats_json(&stats, &sessions, &estimates, goal.as_ref());
+        println!("{}", serde_json::to_string_pretty(&json)?);
+    } else if args.get_flag("heatmap") {
+        let weeks = *args.get_one::<u32>("weeks").unwrap();
+        print!("{}", heatmap(&sessions, today, weeks));
+    } else if args.get_flag("spark") {
+        let days = *args.get_one::<u32>("days").unwrap();
+        println!("{}", sparkline(&sessions, today, days));
+    } else if args.get_flag("points") {
+        print!("{}", points_report(&stats));
+    } else {
+        print!("{}", stats);
+        if let Some(goal) = goal {
+            print!("{}", goal);
+        }
+        if args.get_flag("by-tag") {
+            println!();
+            print!("{}", query(&sessions, None, None, GroupBy::Tag));
+        }
+        let estimates = estimates(&history, &sessions);
+        if !estimates.is_empty() {
+            println!();
+            print!("{}", estimates_report(&estimates));
+        }
+    }
+    Ok(())
+}
+
+/// Sends the weekly report unless it was already sent today
+fn send_scheduled_report(config: &ReportConfig) -> CmdResult {
+    let history = read_history()?;
+    let now = Utc::now();
+    let today = now.with_timezone(&Local).date_naive();
+    let sent_today = history.iter().any(|e| {
+        matches!(e.event, HistoryEvent::ReportSent)
+            && e.time.with_timezone(&Local).date_naive() == today
+    });
+    if sent_today {
+        return Ok(());
+    }
+    let current = current_pomo().ok();
+    let report = weekly_report(&sessions(&history, current.as_ref(), now), now);
+    send_report(config, &report)?;
+    append_history(HistoryEntry::new(now, HistoryEvent::ReportSent))
+}
+
+/// Notifies once a day when the daily goal is reached and records it in the history
+fn celebrate_goal(goal: u32) -> CmdResult {
+    let history = read_history()?;
+    let now = Utc::now();
+    let today = now.with_timezone(&Local).date_naive();
+    let celebrated = history.iter().any(|e| {
+        matches!(e.event, HistoryEvent::GoalReached { .. })
+            && e.time.with_timezone(&Local).date_naive() == today
+    });
+    if celebrated {
+        return Ok(());
+    }
+    let current = current_pomo().ok();
+    let progress = GoalProgress::new(&sessions(&history, current.as_ref(), now), goal, today);
+    if !progress.reached() {
+        return Ok(());
+    }
+    info!("Daily goal of {} work sections reached", goal);
+    let details = tr("{sections} work sections, {days} days in a row")
+        .replace("{sections}", &progress.today.to_string())
+        .replace("{days}", &progress.streak.to_string());
+    notify(&format!("{} {}", tr("Daily goal reached!"), details));
+    append_history(HistoryEntry::new(
+        now,
+        HistoryEvent::GoalReached {
+            work_sections: progress.today,
+            streak: progress.streak,
+        },
+    ))
+}
+
+/// Time until the next wall clock second that is a multiple of `interval`, so that all
+/// watchers update at the same instant and don't drift
+fn until_next_tick(now: DateTime<Utc>, interval: u64) -> time::Duration {
+    let interval = interval * 1000;
+    let millis = now.timestamp_millis().max(0) as u64;
+    let next = (millis / interval + 1) * interval;
+    time::Duration::from_millis(next - millis)
+}
+
+/// What wakes `pomo watch` before its next update
+enum Wakeup {
+    PomoChanged,
+    Signal,
+    Interrupted,
+}
+
+/// The next tick of `interval` at which something `pomo watch` shows or does changes: the status
+/// line, the section or a due reminder, but at the latest on the next full minute, when the
+/// schedule and the reports are checked
+fn next_change(
+    pomodoro: &Pomodoro,
+    line: &str,
+    template: Option<&String>,
+    options: FormatOptions,
+    interval: u64,
+    now: DateTime<Utc>,
+) -> DateTime<Utc> {
+    let limit = now + until_next_tick(now, 60);
+    let boundary = pomodoro.next_boundary(now).filter(|b| *b > now);
+    let micro_breaks = pomodoro.micro_breaks_due(now);
+    let eye_care = pomodoro.eye_care_due(now);
+    let mut t =
+        now + Duration::from_std(until_next_tick(now, interval)).unwrap_or(Duration::zero());
+    while t < limit {
+        if let Some(boundary) = boundary.filter(|b| *b <= t) {
+            return boundary;
+        }
+        if watch_line(pomodoro, &pomodoro.state(t), template, options) != line
+            || pomodoro.micro_breaks_due(t) != micro_breaks
+            || pomodoro.eye_care_due(t) != eye_care
+        {
+            return t;
+        }
+        t += Duration::seconds(interval as i64);
+    }
+    limit
+}
+
+/// Sleeps until `deadline` or until something wakes `pomo watch`, returns whether the current
+/// pomo file changed
+fn wait_until(wakeups: &Receiver<Wakeup>, deadline: DateTime<Utc>) -> bool {
+    let timeout = (deadline - Utc::now()).to_std().unwrap_or_default();
+    let first = wakeups.recv_timeout(timeout).ok();
+    // the events that arrived in the meantime are handled by the same update
+    first
+        .into_iter()
+        .chain(wakeups.try_iter())
+        .filter(|w| matches!(w, Wakeup::PomoChanged))
+        .count()
+        > 0
+}
+
+/// Sleeps for `timeout`, but returns early with `true` as soon as the current pomo file changes
+fn wait_for_change(rx: Option<&Receiver<notify::Result<Event>>>, timeout: time::Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    let Some(rx) = rx else {
+        thread::sleep(timeout);
+        return false;
+    };
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        match rx.recv_timeout(remaining) {
+            Ok(Ok(event)) if changes_current_pomo(&event).unwrap_or(false) => return true,
+            Ok(_) => (),
+            Err(RecvTimeoutError::Timeout) => return false,
+            Err(RecvTimeoutError::Disconnected) => {
+                thread::sleep(remaining);
+                return false;
+            }
+        }
+    }
+}
+
+fn watch_cmd(args: &ArgMatches) -> CmdResult {
+    let icons = load_config()?.icons();
+    let options = FormatOptions {
+        privacy: args.get_flag("privacy"),
+        human: args.get_flag("human"),
+        icons: icons.as_ref(),
+    };
+    let files: Vec<String> = args
+        .get_many::<String>("file")
+        .map(|f| f.cloned().collect())
+        .unwrap_or_default();
+    let template = args.get_one::<String>("format");
+    if args.get_flag("once") {
+        let pomodoro = current_pomo()?;
+        let line = watch_line(&pomodoro, &pomodoro.state(Utc::now()), template, options);
+        for path in files.iter() {
+            Sink::open(path)?.write(&line)?;
+        }
+        println!("{}", line);
+        return Ok(());
+    }
+    let output = if args.get_flag("big") {
+        WatchOutput::Big
+    } else {
+        WatchOutput::Line
+    };
+    let options = WatchOptions {
+        format: options,
+        template,
+        interval: *args.get_one::<u64>("interval").unwrap(),
+        exit_on_done: args.get_flag("exit-on-done"),
+        bell: args.get_flag("bell"),
+        display_only: args.get_flag("display-only"),
+    };
+    watch_loop(&files, output, options)
+}
+
+/// Starts the pomos of the schedule that are due, unless a pomo is running already. `started`
+/// holds the day each entry last came due, so it runs once a day.
+fn start_scheduled(
+    schedule: &[&ScheduleEntry],
+    started: &mut [Option<NaiveDate>],
+    pomodoro: &mut Pomodoro,
+) -> CmdResult {
+    let now = Local::now().naive_local();
+    for (entry, started) in schedule.iter().zip(started.iter_mut()) {
+        if *started == Some(now.date()) || !entry.is_due(now)? {
+            continue;
+        }
+        *started = Some(now.date());
+        if pomodoro.active && pomodoro.state(Utc::now()).current_state != PomodoroState::Done {
+            info!(
+                "Not starting the pomo of {} {}, one is running",
+                entry.days, entry.at
+            );
+            continue;
+        }
+        info!(
+            "Starting the pomo of {} {} as scheduled",
+            entry.days, entry.at
+        );
+        let options = StartOptions {
+            pom: entry.pomo.as_deref(),
+            task: entry.task.as_deref(),
+            ..Default::default()
+        };
+        match start(options) {
+            Ok(_) => *pomodoro = current_pomo()?,
+            Err(e) => error!("Could not start the scheduled pomo: {}", e.message()),
+        }
+    }
+    Ok(())
+}
+
+/// The current pomo, an idle one before the first pomo is started so `pomo daemon` can wait for
+/// it or start it from the schedule
+fn current_or_idle() -> Result<Pomodoro, FixMeLaterError> {
+    match current_pomo() {
+        Err(FixMeLaterError::K(ErrKind::NoActivePomo, _)) => Ok(Pomodoro::idle(Utc::now())),
+        result => result,
+    }
+}
+
+/// The status line of `pomo watch`, rendered with `template` if given
+fn watch_line(
+    pomodoro: &Pomodoro,
+    state: &CurrentPomoState,
+    template: Option<&String>,
+    options: FormatOptions,
+) -> String {
+    match template {
+        Some(t) => render_template(t, pomodoro, state, options),
+        None => status_line(pomodoro, state, options),
+    }
+}
+
+/// What `pomo watch` shows in the terminal
+#[derive(PartialEq, Clone, Copy)]
+enum WatchOutput {
+    /// the status line, updated in place
+    Line,
+    /// the time left in big digits, see `BigClock`
+    Big,
+    /// nothing, for the daemon
+    Quiet,
+}
+
+struct WatchOptions<'a> {
+    format: FormatOptions<'a>,
+    /// template of the status line
+    template: Option<&'a String>,
+    /// seconds between two updates
+    interval: u64,
+    /// return once the pomo is over
+    exit_on_done: bool,
+    /// ring the terminal bell on every change of the section
+    bell: bool,
+    /// never become the watcher that sends the notifications
+    display_only: bool,
+}
+
+/// Runs until interrupted, sends notifications on state changes and writes the state to `files`
+/// on every update. Only one watcher of a pomo sends notifications and runs the integrations, the
+/// others only display the state and take over when it exits.
+fn watch_loop(files: &[String], output: WatchOutput, options: WatchOptions) -> CmdResult {
+    let WatchOptions {
+        format: options,
+        template,
+        interval,
+        exit_on_done,
+        bell,
+        display_only,
+    } = options;
+    let mut sinks = files
+        .iter()
+        .map(|path| Sink::open(path))
+        .collect::<Result<Vec<_>, _>>()?;
+    let config = load_config()?;
+    let mut report_checked = None;
+    let mut history_pruned = None;
+    let bell = bell || (output != WatchOutput::Quiet && config.bell);
+    let quiet_hours = config.notification.quiet_hours()?;
+    let big_clock = (output == WatchOutput::Big).then(BigClock::new);
+    let mut notifier = None;
+    let mut integrations = vec![];
+    let mut idle_detector = config
+        .idle
+        .threshold
+        .filter(|t| *t > 0)
+        .and_then(|t| IdleDetector::new(Duration::minutes(t)));
+    let mut idle_paused = false;
+    let mut micro_breaks = None;
+    let mut eye_care = None;
+    let schedule: Vec<&ScheduleEntry> = config
+        .schedule
+        .iter()
+        .filter(|entry| match entry.weekdays().and(entry.time()) {
+            Ok(_) => true,
+            Err(e) => {
+                error!("{}", e.message());
+                false
+            }
+        })
+        .collect();
+    let mut schedule_started = vec![None; schedule.len()];
+
+    let (wake, wakeups) = mpsc::channel();
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = running.clone();
+        let wake = wake.clone();
+        if let Err(e) = ctrlc::set_handler(move || {
+            running.store(false, Ordering::SeqCst);
+            let _ = wake.send(Wakeup::Interrupted);
+        }) {
+            warn!("unable to handle Ctrl-C: {}", e);
+        }
+    }
+    {
+        let wake = wake.clone();
+        signals::listen(move || {
+            let _ = wake.send(Wakeup::Signal);
+        });
+    }
+
+    // only the daemon and watchers with a schedule wait for the first pomo, `pomo watch` fails
+    // like `pomo watch --once` without one
+    let waits = output == WatchOutput::Quiet || !schedule.is_empty();
+    let load = || {
+        if waits {
+            current_or_idle()
+        } else {
+            current_pomo()
+        }
+    };
+    let mut pomodoro = load()?;
+
+    // nothing is announced before the first pomo is started
+    let mut pomodoro_state = if pomodoro.sections.is_empty() {
+        PomodoroState::Done
+    } else {
+        PomodoroState::NotStarted
+    };
+
+    // needed so it won't be freed until the funcion concludes
+    let _watcher;
+
+    match subscribe_current_pomo() {
+        Err(e) => warn!("unable to subscribe to changes of the pomo file: {:?}", e),
+        Ok((rx, m)) => {
+            _watcher = m;
+            let wake = wake.clone();
+            thread::spawn(move || {
+                for event in rx {
+                    if matches!(event, Ok(e) if changes_current_pomo(&e).unwrap_or(false))
+                        && wake.send(Wakeup::PomoChanged).is_err()
+                    {
+                        break;
+                    }
+                }
+            });
+        }
+    };
+
+    let mut changed = false;
+    let mut last_tick = (Utc::now(), Instant::now(), boot_clock());
+    while running.load(Ordering::SeqCst) {
+        // the monotonic clock stands still while the computer is asleep, the clock since boot
+        // and the wall clock do not. The wall clock also moves when it is set.
+        let awake = Duration::from_std(last_tick.1.elapsed()).unwrap_or(Duration::zero());
+        let now = (Utc::now(), Instant::now(), boot_clock());
+        let asleep = match (&last_tick.2, &now.2) {
+            (Some((id, before)), Some((now_id, after))) if id == now_id => *after - *before - awake,
+            _ => now.0 - last_tick.0 - awake,
+        };
+        last_tick = now;
+        if asleep > SUSPEND_GAP {
+            pomodoro = load()?;
+            changed = false;
+            let awake_at = last_tick.0;
+            if notifier.is_some() {
+                handle_suspend(
+                    config.suspend.action,
+                    &mut pomodoro,
+                    awake_at - asleep,
+                    awake_at,
+                )?;
+            }
+        }
+        if let Some(offset) = pomodoro.reconcile_clock(last_tick.0) {
+            info!("The clock was changed by {}s", offset.num_seconds());
+        }
+        if changed {
+            // the file might be read while it is still being written, keep the old state and
+            // retry on the next tick in that case
+            match load() {
+                Ok(p) => {
+                    debug!("Reloaded the current pomo");
+                    pomodoro = p;
+                    changed = false;
+                }
+                Err(e) => debug!(
+                    "Could not reload the current pomo, retrying: {}",
+                    e.message()
+                ),
+            }
+        }
+
+        if notifier.is_none() && !display_only {
+            notifier = try_lock_notifier().unwrap_or_else(|e| {
+                warn!("Could not lock the notifications: {}", e.message());
+                None
+            });
+            if notifier.is_some() {
+                info!("Sending the notifications of this pomo");
+                integrations = pomo_integrations::integrations(&config);
+                if pomodoro_state != PomodoroState::NotStarted {
+                    // the watcher that sent the notifications exited, the section it announced
+                    // isn't announced again
+                    let state = pomodoro.state(Utc::now());
+                    for integration in integrations.iter_mut() {
+                        integration.transition(PomodoroState::NotStarted, &state);
+                    }
+                }
+            }
+        }
+        let notifies = notifier.is_some();
+        // files and the status line are still updated during the quiet hours
+        let quiet = is_quiet(quiet_hours, Local::now().time());
+        let loud = notifies && !quiet;
+
+        // only the watcher that sends the notifications acts on signals, so `pkill` doesn't
+        // pause and unpause again in the next watcher
+        for action in signals::received().into_iter().filter(|_| notifies) {
+            let (command, result) = match action {
+                SignalAction::TogglePause if pomodoro.pause_started.is_some() => {
+                    ("unpause", unpause(None))
+                }
+                SignalAction::TogglePause => ("pause", pause_cmd()),
+                SignalAction::Skip => ("skip", skip(false)),
+            };
+            info!("Ran '{}' for a signal", command);
+            match result {
+                Ok(()) => pomodoro = load()?,
+                Err(e) => error!("Could not {}: {}", command, e.message()),
+            }
+        }
+
+        let today = Local::now().date_naive();
+        if notifies
+            && config.report.schedule
+            && report_due(Utc::now())
+            && report_checked != Some(today)
+        {
+            report_checked = Some(today);
+            if let Err(e) = send_scheduled_report(&config.report) {
+                error!("Could not send weekly report: {}", e.message());
+            }
+        }
+
+        if notifies && config.history.is_enabled() && history_pruned != Some(today) {
+            history_pruned = Some(today);
+            let (remove_before, compact_before) = config.history.cutoffs(Utc::now());
+            match prune_history(remove_before, compact_before, false) {
+                Ok(p) => info!(
+                    "Pruned the history: removed {} entries, compacted {} sessions",
+                    p.removed, p.compacted
+                ),
+                Err(e) => error!("Could not prune the history: {}", e.message()),
+            }
+        }
+
+        if notifies {
+            start_scheduled(&schedule, &mut schedule_started, &mut pomodoro)?;
+        }
+
+        if let Some(detector) = idle_detector.as_mut().filter(|_| notifies) {
+            let now = Utc::now();
+            let state = pomodoro.state(now);
+            match detector.idle_time() {
+                Some(idle) if state.current_state == PomodoroState::Work && !state.pause => {
+                    // the idle time does not count as work
+                    pomodoro.set_pause((now - idle).max(pomodoro.start));
+                    write_current_pomo(pomodoro.clone())?;
+                    idle_paused = true;
+                }
+                None if idle_paused => {
+                    idle_paused = false;
+                    if config.idle.resume == IdleResume::Auto {
+                        pomodoro.set_unpause(now, false);
+                        write_current_pomo(pomodoro.clone())?;
+                    } else if !quiet {
+                        notify(tr("Welcome back! Run 'pomo unpause' to continue working."));
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        let cur_state = pomodoro.state(Utc::now());
+        if cur_state.current_state != pomodoro_state {
+            info!(
+                "State changed from {} to {}",
+                pomodoro_state, cur_state.current_state
+            );
+            for integration in integrations.iter_mut() {
+                integration.transition(pomodoro_state, &cur_state);
+            }
+            let previous_state = pomodoro_state;
+            pomodoro_state = cur_state.current_state;
+            if loud {
+                notify_state(&config.notification, &pomodoro, &cur_state, options);
+            }
+            if loud
+                && cur_state.current_state == PomodoroState::Done
+                && matches!(previous_state, PomodoroState::Work | PomodoroState::Break)
+            {
+                if let Err(e) = notify_summary(&config.report) {
+                    error!("Could not show the summary of the day: {}", e.message());
+                }
+            }
+            if let Some(goal) = config.daily_goal.filter(|g| *g > 0 && loud) {
+                if let Err(e) = celebrate_goal(goal) {
+                    error!("Could not check the daily goal: {}", e.message());
+                }
+            }
+            if bell && !quiet {
+                print!("\x07");
+            }
+        }
+        let due = pomodoro.micro_breaks_due(Utc::now());
+        if let Some(micro) = pomodoro
+            .micro_break
+            .filter(|_| loud && reminder_due(due, micro_breaks))
+        {
+            notify_reminder(
+                &tr("Micro-break! Look away and stretch for {length}.")
+                    .replace("{length}", &format_duration_human(micro.length)),
+                micro.length,
+            );
+        }
+        micro_breaks = due;
+        let due = pomodoro.eye_care_due(Utc::now());
+        if loud && reminder_due(due, eye_care) {
+            notify_reminder(
+                tr("Look at something 20 feet away for 20 seconds."),
+                EYE_CARE.length,
+            );
+        }
+        eye_care = due;
+        let now = Utc::now();
+        let state = pomodoro.state(now);
+        for integration in integrations.iter_mut() {
+            integration.tick(&pomodoro, &state);
+        }
+        let plain_line = watch_line(&pomodoro, &state, template, options);
+        let mut line = plain_line.clone();
+        for integration in integrations.iter_mut() {
+            line = integration.format_status(&pomodoro, &state, line);
+        }
+        for sink in sinks.iter_mut() {
+            sink.write(&line)?;
+        }
+        match &big_clock {
+            Some(clock) => clock.draw(&state, &line),
+            None if output == WatchOutput::Line => {
+                print!("\r{}        ", line);
+                stdout().flush().unwrap();
+            }
+            None => (),
+        }
+        if exit_on_done && (!pomodoro.active || state.current_state == PomodoroState::Done) {
+            break;
+        }
+        // the big clock shows the seconds, idle time and new readers of a pipe have to be polled
+        // and a failed reload is retried on the next tick
+        let next = if big_clock.is_some()
+            || (idle_detector.is_some() && notifies)
+            || sinks.iter().any(Sink::waits_for_reader)
+            || changed
+        {
+            now + until_next_tick(now, interval)
+        } else {
+            next_change(&pomodoro, &plain_line, template, options, interval, now)
+        };
+        let next = integrations
+            .iter()
+            .filter_map(|i| i.next_tick())
+            .map(|t| t.max(now))
+            .map(|t| t + until_next_tick(t, interval))
+            .fold(next, DateTime::min);
+        changed |= wait_until(&wakeups, next);
+    }
+
+    for integration in integrations.iter_mut() {
+        integration.cleanup();
+    }
+    if output == WatchOutput::Line {
+        println!();
+    }
+    Ok(())
+}
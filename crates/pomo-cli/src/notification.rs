@@ -0,0 +1,193 @@
+#[cfg(feature = "notifications")]
+use notify_rust::{Notification, Timeout};
+use pomo_core::config::NotificationConfig;
+#[cfg(feature = "notifications")]
+use pomo_core::config::NotificationContent;
+use pomo_core::format::{render_template, FormatOptions};
+use pomo_core::i18n::tr;
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+#[cfg(not(feature = "notifications"))]
+use std::process::Command;
+use tracing::{debug, warn};
+
+const DEFAULT_TITLE: &str = "Pomodoro State {state}!";
+const DEFAULT_BODY: &str = "{task}";
+const WARMUP_BODY: &str = "Clear your desk and write down your goal. {task}";
+
+/// Shows a desktop notification through the notification daemon on Linux and natively on macOS
+/// and Windows. Failures are printed, a missing notification daemon doesn't stop `watch`.
+pub fn notify(summary: &str) {
+    #[cfg(feature = "notifications")]
+    show(Notification::new().summary(summary));
+    #[cfg(not(feature = "notifications"))]
+    show_with_command(summary, "", None, None);
+}
+
+/// A low urgency notification that disappears after `duration`, for reminders during a section
+pub fn notify_reminder(summary: &str, duration: chrono::Duration) {
+    let timeout = duration.num_milliseconds() as u32;
+    #[cfg(feature = "notifications")]
+    {
+        let mut notification = Notification::new();
+        notification
+            .summary(summary)
+            .timeout(Timeout::Milliseconds(timeout));
+        #[cfg(any(all(unix, not(target_os = "macos")), target_os = "windows"))]
+        notification.urgency(notify_rust::Urgency::Low);
+        show(&mut notification);
+    }
+    #[cfg(not(feature = "notifications"))]
+    show_with_command(summary, "", Some("low"), Some(timeout));
+}
+
+/// Notifies about the start of the current section with the content configured for its state
+pub fn notify_state(
+    config: &NotificationConfig,
+    pomo: &Pomodoro,
+    state: &CurrentPomoState,
+    options: FormatOptions,
+) {
+    let content = config.for_state(state.current_state);
+    let render = |template: Option<&str>, default| {
+        render_template(template.unwrap_or(default), pomo, state, options)
+    };
+    let default_body = match state.current_state {
+        PomodoroState::Warmup => tr(WARMUP_BODY),
+        _ => DEFAULT_BODY,
+    };
+    let summary = render(content.title.as_deref(), tr(DEFAULT_TITLE));
+    let body = render(content.body.as_deref(), default_body);
+    #[cfg(not(feature = "notifications"))]
+    {
+        use pomo_core::config::Urgency;
+        let urgency = content.urgency.map(|urgency| match urgency {
+            Urgency::Low => "low",
+            Urgency::Normal => "normal",
+            Urgency::Critical => "critical",
+        });
+        let expire = content.expire.map(|expire| expire * 1000);
+        show_with_command(&summary, body.trim(), urgency, expire);
+    }
+    #[cfg(feature = "notifications")]
+    show_state(config, state, &content, &summary, body.trim());
+}
+
+#[cfg(feature = "notifications")]
+fn show_state(
+    config: &NotificationConfig,
+    state: &CurrentPomoState,
+    content: &NotificationContent,
+    summary: &str,
+    body: &str,
+) {
+    let mut notification = Notification::new();
+    notification.summary(summary).body(body);
+    if let Some(icon) = &content.icon {
+        notification.icon(icon);
+    }
+    if let Some(expire) = content.expire {
+        notification.timeout(Timeout::Milliseconds(expire * 1000));
+    }
+    #[cfg(any(all(unix, not(target_os = "macos")), target_os = "windows"))]
+    if let Some(urgency) = content.urgency {
+        use pomo_core::config::Urgency;
+        notification.urgency(match urgency {
+            Urgency::Low => notify_rust::Urgency::Low,
+            Urgency::Normal => notify_rust::Urgency::Normal,
+            Urgency::Critical => notify_rust::Urgency::Critical,
+        });
+    }
+    let actions = if config.actions.unwrap_or(true) {
+        actions(state.current_state)
+    } else {
+        vec![]
+    };
+    for (command, label) in actions.iter() {
+        notification.action(command, label);
+    }
+    debug!("Showing notification '{}'", notification.summary);
+    match notification.appname("pomo").show() {
+        // buttons are only supported by the notification daemons on Linux and on Windows
+        #[cfg(any(all(unix, not(target_os = "macos")), target_os = "windows"))]
+        Ok(handle) if !actions.is_empty() => {
+            std::thread::spawn(move || handle.wait_for_action(run_action));
+        }
+        Ok(_) => (),
+        Err(e) => warn!("Could not show notification: {}", e),
+    }
+}
+
+/// Buttons of the notification for the section that just started as (command, label)
+#[cfg(feature = "notifications")]
+fn actions(state: PomodoroState) -> Vec<(&'static str, &'static str)> {
+    match state {
+        PomodoroState::Work => vec![
+            ("break", tr("Start break now")),
+            ("extend", "+5 min"),
+            ("skip", tr("Skip")),
+        ],
+        PomodoroState::Break => vec![("extend", "+5 min"), ("skip", tr("Skip"))],
+        PomodoroState::Warmup => vec![("skip", tr("Skip"))],
+        PomodoroState::NotStarted | PomodoroState::Done => vec![],
+    }
+}
+
+/// Runs the command of a button like it was run in the terminal
+#[cfg(feature = "notifications")]
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+fn run_action(action: &str) {
+    let result = match action {
+        "break" => crate::take_break(false),
+        "extend" => crate::extend(5, false),
+        "skip" => crate::skip(false),
+        // the notification was closed without pressing a button
+        _ => return,
+    };
+    if let Err(e) = result {
+        notify(&format!("Could not {}: {}", action, e.message()));
+    }
+}
+
+#[cfg(feature = "notifications")]
+fn show(notification: &mut Notification) {
+    if let Err(e) = notification.appname("pomo").show() {
+        warn!("Could not show notification: {}", e);
+    }
+}
+
+/// Without the notifications feature the command of the system shows them, `notify-send` on Linux
+/// and AppleScript on macOS. They have no buttons then.
+#[cfg(not(feature = "notifications"))]
+fn show_with_command(summary: &str, body: &str, urgency: Option<&str>, expire_ms: Option<u32>) {
+    debug!("Showing notification '{}'", summary);
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let _ = (urgency, expire_ms);
+        let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {} with title {}",
+            quote(body),
+            quote(summary)
+        ));
+        command
+    };
+    #[cfg(not(target_os = "macos"))]
+    let mut command = {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name", "pomo"]);
+        if let Some(urgency) = urgency {
+            command.args(["--urgency", urgency]);
+        }
+        if let Some(expire) = expire_ms {
+            command.arg(format!("--expire-time={}", expire));
+        }
+        command.arg(summary).arg(body);
+        command
+    };
+    match command.status() {
+        Ok(status) if status.success() => (),
+        Ok(status) => warn!("Could not show notification: {}", status),
+        Err(e) => warn!("Could not show notification: {}", e),
+    }
+}
@@ -0,0 +1,343 @@
+use crate::{
+    error_json, pause_cmd, skip, start, stop, unpause, until_next_tick, wait_for_change, CmdResult,
+    StartOptions,
+};
+use chrono::{Local, Utc};
+use pomo_core::config::load_config;
+use pomo_core::format::{state_json, FormatOptions};
+use pomo_core::history::sessions;
+use pomo_core::pomo::PomodoroState;
+use pomo_core::query::{query, GroupBy};
+use pomo_core::stats::Stats;
+use pomo_core::storage::{current_pomo, read_history, state_path, subscribe_current_pomo};
+use pomo_core::team::{read_members, write_member, MemberStatus};
+use pomo_core::util::{ErrKind, FixMeLaterError};
+use std::io::Write;
+use std::thread;
+use std::time::{self, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{info, warn};
+
+const JSON: &str = "application/json";
+
+/// Serves the state of the current pomo and the commands that change it as JSON
+pub fn serve(listen: &str) -> CmdResult {
+    let token = load_config()?.serve.token.filter(|t| !t.is_empty());
+    if token.is_none() {
+        warn!("No token in the [serve] section of the config file, the POST and PUT routes are refused");
+    }
+    let server = Server::http(listen).map_err(|e| {
+        FixMeLaterError::K(
+            ErrKind::Io,
+            format!("Could not listen on {}: {}", listen, e),
+        )
+    })?;
+    println!("Listening on http://{}", listen);
+    for mut request in server.incoming_requests() {
+        let path = request.url().split('?').next().unwrap_or("").to_string();
+        info!("{} {}", request.method(), path);
+        let get = request.method() == &Method::Get;
+        if get && path == "/events" {
+            thread::spawn(move || stream_events(request));
+            continue;
+        }
+        let (status, body, content_type) = if get && path == "/metrics" {
+            match metrics() {
+                Ok(metrics) => (200, metrics, "text/plain; version=0.0.4"),
+                Err(e) => (http_status(e.kind()), error_json(&e).to_string(), JSON),
+            }
+        } else {
+            let (status, body) = handle(&mut request, &path, token.as_deref());
+            (status, body.to_string(), JSON)
+        };
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(Header::from_bytes("Content-Type", content_type).unwrap());
+        if let Err(e) = request.respond(response) {
+            warn!("Could not send response: {}", e);
+        }
+    }
+    Ok(())
+}
+
+fn handle(request: &mut Request, path: &str, token: Option<&str>) -> (u16, serde_json::Value) {
+    if matches!(request.method(), Method::Post | Method::Put) {
+        if let Err(refused) = authorize(request, token) {
+            return refused;
+        }
+    }
+    let result = match (request.method(), path) {
+        (Method::Get, "/status") => status(),
+        (Method::Get, "/info") => current_pomo().and_then(|p| Ok(serde_json::to_value(p)?)),
+        (Method::Post, "/start" | "/pause" | "/unpause" | "/skip" | "/stop") => {
+            command(request, path)
+        }
+        (Method::Get, "/team") => {
+            read_members(&state_path("team")).and_then(|m| Ok(serde_json::to_value(m)?))
+        }
+        (Method::Put, _) if team_member(path).is_some() => update_member(request, path),
+        (
+            _,
+            "/status" | "/info" | "/start" | "/pause" | "/unpause" | "/skip" | "/stop" | "/metrics"
+            | "/events" | "/team",
+        ) => {
+            let message = format!("{} is not allowed on {}", request.method(), path);
+            return (405, route_error("MethodNotAllowed", &message));
+        }
+        _ => return (404, route_error("NotFound", &format!("No route {}", path))),
+    };
+    match result {
+        Ok(body) => (200, body),
+        Err(e) => (http_status(e.kind()), error_json(&e)),
+    }
+}
+
+/// Checks that a request that changes something comes with the token and not from a web page,
+/// any page the user visits could send requests to `127.0.0.1` otherwise
+fn authorize(request: &Request, token: Option<&str>) -> Result<(), (u16, serde_json::Value)> {
+    let header = |name: &'static str| {
+        request
+            .headers()
+            .iter()
+            .find(|h| h.field.equiv(name))
+            .map(|h| h.value.as_str().trim())
+    };
+    // browser extensions have their own schemes like chrome-extension://
+    if header("Origin").is_some_and(|o| o == "null" || o.starts_with("http")) {
+        let message = "Requests from web pages are not allowed";
+        return Err((403, route_error("Forbidden", message)));
+    }
+    let Some(token) = token else {
+        let message = "Set a token in the [serve] section of the config file to allow this route";
+        return Err((403, route_error("Forbidden", message)));
+    };
+    let sent = header("Authorization").and_then(|a| a.strip_prefix("Bearer "));
+    if !sent.is_some_and(|sent| same_secret(sent.trim(), token)) {
+        let message = "Send the token of the config file as 'Authorization: Bearer <token>'";
+        return Err((401, route_error("Unauthorized", message)));
+    }
+    Ok(())
+}
+
+/// Compares in a time that doesn't depend on where the strings differ
+fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |d, (x, y)| d | (x ^ y)) == 0
+}
+
+/// Sends a server-sent event with the status whenever the state changes, until the client disconnects
+fn stream_events(request: Request) {
+    let mut writer = request.into_writer();
+    let header =
+        "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nCache-Control: no-cache\r\n\r\n";
+    if writer
+        .write_all(header.as_bytes())
+        .and_then(|_| writer.flush())
+        .is_err()
+    {
+        return;
+    }
+
+    // needed so it won't be freed until the funcion concludes
+    let _watcher;
+    let rx = match subscribe_current_pomo() {
+        Err(_) => None,
+        Ok((rx, m)) => {
+            _watcher = m;
+            Some(rx)
+        }
+    };
+    let mut pomo = current_pomo().ok();
+    let mut last = None;
+    let mut last_write = Instant::now();
+    loop {
+        let now = Utc::now();
+        let status = pomo
+            .as_ref()
+            .map(|p| state_json(p, &p.state(now), FormatOptions::default()));
+        // the countdown alone is no change, clients count down themselves
+        let key = status.clone().map(|mut s| {
+            s["remaining_seconds"].take();
+            s
+        });
+        let event = match status {
+            Some(status) if key != last => format!("event: state\ndata: {}\n\n", status),
+            // comments keep proxies from closing the connection and detect clients that left
+            _ if last_write.elapsed() >= time::Duration::from_secs(15) => ":\n\n".to_string(),
+            _ => String::new(),
+        };
+        last = key;
+        if !event.is_empty() {
+            if writer
+                .write_all(event.as_bytes())
+                .and_then(|_| writer.flush())
+                .is_err()
+            {
+                return;
+            }
+            last_write = Instant::now();
+        }
+        if wait_for_change(rx.as_ref(), until_next_tick(now, 1)) {
+            if let Ok(p) = current_pomo() {
+                pomo = Some(p);
+            }
+        }
+    }
+}
+
+/// The name in `/team/<name>`
+fn team_member(path: &str) -> Option<&str> {
+    path.strip_prefix("/team/")
+        .filter(|name| !name.is_empty() && !name.contains('/'))
+}
+
+/// Stores the status a team member's watch sends, `GET /team` lists them
+fn update_member(request: &mut Request, path: &str) -> Result<serde_json::Value, FixMeLaterError> {
+    let mut status: MemberStatus = serde_json::from_value(read_body(request)?)
+        .map_err(|e| FixMeLaterError::K(ErrKind::InvalidInput, format!("Invalid body: {}", e)))?;
+    status.name = team_member(path).unwrap_or_default().to_string();
+    write_member(&state_path("team"), &status)?;
+    Ok(serde_json::to_value(status)?)
+}
+
+fn route_error(kind: &str, message: &str) -> serde_json::Value {
+    serde_json::json!({ "error": { "kind": kind, "message": message } })
+}
+
+/// Renders the current state and the totals from the history in the Prometheus text format
+fn metrics() -> Result<String, FixMeLaterError> {
+    let now = Utc::now();
+    let history = read_history()?;
+    let current = current_pomo().ok();
+    let sessions = sessions(&history, current.as_ref(), now);
+    let stats = Stats::new(&history, &sessions);
+    let today = now.with_timezone(&Local).date_naive();
+    let work_today = query(&sessions, Some(today), Some(today), GroupBy::Day)
+        .groups
+        .first()
+        .map_or(0, |g| g.work_sections);
+
+    let state = current.as_ref().filter(|p| p.active).map(|p| p.state(now));
+    let current_state = state
+        .as_ref()
+        .map_or("inactive", |s| match s.current_state {
+            PomodoroState::NotStarted => "not_started",
+            PomodoroState::Warmup => "warmup",
+            PomodoroState::Work => "work",
+            PomodoroState::Break => "break",
+            PomodoroState::Done => "done",
+        });
+    let mut out = String::new();
+    out += "# HELP pomo_state The state of the current pomo, 1 for the state it is in.\n";
+    out += "# TYPE pomo_state gauge\n";
+    for s in ["inactive", "not_started", "warmup", "work", "break", "done"] {
+        out += &format!(
+            "pomo_state{{state=\"{}\"}} {}\n",
+            s,
+            (s == current_state) as u8
+        );
+    }
+    let paused = state.as_ref().is_some_and(|s| s.pause);
+    let remaining = state.as_ref().map_or(0, |s| s.duration.num_seconds());
+    for (name, kind, help, value) in [
+        (
+            "pomo_paused",
+            "gauge",
+            "Whether the current pomo is paused.",
+            paused as i64,
+        ),
+        (
+            "pomo_remaining_seconds",
+            "gauge",
+            "Seconds left in the current section.",
+            remaining,
+        ),
+        (
+            "pomo_work_sections_today",
+            "gauge",
+            "Work sections completed today.",
+            work_today as i64,
+        ),
+        (
+            "pomo_focus_seconds_total",
+            "counter",
+            "Seconds spent in work sections over the whole history.",
+            stats.focus.num_seconds(),
+        ),
+        (
+            "pomo_work_sections_total",
+            "counter",
+            "Work sections completed over the whole history.",
+            stats.work_sections as i64,
+        ),
+    ] {
+        out += &format!(
+            "# HELP {} {}\n# TYPE {} {}\n{} {}\n",
+            name, help, name, kind, name, value
+        );
+    }
+    Ok(out)
+}
+
+fn status() -> Result<serde_json::Value, FixMeLaterError> {
+    let pomo = current_pomo()?;
+    let state = pomo.state(Utc::now());
+    Ok(state_json(&pomo, &state, FormatOptions::default()))
+}
+
+/// Runs the command named by `path` with the options in the body and responds with the new status
+fn command(request: &mut Request, path: &str) -> Result<serde_json::Value, FixMeLaterError> {
+    let body = read_body(request)?;
+    let force = body["force"].as_bool() == Some(true);
+    match path {
+        "/start" => {
+            start(start_options(&body)?)?;
+        }
+        "/pause" => pause_cmd()?,
+        "/unpause" => unpause(None)?,
+        "/skip" => skip(force)?,
+        _ => stop(force)?,
+    }
+    status()
+}
+
+/// The request body is optional, an empty body is treated like `{}`
+fn read_body(request: &mut Request) -> Result<serde_json::Value, FixMeLaterError> {
+    let mut body = String::new();
+    request.as_reader().read_to_string(&mut body)?;
+    if body.trim().is_empty() {
+        return Ok(serde_json::json!({}));
+    }
+    serde_json::from_str(&body)
+        .map_err(|e| FixMeLaterError::K(ErrKind::InvalidInput, format!("Invalid body: {}", e)))
+}
+
+/// The options of `POST /start`, only the pomo, the task and the tags can be set
+fn start_options(body: &serde_json::Value) -> Result<StartOptions<'_>, FixMeLaterError> {
+    let pom = body["pomo"].as_str();
+    if let Some(pom) = pom.filter(|p| p.starts_with('-')) {
+        return Err(FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            format!("Invalid pomo '{}'", pom),
+        ));
+    }
+    Ok(StartOptions {
+        pom,
+        task: body["task"].as_str(),
+        tags: body["tags"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|tag| tag.as_str())
+            .collect(),
+        ..Default::default()
+    })
+}
+
+fn http_status(kind: ErrKind) -> u16 {
+    match kind {
+        ErrKind::InvalidInput => 400,
+        ErrKind::NoActivePomo => 404,
+        ErrKind::InvalidState | ErrKind::StrictMode => 409,
+        _ => 500,
+    }
+}
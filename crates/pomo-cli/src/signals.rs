@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static TOGGLE_PAUSE: AtomicBool = AtomicBool::new(false);
+static SKIP: AtomicBool = AtomicBool::new(false);
+/// Write end of the pipe that wakes the thread calling `on_signal`, -1 before `listen`
+#[cfg(unix)]
+static WAKE_FD: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(-1);
+
+/// What a signal asks `pomo watch` or `pomo daemon` to do
+pub enum SignalAction {
+    /// `SIGUSR1`, pause or unpause the pomo
+    TogglePause,
+    /// `SIGUSR2`, end the current section
+    Skip,
+}
+
+/// Handles `SIGUSR1` and `SIGUSR2` from now on, instead of being terminated by them, and calls
+/// `on_signal` from another thread after each of them
+#[cfg(unix)]
+pub fn listen(on_signal: impl Fn() + Send + 'static) {
+    extern "C" fn handle(signal: libc::c_int) {
+        match signal {
+            libc::SIGUSR1 => TOGGLE_PAUSE.store(true, Ordering::SeqCst),
+            libc::SIGUSR2 => SKIP.store(true, Ordering::SeqCst),
+            _ => (),
+        }
+        // writing to a pipe is one of the few things a signal handler may do
+        let fd = WAKE_FD.load(Ordering::SeqCst);
+        if fd >= 0 {
+            unsafe { libc::write(fd, [0u8].as_ptr().cast(), 1) };
+        }
+    }
+    let mut fds = [-1; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } == 0 {
+        WAKE_FD.store(fds[1], Ordering::SeqCst);
+        std::thread::spawn(move || {
+            let mut buf = [0u8; 16];
+            loop {
+                match unsafe { libc::read(fds[0], buf.as_mut_ptr().cast(), buf.len()) } {
+                    n if n > 0 => on_signal(),
+                    n if n < 0
+                        && std::io::Error::last_os_error().kind()
+                            == std::io::ErrorKind::Interrupted => {}
+                    _ => break,
+                }
+            }
+        });
+    }
+    let handler = handle as extern "C" fn(libc::c_int) as libc::sighandler_t;
+    unsafe {
+        libc::signal(libc::SIGUSR1, handler);
+        libc::signal(libc::SIGUSR2, handler);
+    }
+}
+
+#[cfg(not(unix))]
+pub fn listen(_on_signal: impl Fn() + Send + 'static) {}
+
+/// The actions of the signals received since the last call
+pub fn received() -> Vec<SignalAction> {
+    let mut actions = vec![];
+    if TOGGLE_PAUSE.swap(false, Ordering::SeqCst) {
+        actions.push(SignalAction::TogglePause);
+    }
+    if SKIP.swap(false, Ordering::SeqCst) {
+        actions.push(SignalAction::Skip);
+    }
+    actions
+}
@@ -0,0 +1,83 @@
+use crate::FixMeLaterError;
+use std::fs::File;
+use std::io::{ErrorKind, Seek, SeekFrom, Write};
+
+/// A target `pomo watch` writes the status line to
+pub enum Sink {
+    /// A regular file, overwritten on every update so it always contains the current line
+    File(File),
+    /// A named pipe, every update is written as a new line for whoever is reading right now.
+    /// The pipe is kept open while a reader is connected, so it does not see an end of file.
+    Fifo(String, Option<File>),
+}
+
+impl Sink {
+    pub fn open(path: &str) -> Result<Sink, FixMeLaterError> {
+        if is_fifo(path) {
+            return Ok(Sink::Fifo(path.to_string(), None));
+        }
+        match File::create(path) {
+            Ok(f) => Ok(Sink::File(f)),
+            Err(e) => Err(FixMeLaterError::S(format!(
+                "Could not open {}: {}",
+                path, e
+            ))),
+        }
+    }
+
+    pub fn write(&mut self, line: &str) -> Result<(), FixMeLaterError> {
+        match self {
+            Sink::File(file) => {
+                file.set_len(0)?;
+                file.seek(SeekFrom::Start(0))?;
+                file.write_all(line.as_bytes())?;
+            }
+            Sink::Fifo(path, pipe) => {
+                // readers come and go, an update without a reader or with a full pipe is dropped
+                if pipe.is_none() {
+                    *pipe = open_fifo(path);
+                }
+                if let Some(p) = pipe {
+                    match p.write_all(format!("{}\n", line).as_bytes()) {
+                        Err(e) if e.kind() == ErrorKind::BrokenPipe => *pipe = None,
+                        _ => (),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether this is a named pipe nobody reads yet, a new reader should see a line right away
+    pub fn waits_for_reader(&self) -> bool {
+        matches!(self, Sink::Fifo(_, None))
+    }
+}
+
+#[cfg(unix)]
+fn is_fifo(path: &str) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|m| m.file_type().is_fifo())
+}
+
+#[cfg(not(unix))]
+fn is_fifo(_path: &str) -> bool {
+    false
+}
+
+/// Opens the pipe without blocking, fails if there is no reader
+#[cfg(unix)]
+fn open_fifo(path: &str) -> Option<File> {
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::OpenOptionsExt;
+    OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_NONBLOCK)
+        .open(path)
+        .ok()
+}
+
+#[cfg(not(unix))]
+fn open_fifo(_path: &str) -> Option<File> {
+    None
+}
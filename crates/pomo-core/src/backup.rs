@@ -0,0 +1,215 @@
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use tracing::debug;
+
+use crate::config::config_file;
+use crate::storage::state_dir;
+use crate::util::{hostname, ErrKind, FixMeLaterError};
+
+/// Name of the file in the archive that marks it as a backup of pomo
+const MANIFEST: &str = "pomo-backup.json";
+/// Version of the archive layout, backups of newer versions are refused
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    version: u32,
+    #[serde(with = "ts_seconds")]
+    created: DateTime<Utc>,
+    hostname: String,
+}
+
+/// What a restore replaced
+pub struct Restored {
+    pub files: usize,
+    /// where the files that were replaced by the restore are kept
+    pub previous: Vec<PathBuf>,
+}
+
+/// The folders that are backed up, with their name in the archive
+fn folders() -> Vec<(&'static str, PathBuf)> {
+    let config = Path::new(&config_file())
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let state = PathBuf::from(shellexpand::tilde(&state_dir()).to_string());
+    vec![("config", config), ("state", state)]
+}
+
+/// `dir` with `.suffix` appended, next to it so it can be renamed into place
+fn sibling(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}", suffix));
+    dir.with_file_name(name)
+}
+
+/// The files in `dir` relative to it, without the lock files of running watchers
+fn files(dir: &Path) -> Result<Vec<PathBuf>, FixMeLaterError> {
+    let mut files = vec![];
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(relative) = dirs.pop() {
+        let Ok(entries) = fs::read_dir(dir.join(&relative)) else {
+            continue;
+        };
+        for entry in entries {
+            let entry = entry?;
+            let path = relative.join(entry.file_name());
+            let kind = entry.file_type()?;
+            if kind.is_dir() {
+                dirs.push(path);
+            } else if kind.is_file() && path.extension().is_none_or(|e| e != "notifier") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Writes the config folder and the state folder with the current pomo and the history to a
+/// gzipped tar archive at `path`. The archive only appears at `path` once it is complete.
+pub fn backup(path: &Path) -> Result<usize, FixMeLaterError> {
+    let tmp = sibling(path, "tmp");
+    let mut archive =
+        tar::Builder::new(GzEncoder::new(File::create(&tmp)?, Compression::default()));
+    let manifest = serde_json::to_vec_pretty(&Manifest {
+        version: VERSION,
+        created: Utc::now(),
+        hostname: hostname(),
+    })?;
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(Utc::now().timestamp().max(0) as u64);
+    header.set_cksum();
+    archive.append_data(&mut header, MANIFEST, manifest.as_slice())?;
+    let mut count = 0;
+    for (name, dir) in folders() {
+        for file in files(&dir)? {
+            debug!("Adding {} to the backup", dir.join(&file).display());
+            archive.append_path_with_name(dir.join(&file), Path::new(name).join(&file))?;
+            count += 1;
+        }
+    }
+    archive.into_inner()?.finish()?;
+    fs::rename(&tmp, path)?;
+    Ok(count)
+}
+
+/// Replaces the config folder and the state folder with the ones in the backup at `path`. The
+/// archive is unpacked next to them first, so a broken archive leaves everything as it was. The
+/// replaced folders are kept with the suffix `.before-restore`.
+pub fn restore(path: &Path) -> Result<Restored, FixMeLaterError> {
+    let file = File::open(path).map_err(|e| {
+        FixMeLaterError::K(
+            ErrKind::Io,
+            format!("Can't read the backup {}: {}", path.display(), e),
+        )
+    })?;
+    let folders = folders();
+    let staging: Vec<PathBuf> = folders
+        .iter()
+        .map(|(_, dir)| sibling(dir, "restore"))
+        .collect();
+    for dir in &staging {
+        if dir.exists() {
+            fs::remove_dir_all(dir)?;
+        }
+        fs::create_dir_all(dir)?;
+    }
+    let files = match unpack(path, file, &folders, &staging) {
+        Ok(files) => files,
+        Err(e) => {
+            for dir in &staging {
+                let _ = fs::remove_dir_all(dir);
+            }
+            return Err(e);
+        }
+    };
+    let mut previous = vec![];
+    for ((_, dir), staged) in folders.iter().zip(&staging) {
+        let old = sibling(dir, "before-restore");
+        if old.exists() {
+            fs::remove_dir_all(&old)?;
+        }
+        if dir.exists() {
+            fs::rename(dir, &old)?;
+            previous.push(old);
+        }
+        debug!("Restoring {}", dir.display());
+        fs::rename(staged, dir)?;
+    }
+    Ok(Restored { files, previous })
+}
+
+fn unpack(
+    path: &Path,
+    file: File,
+    folders: &[(&str, PathBuf)],
+    staging: &[PathBuf],
+) -> Result<usize, FixMeLaterError> {
+    let invalid = |reason: &str| {
+        FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            format!("{} is not a backup of pomo: {}", path.display(), reason),
+        )
+    };
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+    let mut manifest = None;
+    let mut count = 0;
+    for entry in archive.entries().map_err(|e| invalid(&e.to_string()))? {
+        let mut entry = entry.map_err(|e| invalid(&e.to_string()))?;
+        let name = entry.path()?.into_owned();
+        if name == Path::new(MANIFEST) {
+            let mut content = String::new();
+            entry.read_to_string(&mut content)?;
+            manifest = Some(
+                serde_json::from_str::<Manifest>(&content).map_err(|e| invalid(&e.to_string()))?,
+            );
+            continue;
+        }
+        let mut components = name.components();
+        let folder = match components.next() {
+            Some(Component::Normal(first)) => folders.iter().position(|(n, _)| first == *n),
+            _ => None,
+        };
+        let relative = components.as_path();
+        let Some(folder) = folder.filter(|_| {
+            relative
+                .components()
+                .all(|c| matches!(c, Component::Normal(_)))
+        }) else {
+            return Err(invalid(&format!("unexpected file {}", name.display())));
+        };
+        let target = staging[folder].join(relative);
+        if let Some(dir) = target.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        entry.unpack(&target)?;
+        count += 1;
+    }
+    match manifest {
+        Some(m) if m.version > VERSION => Err(FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            format!(
+                "{} was made by a newer version of pomo, update pomo to restore it",
+                path.display()
+            ),
+        )),
+        Some(m) => {
+            debug!(
+                "Restored {} files backed up on {} at {}",
+                count, m.hostname, m.created
+            );
+            Ok(count)
+        }
+        None => Err(invalid("it has no pomo-backup.json")),
+    }
+}
@@ -0,0 +1,891 @@
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDateTime, NaiveTime, Utc, Weekday};
+use directories::ProjectDirs;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::io::ErrorKind;
+use std::iter::successors;
+use std::path::{Path, PathBuf};
+
+use crate::format::Icons;
+use crate::pomo::{
+    LongBreak, MaxPause, MaxPauseAction, PauseMode, PomodoroDefaults, PomodoroState, UntilMode,
+};
+use crate::util::{ErrKind, FixMeLaterError};
+
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct Config {
+    /// named pomodoro definitions, started with `pomo start @name`
+    pub presets: BTreeMap<String, Preset>,
+    /// when a pomodoro started during an active one begins
+    pub start_when: StartWhen,
+    /// what `--until` changes so the pomodoro ends in time
+    pub until_mode: UntilMode,
+    /// what a time of the day means in `--until` and `--at` once it passed today
+    pub past_time: PastTime,
+    /// start every pomodoro in strict mode
+    pub strict: bool,
+    /// keep work sections running past their end until they are skipped
+    pub overtime: bool,
+    /// wait for `pomo next` at the end of every section
+    pub manual: bool,
+    /// keep a separate current pomo per login session (`$XDG_SESSION_ID`)
+    pub per_session: bool,
+    /// ring the terminal bell in `pomo watch` when a section changes
+    pub bell: bool,
+    /// number of work sections to finish every day
+    pub daily_goal: Option<u32>,
+    pub report: ReportConfig,
+    /// commands that start or switch music, e.g. `work = "mpc play"`
+    pub music: StateCommands,
+    pub idle: IdleConfig,
+    pub suspend: SuspendConfig,
+    pub eye_care: EyeCareConfig,
+    pub dnd: DndConfig,
+    pub lock: LockConfig,
+    pub dim: DimConfig,
+    pub block: BlockConfig,
+    pub pause: PauseConfig,
+    /// what `pomo start` uses for the parts left out of the pomo definition
+    pub defaults: DefaultsConfig,
+    pub sync: SyncConfig,
+    pub history: HistoryConfig,
+    pub mqtt: MqttConfig,
+    pub slack: SlackConfig,
+    pub discord: DiscordConfig,
+    pub google_calendar: GoogleCalendarConfig,
+    pub caldav: CaldavConfig,
+    pub webhook: WebhookConfig,
+    pub team: TeamConfig,
+    pub serve: ServeConfig,
+    /// pomos watch or daemon start on their own, e.g. every weekday at 9:00
+    pub schedule: Vec<ScheduleEntry>,
+    pub notification: NotificationConfig,
+    /// show the states as names, emoji (`icons`) or Nerd Font glyphs (`nerdfont`)
+    pub style: OutputStyle,
+    /// glyphs that replace the ones of the `icons` and `nerdfont` styles
+    pub icons: IconsConfig,
+    /// language of the output, e.g. `de`, taken from `$LANG` if not set
+    pub language: Option<String>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputStyle {
+    #[default]
+    Text,
+    Icons,
+    NerdFont,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct IconsConfig {
+    pub warmup: Option<String>,
+    pub work: Option<String>,
+    #[serde(rename = "break")]
+    pub break_: Option<String>,
+    pub paused: Option<String>,
+    pub done: Option<String>,
+    pub not_started: Option<String>,
+}
+
+/// Notifications sent by `watch` and `daemon` when a section starts, the defaults are used for
+/// every state that doesn't set its own values
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct NotificationConfig {
+    #[serde(flatten)]
+    pub default: NotificationContent,
+    pub warmup: NotificationContent,
+    pub work: NotificationContent,
+    #[serde(rename = "break")]
+    pub break_: NotificationContent,
+    pub done: NotificationContent,
+    /// buttons to start the break, extend or skip the section, `true` unless set
+    pub actions: Option<bool>,
+    /// local times without notifications and bell, e.g. `"22:00-07:00"`
+    pub quiet_hours: Option<String>,
+}
+
+/// Title and body are templates with the placeholders of `pomo watch --format`
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct NotificationContent {
+    pub title: Option<String>,
+    pub body: Option<String>,
+    pub urgency: Option<Urgency>,
+    /// name or path of the icon
+    pub icon: Option<String>,
+    /// seconds until the notification closes on its own
+    pub expire: Option<u32>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+impl NotificationConfig {
+    pub fn for_state(&self, state: PomodoroState) -> NotificationContent {
+        let specific = match state {
+            PomodoroState::Warmup => self.warmup.clone(),
+            PomodoroState::Work => self.work.clone(),
+            PomodoroState::Break => self.break_.clone(),
+            PomodoroState::Done => self.done.clone(),
+            PomodoroState::NotStarted => NotificationContent::default(),
+        };
+        let default = self.default.clone();
+        NotificationContent {
+            title: specific.title.or(default.title),
+            body: specific.body.or(default.body),
+            urgency: specific.urgency.or(default.urgency),
+            icon: specific.icon.or(default.icon),
+            expire: specific.expire.or(default.expire),
+        }
+    }
+
+    /// Start and end of the quiet hours
+    pub fn quiet_hours(&self) -> Result<Option<(NaiveTime, NaiveTime)>, FixMeLaterError> {
+        let Some(hours) = &self.quiet_hours else {
+            return Ok(None);
+        };
+        let invalid = || {
+            FixMeLaterError::K(
+                ErrKind::Config,
+                format!(
+                    "notification.quiet_hours has to be like \"22:00-07:00\", not \"{}\"",
+                    hours
+                ),
+            )
+        };
+        let (from, to) = hours.split_once('-').ok_or_else(invalid)?;
+        let time = |t: &str| NaiveTime::parse_from_str(t.trim(), "%H:%M").map_err(|_| invalid());
+        Ok(Some((time(from)?, time(to)?)))
+    }
+}
+
+/// Whether `t` is between `from` and `to`, which may span midnight
+pub fn is_quiet(hours: Option<(NaiveTime, NaiveTime)>, t: NaiveTime) -> bool {
+    match hours {
+        Some((from, to)) if from <= to => from <= t && t < to,
+        Some((from, to)) => t >= from || t < to,
+        None => false,
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// store the current pomo in one journal per device, so the state directory can be synced
+    pub enabled: bool,
+    /// name of this device, the hostname if not set
+    pub device: Option<String>,
+}
+
+/// How long the history keeps what, applied by `pomo history prune` and once a day by watch and
+/// daemon
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// entries older than this many months are deleted
+    pub keep_months: Option<u32>,
+    /// sessions older than this many months keep only what the stats need
+    pub compact_after_months: Option<u32>,
+    /// where the history is kept, `sqlite` needs pomo built with the `sqlite` feature
+    pub backend: HistoryBackend,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryBackend {
+    /// one json object per line in `history`
+    #[default]
+    File,
+    /// a SQLite database `history.sqlite3` with indexes for the queries
+    Sqlite,
+}
+
+impl HistoryConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.keep_months.is_some() || self.compact_after_months.is_some()
+    }
+    /// The times before which entries are deleted and sessions are compacted
+    pub fn cutoffs(&self, now: DateTime<Utc>) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+        let before =
+            |months: Option<u32>| months.and_then(|m| now.checked_sub_months(Months::new(m)));
+        (before(self.keep_months), before(self.compact_after_months))
+    }
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct MqttConfig {
+    /// `host:port` of the MQTT broker, publishing is enabled when this is set
+    pub broker: Option<String>,
+    /// prefix of all topics, `pomo` by default
+    pub prefix: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// publish Home Assistant discovery configs so the pomo shows up as a device
+    pub discovery: bool,
+    /// prefix of the discovery topics, `homeassistant` by default
+    pub discovery_prefix: Option<String>,
+}
+
+impl MqttConfig {
+    pub fn prefix(&self) -> &str {
+        self.prefix.as_deref().unwrap_or("pomo")
+    }
+    pub fn discovery_prefix(&self) -> &str {
+        self.discovery_prefix.as_deref().unwrap_or("homeassistant")
+    }
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct SlackConfig {
+    /// user token with the `users.profile:write` and `dnd:write` scopes, the status is only set
+    /// when this is set
+    pub token: Option<String>,
+    /// status emoji during work sections, `:tomato:` by default
+    pub emoji: Option<String>,
+    /// status text during work sections, `{end}` is replaced with the end of the section
+    pub text: Option<String>,
+    /// pause Slack notifications during work sections, on by default
+    pub dnd: Option<bool>,
+}
+
+impl SlackConfig {
+    pub fn emoji(&self) -> &str {
+        self.emoji.as_deref().unwrap_or(":tomato:")
+    }
+    pub fn dnd(&self) -> bool {
+        self.dnd.unwrap_or(true)
+    }
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct DiscordConfig {
+    /// id of the Discord application shown as the activity, Rich Presence is enabled when this
+    /// is set
+    pub client_id: Option<String>,
+    /// template of the first line, see `pomo watch --template`
+    pub details: Option<String>,
+    /// template of the second line
+    pub state: Option<String>,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct GoogleCalendarConfig {
+    /// create an event for every pomo, needs `pomo google-login` first
+    pub enabled: bool,
+    /// id of the calendar, `primary` by default
+    pub calendar: Option<String>,
+    /// title of the events, `Focus block` by default
+    pub summary: Option<String>,
+}
+
+impl GoogleCalendarConfig {
+    pub fn calendar(&self) -> &str {
+        self.calendar.as_deref().unwrap_or("primary")
+    }
+    pub fn summary(&self) -> &str {
+        self.summary.as_deref().unwrap_or("Focus block")
+    }
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct CaldavConfig {
+    /// URL of the calendar collection, sessions are uploaded when this is set
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// also upload the planned block when a pomo starts
+    pub planned: bool,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// URLs that receive a JSON POST on every change of the pomo
+    pub urls: Vec<String>,
+    /// attempts after a failed request, 5 by default
+    pub retries: Option<u32>,
+}
+
+impl WebhookConfig {
+    pub fn retries(&self) -> u32 {
+        self.retries.unwrap_or(5)
+    }
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ServeConfig {
+    /// secret that requests which change the pomo or the team have to send as
+    /// `Authorization: Bearer <token>`, they are refused if it is not set
+    pub token: Option<String>,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct TeamConfig {
+    /// how the team sees you, the user name by default
+    pub name: Option<String>,
+    /// folder shared with the team, e.g. on a network drive, every member writes a file to it
+    pub dir: Option<String>,
+    /// address of the `pomo serve` of a team member that collects the statuses instead of a folder
+    pub url: Option<String>,
+    /// the `token` of that `pomo serve`
+    pub token: Option<String>,
+    /// show the task of the pomo to the team
+    pub share_task: bool,
+}
+
+impl TeamConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.dir.is_some() || self.url.is_some()
+    }
+    pub fn name(&self) -> String {
+        self.name
+            .clone()
+            .or_else(|| env::var("USER").ok())
+            .or_else(|| env::var("USERNAME").ok())
+            .unwrap_or_else(|| "me".to_string())
+    }
+    pub fn dir(&self) -> Option<PathBuf> {
+        self.dir
+            .as_ref()
+            .map(|d| PathBuf::from(shellexpand::tilde(d).to_string()))
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct DefaultsConfig {
+    pub repetitions: Option<u32>,
+    /// minutes of every work section
+    pub work: Option<i64>,
+    /// minutes of every break
+    #[serde(rename = "break")]
+    pub break_: Option<i64>,
+    /// minutes of the long break, no long breaks if not set
+    pub long_break: Option<i64>,
+    /// the break after every this many work sections is a long break, 4 by default
+    pub long_break_every: Option<u32>,
+    /// minutes to get ready before the first work section, no warmup if not set
+    pub warmup: Option<i64>,
+    /// values that replace the ones above on a day of the week, e.g. `[defaults.friday]`
+    #[serde(flatten)]
+    pub weekdays: HashMap<Weekday, DefaultsConfig>,
+}
+
+impl DefaultsConfig {
+    /// The defaults with the values of `day` taking precedence
+    pub fn for_day(&self, day: Weekday) -> DefaultsConfig {
+        let Some(d) = self.weekdays.get(&day) else {
+            return DefaultsConfig {
+                weekdays: HashMap::new(),
+                ..*self
+            };
+        };
+        DefaultsConfig {
+            repetitions: d.repetitions.or(self.repetitions),
+            work: d.work.or(self.work),
+            break_: d.break_.or(self.break_),
+            long_break: d.long_break.or(self.long_break),
+            long_break_every: d.long_break_every.or(self.long_break_every),
+            warmup: d.warmup.or(self.warmup),
+            weekdays: HashMap::new(),
+        }
+    }
+    pub fn pomodoro_defaults(&self) -> Result<PomodoroDefaults, FixMeLaterError> {
+        let minutes = |name: &str, value: Option<i64>| match value {
+            Some(m) if m <= 0 => Err(FixMeLaterError::K(
+                ErrKind::Config,
+                format!("defaults.{} has to be at least 1 minute", name),
+            )),
+            value => Ok(value.map(Duration::minutes)),
+        };
+        let builtin = PomodoroDefaults::default();
+        let long_break = minutes("long_break", self.long_break)?.map(|duration| LongBreak {
+            duration,
+            every: self.long_break_every.unwrap_or(4),
+        });
+        if self.repetitions == Some(0) || self.long_break_every == Some(0) {
+            return Err(FixMeLaterError::K(
+                ErrKind::Config,
+                "defaults.repetitions and defaults.long_break_every have to be at least 1"
+                    .to_string(),
+            ));
+        }
+        if self.warmup.is_some_and(|m| m >= 24 * 60) {
+            return Err(FixMeLaterError::K(
+                ErrKind::Config,
+                "defaults.warmup has to be shorter than 24 hours".to_string(),
+            ));
+        }
+        Ok(PomodoroDefaults {
+            repetitions: self.repetitions.unwrap_or(builtin.repetitions),
+            work_time: minutes("work", self.work)?.unwrap_or(builtin.work_time),
+            break_time: minutes("break", self.break_)?.unwrap_or(builtin.break_time),
+            long_break,
+            warmup: minutes("warmup", self.warmup)?,
+        })
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct PauseConfig {
+    /// pauses longer than this many minutes are long pauses
+    pub long_after: Option<i64>,
+    /// what `unpause` does after a long pause
+    pub long_action: LongPauseAction,
+    /// pauses longer than this many minutes end on their own
+    pub max: Option<i64>,
+    /// how a pause ends when it reaches `max`
+    pub max_action: MaxPauseAction,
+    /// how pauses are recorded when the pomo is unpaused
+    pub mode: PauseMode,
+}
+
+impl PauseConfig {
+    pub fn max_pause(&self) -> Option<MaxPause> {
+        self.max.filter(|m| *m > 0).map(|m| MaxPause {
+            duration: Duration::minutes(m),
+            action: self.max_action,
+        })
+    }
+}
+
+#[derive(Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum LongPauseAction {
+    /// ask whether the interrupted section should start over
+    #[default]
+    Ask,
+    /// start the interrupted section over
+    Restart,
+    /// continue the interrupted section where it was paused
+    Split,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct DndConfig {
+    /// enable do-not-disturb during work sections
+    pub enabled: bool,
+    pub backend: DndBackend,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum DndBackend {
+    /// use the first notification daemon that is running
+    #[default]
+    Auto,
+    Dunst,
+    Mako,
+    Gnome,
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct LockConfig {
+    /// lock the screen at the start of warmup sections
+    pub warmup: bool,
+    /// lock the screen at the start of work sections
+    pub work: bool,
+    /// lock the screen at the start of breaks
+    #[serde(rename = "break")]
+    pub break_: bool,
+    /// lock the screen when the pomo is done
+    pub done: bool,
+    /// seconds between the start of the section and locking the screen, 30 if not set
+    pub delay: Option<i64>,
+    /// command that locks the screen instead of `loginctl lock-session` or `xdg-screensaver lock`
+    pub command: Option<String>,
+}
+
+impl LockConfig {
+    pub fn for_state(&self, state: PomodoroState) -> bool {
+        match state {
+            PomodoroState::Warmup => self.warmup,
+            PomodoroState::Work => self.work,
+            PomodoroState::Break => self.break_,
+            PomodoroState::Done => self.done,
+            PomodoroState::NotStarted => false,
+        }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.warmup || self.work || self.break_ || self.done
+    }
+    pub fn delay(&self) -> Duration {
+        Duration::seconds(self.delay.unwrap_or(30).max(0))
+    }
+}
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct BlockConfig {
+    /// domains that are blocked during work sections, `www.` is blocked as well
+    pub sites: Vec<String>,
+    /// hosts file the blocklist is written to, `/etc/hosts` if not set
+    pub hosts_file: Option<String>,
+    /// command that blocks the sites instead, e.g. through a local DNS server, the sites are in
+    /// `$POMO_BLOCKLIST`
+    pub command: Option<String>,
+    /// command that removes the block of `command`
+    pub unblock_command: Option<String>,
+}
+
+impl BlockConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.sites.is_empty()
+    }
+    pub fn hosts_file(&self) -> &str {
+        self.hosts_file.as_deref().unwrap_or("/etc/hosts")
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(default)]
+pub struct DimConfig {
+    /// dim and warm the screen during breaks
+    pub enabled: bool,
+    pub backend: DimBackend,
+    /// color temperature in Kelvin during breaks, 3500 if not set
+    pub temperature: Option<u32>,
+    /// brightness between 0.1 and 1.0 during breaks, 0.7 if not set
+    pub brightness: Option<f64>,
+}
+
+impl DimConfig {
+    pub fn temperature(&self) -> u32 {
+        self.temperature.unwrap_or(3500).clamp(1000, 25000)
+    }
+    pub fn brightness(&self) -> f64 {
+        self.brightness.unwrap_or(0.7).clamp(0.1, 1.0)
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DimBackend {
+    /// gammastep if it is installed, redshift otherwise
+    #[default]
+    Auto,
+    Gammastep,
+    Redshift,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct IdleConfig {
+    /// pause work sections after this many minutes without user activity
+    pub threshold: Option<i64>,
+    /// what happens when the user is back after an automatic pause
+    pub resume: IdleResume,
+}
+
+#[derive(Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdleResume {
+    /// unpause automatically
+    #[default]
+    Auto,
+    /// send a notification asking to unpause
+    Ask,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct SuspendConfig {
+    /// what happens to the pomo when the computer was asleep during a section
+    pub action: SuspendAction,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct EyeCareConfig {
+    /// remind every 20 minutes of a work section to look 20 feet away for 20 seconds
+    pub enabled: bool,
+}
+
+#[derive(Deserialize, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum SuspendAction {
+    /// keep the schedule, sections end while the computer is asleep
+    #[default]
+    Ignore,
+    /// pause the pomo when the computer went to sleep, it stays paused until `pomo unpause`
+    Pause,
+    /// move the rest of the pomo by the time the computer was asleep
+    Shift,
+    /// stop the pomo when the computer went to sleep
+    Abandon,
+}
+
+/// Shell commands that are run when the pomo enters a state
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct StateCommands {
+    pub warmup: Option<String>,
+    pub work: Option<String>,
+    #[serde(rename = "break")]
+    pub break_: Option<String>,
+    pub done: Option<String>,
+}
+
+impl StateCommands {
+    pub fn for_state(&self, state: PomodoroState) -> Option<&str> {
+        match state {
+            PomodoroState::Warmup => self.warmup.as_deref(),
+            PomodoroState::Work => self.work.as_deref(),
+            PomodoroState::Break => self.break_.as_deref(),
+            PomodoroState::Done => self.done.as_deref(),
+            PomodoroState::NotStarted => None,
+        }
+    }
+    pub fn is_enabled(&self) -> bool {
+        self.warmup.is_some() || self.work.is_some() || self.break_.is_some() || self.done.is_some()
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct ReportConfig {
+    /// address the weekly report is sent to
+    pub email_to: Option<String>,
+    /// sendmail compatible command that reads the email from stdin, `sendmail -t` by default
+    pub email_command: Option<String>,
+    /// send the report every sunday evening while watch or daemon is running
+    pub schedule: bool,
+    /// show the summary of the day as a notification when a pomo is done after this local time,
+    /// e.g. `"17:00"`
+    pub summary_after: Option<String>,
+}
+
+/// Starts a pomo at a time of the day on some days of the week
+#[derive(Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct ScheduleEntry {
+    /// e.g. `mon-fri`, `sat,sun` or `daily`
+    pub days: String,
+    /// local time of the day, e.g. `"09:00"`
+    pub at: String,
+    /// definition or `@preset` to start, the defaults if not set
+    pub pomo: Option<String>,
+    pub task: Option<String>,
+}
+
+/// A schedule that was missed by more than this many minutes, e.g. while the computer was asleep,
+/// is not started late anymore
+const SCHEDULE_LATE: i64 = 15;
+
+impl ScheduleEntry {
+    fn error(&self, message: &str) -> FixMeLaterError {
+        FixMeLaterError::K(
+            ErrKind::Config,
+            format!("Invalid schedule '{} {}': {}", self.days, self.at, message),
+        )
+    }
+
+    /// The days of the week the pomo starts on
+    pub fn weekdays(&self) -> Result<Vec<Weekday>, FixMeLaterError> {
+        let day = |d: &str| {
+            d.trim()
+                .parse::<Weekday>()
+                .map_err(|_| self.error(&format!("unknown day '{}'", d.trim())))
+        };
+        let mut days = vec![];
+        for part in self.days.split(',') {
+            match part.trim() {
+                "daily" | "*" => {
+                    return Ok(successors(Some(Weekday::Mon), |d| Some(d.succ()))
+                        .take(7)
+                        .collect())
+                }
+                part => match part.split_once('-') {
+                    // ranges can wrap around the end of the week, e.g. `fri-mon`
+                    Some((from, to)) => {
+                        let (from, to) = (day(from)?, day(to)?);
+                        let mut d = from;
+                        days.push(d);
+                        while d != to {
+                            d = d.succ();
+                            days.push(d);
+                        }
+                    }
+                    None => days.push(day(part)?),
+                },
+            }
+        }
+        Ok(days)
+    }
+
+    pub fn time(&self) -> Result<NaiveTime, FixMeLaterError> {
+        NaiveTime::parse_from_str(self.at.trim(), "%H:%M")
+            .map_err(|_| self.error("the time has to be like 09:00"))
+    }
+
+    /// Whether the pomo should be started at the local time `now`, which is up to a few minutes
+    /// after the scheduled time
+    pub fn is_due(&self, now: NaiveDateTime) -> Result<bool, FixMeLaterError> {
+        let at = now.date().and_time(self.time()?);
+        Ok(self.weekdays()?.contains(&now.weekday())
+            && now >= at
+            && now < at + Duration::minutes(SCHEDULE_LATE))
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "lowercase")]
+pub enum StartWhen {
+    /// replace the active pomodoro immediately
+    #[default]
+    Now,
+    /// let the current section of the active pomodoro finish first
+    Boundary,
+    /// let all sections of the active pomodoro finish first
+    After,
+}
+
+#[derive(Deserialize, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PastTime {
+    /// the time tomorrow
+    #[default]
+    Tomorrow,
+    /// refuse it
+    Error,
+}
+
+impl StartWhen {
+    pub fn from_arg(s: &str) -> StartWhen {
+        match s {
+            "boundary" => StartWhen::Boundary,
+            "after" => StartWhen::After,
+            _ => StartWhen::Now,
+        }
+    }
+}
+
+/// A pomodoro definition like `2p50b10`, or a table with the definition and the micro-breaks:
+/// `{ pomo = "2p90b15", micro_breaks = "10m/30s" }`
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+pub enum Preset {
+    Definition(String),
+    Table {
+        pomo: String,
+        micro_breaks: Option<String>,
+    },
+}
+
+impl Preset {
+    pub fn definition(&self) -> &str {
+        match self {
+            Preset::Definition(pomo) | Preset::Table { pomo, .. } => pomo,
+        }
+    }
+    pub fn micro_breaks(&self) -> Option<&str> {
+        match self {
+            Preset::Definition(_) => None,
+            Preset::Table { micro_breaks, .. } => micro_breaks.as_deref(),
+        }
+    }
+}
+
+impl Display for Preset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.definition())?;
+        if let Some(micro_breaks) = self.micro_breaks() {
+            write!(f, ", micro-breaks {}", micro_breaks)?;
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Resolves a `@name` preset, other strings are returned as a preset with just the definition
+    pub fn resolve_preset(&self, pom: &str) -> Result<Preset, FixMeLaterError> {
+        match pom.strip_prefix('@') {
+            Some(name) => match self.presets.get(name) {
+                Some(preset) => Ok(preset.clone()),
+                None => Err(FixMeLaterError::K(
+                    ErrKind::InvalidInput,
+                    format!(
+                        "Unknown preset '{}', run 'pomo presets' to list the available presets",
+                        name
+                    ),
+                )),
+            },
+            None => Ok(Preset::Definition(pom.to_string())),
+        }
+    }
+
+    /// The glyphs of the configured style, none with the `text` style
+    pub fn icons(&self) -> Option<Icons> {
+        let base = match self.style {
+            OutputStyle::Text => return None,
+            OutputStyle::Icons => Icons::emoji(),
+            OutputStyle::NerdFont => Icons::nerd_font(),
+        };
+        let icons = &self.icons;
+        Some(Icons {
+            warmup: icons.warmup.clone().unwrap_or(base.warmup),
+            work: icons.work.clone().unwrap_or(base.work),
+            break_: icons.break_.clone().unwrap_or(base.break_),
+            paused: icons.paused.clone().unwrap_or(base.paused),
+            done: icons.done.clone().unwrap_or(base.done),
+            not_started: icons.not_started.clone().unwrap_or(base.not_started),
+        })
+    }
+}
+
+/// `~/.config/pomo/config.toml` on Linux, the application config folder on macOS and Windows
+pub fn config_file() -> String {
+    match ProjectDirs::from("", "", "pomo") {
+        Some(dirs) => dirs
+            .config_dir()
+            .join("config.toml")
+            .to_string_lossy()
+            .to_string(),
+        None => shellexpand::tilde("~/.config/pomo/config.toml").to_string(),
+    }
+}
+
+/// Path of a file next to the config file, e.g. for credentials
+pub fn config_path(name: &str) -> PathBuf {
+    Path::new(&config_file()).with_file_name(name)
+}
+
+pub fn load_config() -> Result<Config, FixMeLaterError> {
+    let file = config_file();
+    match fs::read_to_string(&file) {
+        Ok(content) => toml::from_str(&content).map_err(|e| {
+            FixMeLaterError::K(
+                ErrKind::Config,
+                format!("Error in config file {}: {}", file, e),
+            )
+        }),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(FixMeLaterError::K(
+            ErrKind::Config,
+            format!("Can't read config file {}: {}", file, e),
+        )),
+    }
+}
@@ -0,0 +1,164 @@
+use chrono::{DateTime, Local, Utc};
+
+use crate::i18n::tr;
+use crate::pomo::{
+    format_duration, format_duration_human, CurrentPomoState, Pomodoro, PomodoroState,
+};
+
+/// Options for rendering the state of a pomo
+#[derive(Default, Clone, Copy)]
+pub struct FormatOptions<'a> {
+    /// hide the task name and tags, e.g. while streaming or screen sharing
+    pub privacy: bool,
+    /// durations like `23 min left` instead of `00:23:00`
+    pub human: bool,
+    /// show the states as glyphs instead of their names
+    pub icons: Option<&'a Icons>,
+}
+
+/// Glyphs for the states of a pomo
+#[derive(Clone)]
+pub struct Icons {
+    pub warmup: String,
+    pub work: String,
+    pub break_: String,
+    pub paused: String,
+    pub done: String,
+    pub not_started: String,
+}
+
+impl Icons {
+    pub fn emoji() -> Icons {
+        Icons {
+            warmup: "📝".to_string(),
+            work: "🍅".to_string(),
+            break_: "☕".to_string(),
+            paused: "⏸".to_string(),
+            done: "✔".to_string(),
+            not_started: "⏳".to_string(),
+        }
+    }
+
+    /// Font Awesome glyphs of the Nerd Fonts, they need a patched font in the terminal
+    pub fn nerd_font() -> Icons {
+        Icons {
+            warmup: "\u{f040}".to_string(),
+            work: "\u{f109}".to_string(),
+            break_: "\u{f0f4}".to_string(),
+            paused: "\u{f04c}".to_string(),
+            done: "\u{f00c}".to_string(),
+            not_started: "\u{f017}".to_string(),
+        }
+    }
+
+    pub fn for_state(&self, state: PomodoroState) -> &str {
+        match state {
+            PomodoroState::Warmup => &self.warmup,
+            PomodoroState::Work => &self.work,
+            PomodoroState::Break => &self.break_,
+            PomodoroState::Done => &self.done,
+            PomodoroState::NotStarted => &self.not_started,
+        }
+    }
+}
+
+/// Renders a template like `{state} {minutes}m`. Available placeholders:
+/// `{state}`, `{remaining}` (HH:MM:SS), `{human}` (e.g. `1 h 05 min`),
+/// `{minutes}` (remaining whole minutes), `{next}`, `{icon}` (glyph of the state),
+/// `{completed}`, `{total}`, `{paused}`, `{task}` and `{tags}`
+pub fn render_template(
+    template: &str,
+    pomo: &Pomodoro,
+    state: &CurrentPomoState,
+    options: FormatOptions,
+) -> String {
+    let (task, tags) = if options.privacy {
+        (String::new(), String::new())
+    } else {
+        (
+            pomo.task.clone().unwrap_or_default(),
+            pomo.tags
+                .iter()
+                .map(|t| format!("#{}", t))
+                .collect::<Vec<_>>()
+                .join(" "),
+        )
+    };
+    let emoji = Icons::emoji();
+    let icons = options.icons.unwrap_or(&emoji);
+    let icon = if state.pause {
+        &icons.paused
+    } else {
+        icons.for_state(state.current_state)
+    };
+    template
+        .replace("{state}", state.current_state.label())
+        .replace("{icon}", icon)
+        .replace("{remaining}", &format_duration(state.duration))
+        .replace("{human}", &format_duration_human(state.duration))
+        .replace("{minutes}", &state.duration.num_minutes().to_string())
+        .replace("{next}", state.next_state.label())
+        .replace("{completed}", &state.completed_repetitions.to_string())
+        .replace("{total}", &state.total_repetitions.to_string())
+        .replace("{paused}", if state.pause { tr("paused") } else { "" })
+        .replace("{task}", &task)
+        .replace("{tags}", &tags)
+}
+
+/// Renders the state of a pomo followed by its task and tags
+pub fn status_line(pomo: &Pomodoro, state: &CurrentPomoState, options: FormatOptions) -> String {
+    let mut line = state.render(options.human, options.icons);
+    if options.privacy {
+        return line;
+    }
+    if let Some(task) = &pomo.task {
+        line += &format!(" {}", task);
+    }
+    for tag in pomo.tags.iter() {
+        line += &format!(" #{}", tag);
+    }
+    line
+}
+
+/// The state and the time left in a few characters, e.g. `W 17:32`, for shell prompts
+pub fn short_status(state: &CurrentPomoState, options: FormatOptions) -> String {
+    state.render_short(options.icons)
+}
+
+/// The state of a pomo as JSON, used by the HTTP API and other machine readable outputs
+pub fn state_json(
+    pomo: &Pomodoro,
+    state: &CurrentPomoState,
+    options: FormatOptions,
+) -> serde_json::Value {
+    let (task, tags) = if options.privacy {
+        (None, vec![])
+    } else {
+        (pomo.task.clone(), pomo.tags.clone())
+    };
+    serde_json::json!({
+        "active": pomo.active,
+        "state": state.current_state.to_string(),
+        "next": state.next_state.to_string(),
+        "remaining_seconds": state.duration.num_seconds(),
+        "completed": state.completed_repetitions,
+        "total": state.total_repetitions,
+        "paused": state.pause,
+        "task": task,
+        "tags": tags,
+    })
+}
+
+/// Renders a time as `HH:MM` in the local timezone, with the date if it isn't today. With `utc`
+/// the full UTC time is shown instead.
+pub fn format_time(time: DateTime<Utc>, utc: bool) -> String {
+    if utc {
+        return time.to_string();
+    }
+    let time = time.with_timezone(&Local);
+    if time.date_naive() == Local::now().date_naive() {
+        time.format("%H:%M").to_string()
+    } else {
+        time.format("%Y-%m-%d %H:%M").to_string()
+    }
+}
@@ -0,0 +1,150 @@
+use chrono::serde::ts_seconds;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::pomo::Pomodoro;
+
+#[derive(Serialize, Deserialize)]
+pub struct HistoryEntry {
+    #[serde(with = "ts_seconds")]
+    pub time: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: HistoryEvent,
+}
+
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HistoryEvent {
+    /// a command that would interrupt a work section of a strict pomo was refused
+    StrictRefused { command: String },
+    /// a work section of a strict pomo was interrupted with `--force`
+    StrictOverride { command: String },
+    /// a work section ran past its planned end before it was ended
+    Overtime {
+        #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+        duration: Duration,
+    },
+    /// a pomo that was stopped or replaced by a new one
+    Session {
+        #[serde(with = "ts_seconds")]
+        end: DateTime<Utc>,
+        pomodoro: Box<Pomodoro>,
+    },
+    /// the pomo was unpaused after a long pause
+    LongPause {
+        #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+        duration: Duration,
+        /// whether the interrupted section was started over
+        restarted: bool,
+    },
+    /// the pomo was paused longer than the maximum pause and got unpaused or stopped
+    PauseExpired {
+        #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+        duration: Duration,
+        stopped: bool,
+    },
+    /// the computer was asleep during a section, `abandoned` if the pomo was stopped for it
+    Suspend {
+        #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+        duration: Duration,
+        abandoned: bool,
+    },
+    /// the weekly report was sent by email
+    ReportSent,
+    /// the daily goal of work sections was reached
+    GoalReached { work_sections: u32, streak: u32 },
+    /// the number of work sections a task is expected to take, the latest estimate of a task counts
+    Estimate { task: String, work_sections: u32 },
+    /// something interrupted a work section, the section of the pomo started at `pomo_start`
+    Interruption {
+        kind: InterruptionKind,
+        reason: Option<String>,
+        #[serde(with = "ts_seconds")]
+        pomo_start: DateTime<Utc>,
+        section: usize,
+    },
+}
+
+/// Whether an interruption came from yourself, e.g. a sudden idea, or from someone else
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum InterruptionKind {
+    Internal,
+    External,
+}
+
+impl InterruptionKind {
+    pub fn from_arg(s: &str) -> InterruptionKind {
+        match s {
+            "external" => InterruptionKind::External,
+            _ => InterruptionKind::Internal,
+        }
+    }
+}
+
+impl HistoryEntry {
+    pub fn new(time: DateTime<Utc>, event: HistoryEvent) -> HistoryEntry {
+        HistoryEntry { time, event }
+    }
+
+    /// When the entry happened, the start of the pomo for sessions
+    pub fn start(&self) -> DateTime<Utc> {
+        match &self.event {
+            HistoryEvent::Session { pomodoro, .. } => pomodoro.start,
+            _ => self.time,
+        }
+    }
+
+    /// The task the entry belongs to, if any
+    pub fn task(&self) -> Option<&str> {
+        match &self.event {
+            HistoryEvent::Session { pomodoro, .. } => pomodoro.task.as_deref(),
+            HistoryEvent::Estimate { task, .. } => Some(task),
+            _ => None,
+        }
+    }
+}
+
+/// A pomo together with the time it ended
+#[derive(Clone, Copy)]
+pub struct Session<'a> {
+    pub end: DateTime<Utc>,
+    pub pomodoro: &'a Pomodoro,
+}
+
+/// All sessions in the history followed by the current pomo, if it has started
+pub fn sessions<'a>(
+    entries: &'a [HistoryEntry],
+    current: Option<&'a Pomodoro>,
+    now: DateTime<Utc>,
+) -> Vec<Session<'a>> {
+    let mut sessions: Vec<Session> = entries
+        .iter()
+        .filter_map(|e| match &e.event {
+            HistoryEvent::Session { end, pomodoro } => Some(Session {
+                end: *end,
+                pomodoro,
+            }),
+            _ => None,
+        })
+        .collect();
+    if let Some(pomo) = current.filter(|p| p.active && p.start < now) {
+        sessions.push(Session {
+            end: now.min(pomo.end().max(pomo.start)),
+            pomodoro: pomo,
+        });
+    }
+    sessions
+}
+
+/// The sessions with at least one of `tags`, all sessions if `tags` is empty
+pub fn with_tags<'a>(sessions: Vec<Session<'a>>, tags: &[String]) -> Vec<Session<'a>> {
+    if tags.is_empty() {
+        return sessions;
+    }
+    sessions
+        .into_iter()
+        .filter(|s| s.pomodoro.tags.iter().any(|t| tags.contains(t)))
+        .collect()
+}
@@ -0,0 +1,85 @@
+use std::env;
+use std::sync::OnceLock;
+
+use crate::config::load_config;
+
+/// Language of the texts shown to people, machine readable output always stays English
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Locale {
+    En,
+    De,
+}
+
+static LOCALE: OnceLock<Locale> = OnceLock::new();
+
+impl Locale {
+    /// Parses a language code like `de` or a locale like `de_CH.UTF-8`, none if unsupported
+    pub fn parse(s: &str) -> Option<Locale> {
+        let language = s.split(['_', '-', '.', '@']).next()?.to_ascii_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "de" => Some(Locale::De),
+            _ => None,
+        }
+    }
+}
+
+/// The locale set with `language` in the config file, otherwise the one of `$LC_ALL`,
+/// `$LC_MESSAGES` or `$LANG`, English if none of them is supported
+pub fn locale() -> Locale {
+    *LOCALE.get_or_init(|| {
+        let configured = load_config().ok().and_then(|c| c.language);
+        configured
+            .into_iter()
+            .chain(
+                ["LC_ALL", "LC_MESSAGES", "LANG"]
+                    .iter()
+                    .filter_map(|v| env::var(v).ok())
+                    .filter(|v| !v.is_empty()),
+            )
+            .find_map(|l| Locale::parse(&l))
+            .unwrap_or(Locale::En)
+    })
+}
+
+/// Translates an English text to the current locale, texts without a translation stay English
+pub fn tr(text: &'static str) -> &'static str {
+    match locale() {
+        Locale::En => text,
+        Locale::De => match text {
+            "warmup" => "Aufwärmen",
+            "work" => "Arbeit",
+            "break" => "Pause",
+            "done" => "fertig",
+            "not started" => "nicht gestartet",
+            "paused" => "pausiert",
+            "waiting" => "wartet",
+            "left" => "übrig",
+            "overtime" => "Überzeit",
+            "Pomodoro State {state}!" => "Pomodoro: {state}!",
+            "Start break now" => "Pause jetzt starten",
+            "Skip" => "Überspringen",
+            "Clear your desk and write down your goal. {task}" => {
+                "Räum den Schreibtisch auf und schreib dein Ziel auf. {task}"
+            }
+            "Daily goal reached!" => "Tagesziel erreicht!",
+            "{sections} work sections, {days} days in a row" => {
+                "{sections} Arbeitsabschnitte, {days} Tage in Folge"
+            }
+            "Micro-break! Look away and stretch for {length}." => {
+                "Kurze Pause! Schau in die Ferne und streck dich {length} lang."
+            }
+            "Look at something 20 feet away for 20 seconds." => {
+                "Schau 20 Sekunden lang auf etwas, das sechs Meter entfernt ist."
+            }
+            "Focusing until {end}" => "Fokussiert bis {end}",
+            "Welcome back! Run 'pomo unpause' to continue working." => {
+                "Willkommen zurück! Mit 'pomo unpause' geht es weiter."
+            }
+            "Paused while the computer was asleep, run 'pomo unpause' to continue." => {
+                "Pausiert, während der Computer geschlafen hat. Mit 'pomo unpause' geht es weiter."
+            }
+            _ => text,
+        },
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+
+use crate::pomo::Pomodoro;
+
+/// Unique id of the event of a pomo, the same for the planned block and the finished session
+pub fn event_uid(pomo: &Pomodoro) -> String {
+    format!("pomo-{}@pomo", pomo.start.timestamp())
+}
+
+/// An iCalendar `VEVENT` for a pomo that ends at `end`, titled with its task
+pub fn pomo_event(pomo: &Pomodoro, end: DateTime<Utc>) -> String {
+    let summary = match &pomo.task {
+        Some(task) => format!("Focus: {}", task),
+        None => "Focus block".to_string(),
+    };
+    let mut description = pomo
+        .tags
+        .iter()
+        .map(|t| format!("#{}", t))
+        .collect::<Vec<_>>()
+        .join(" ");
+    for note in pomo.notes.iter() {
+        description += &format!("\n- {}", note.text);
+    }
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", event_uid(pomo)),
+        format!("DTSTAMP:{}", timestamp(Utc::now())),
+        format!("DTSTART:{}", timestamp(pomo.start)),
+        format!("DTEND:{}", timestamp(end.max(pomo.start))),
+        format!("SUMMARY:{}", escape(&summary)),
+    ];
+    if !description.trim().is_empty() {
+        lines.push(format!("DESCRIPTION:{}", escape(description.trim())));
+    }
+    if !pomo.tags.is_empty() {
+        let tags: Vec<String> = pomo.tags.iter().map(|t| escape(t)).collect();
+        lines.push(format!("CATEGORIES:{}", tags.join(",")));
+    }
+    lines.push("END:VEVENT".to_string());
+    lines.iter().map(|l| fold(l)).collect()
+}
+
+/// Wraps events into an iCalendar file
+pub fn calendar(events: &[String]) -> String {
+    let mut calendar = "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//pomo//pomo//EN\r\n".to_string();
+    for event in events {
+        calendar += event;
+    }
+    calendar + "END:VCALENDAR\r\n"
+}
+
+fn timestamp(t: DateTime<Utc>) -> String {
+    t.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Ends the line with CRLF and folds it so no line is longer than 75 bytes
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded + "\r\n"
+}
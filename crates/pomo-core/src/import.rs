@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, Local, NaiveDateTime, TimeZone, Utc};
+
+use crate::history::{sessions, HistoryEntry, HistoryEvent};
+use crate::pomo::{Pomodoro, PomodoroState};
+use crate::util::{parse_duration, ErrKind, FixMeLaterError};
+
+/// The timer the imported data was exported from
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ImportFormat {
+    Csv,
+    Flow,
+    Pomotroid,
+}
+
+impl ImportFormat {
+    pub fn from_arg(s: &str) -> ImportFormat {
+        match s {
+            "flow" => ImportFormat::Flow,
+            "pomotroid" => ImportFormat::Pomotroid,
+            _ => ImportFormat::Csv,
+        }
+    }
+
+    /// Names of the columns for the start, end, duration, task, tags and type of a row,
+    /// the names of the timer first, compared case insensitive
+    fn columns(self) -> [Vec<&'static str>; 6] {
+        let mut columns = [
+            vec!["start", "started", "start time", "start date"],
+            vec!["end", "ended", "end time", "end date"],
+            vec!["duration", "minutes"],
+            vec!["task", "title", "name"],
+            vec!["tags", "tag", "category"],
+            vec!["type", "kind"],
+        ];
+        let own: [&[&str]; 6] = match self {
+            ImportFormat::Csv => [&[], &[], &[], &[], &[], &[]],
+            ImportFormat::Flow => [
+                &["start date"],
+                &["end date"],
+                &[],
+                &["title"],
+                &[],
+                &["session type"],
+            ],
+            ImportFormat::Pomotroid => [
+                &["started"],
+                &["ended"],
+                &[],
+                &[],
+                &[],
+                &["round", "round type"],
+            ],
+        };
+        for (c, own) in columns.iter_mut().zip(own) {
+            c.splice(0..0, own.iter().copied());
+        }
+        columns
+    }
+}
+
+/// What happened to the rows of an import
+#[derive(Default, Debug)]
+pub struct ImportSummary {
+    pub imported: usize,
+    /// rows that overlap a session in the history or an earlier row
+    pub duplicates: usize,
+    /// break rows, only work is kept in the history
+    pub breaks: usize,
+}
+
+/// Converts exported data, a CSV file with a header or a JSON list of objects, into history
+/// sessions. Rows overlapping a session that is already in `history` are left out.
+pub fn import_sessions(
+    data: &str,
+    format: ImportFormat,
+    history: &[HistoryEntry],
+) -> Result<(Vec<HistoryEntry>, ImportSummary), FixMeLaterError> {
+    let rows = if data.trim_start().starts_with('[') {
+        json_rows(data)?
+    } else {
+        csv_rows(data)?
+    };
+    let [start_col, end_col, duration_col, task_col, tags_col, type_col] = format.columns();
+    let mut taken: Vec<(DateTime<Utc>, DateTime<Utc>)> = sessions(history, None, Utc::now())
+        .iter()
+        .map(|s| (s.pomodoro.start, s.end))
+        .collect();
+    let mut summary = ImportSummary::default();
+    let mut entries = vec![];
+    for (i, row) in rows.iter().enumerate() {
+        let invalid = |message: String| {
+            FixMeLaterError::K(ErrKind::InvalidInput, format!("Row {}: {}", i + 1, message))
+        };
+        if field(row, &type_col).is_some_and(|t| {
+            let t = t.to_lowercase();
+            t.contains("break") || t.contains("rest")
+        }) {
+            summary.breaks += 1;
+            continue;
+        }
+        let start = field(row, &start_col)
+            .ok_or_else(|| invalid("no start time".to_string()))
+            .and_then(|s| {
+                parse_timestamp(s).ok_or_else(|| invalid(format!("invalid time '{}'", s)))
+            })?;
+        let end = match (field(row, &end_col), field(row, &duration_col)) {
+            (Some(e), _) => {
+                parse_timestamp(e).ok_or_else(|| invalid(format!("invalid time '{}'", e)))?
+            }
+            (None, Some(d)) => parse_import_duration(d)
+                .ok()
+                .and_then(|d| start.checked_add_signed(d))
+                .ok_or_else(|| invalid(format!("invalid duration '{}'", d)))?,
+            (None, None) => return Err(invalid("no end time or duration".to_string())),
+        };
+        if end <= start {
+            return Err(invalid("the end is not after the start".to_string()));
+        }
+        // longer sessions are a wrong unit or a timer that was never stopped
+        if end - start >= Duration::hours(24) {
+            return Err(invalid("the session is longer than 24 hours".to_string()));
+        }
+        if taken.iter().any(|(s, e)| start < *e && end > *s) {
+            summary.duplicates += 1;
+            continue;
+        }
+        taken.push((start, end));
+
+        let mut pomodoro = Pomodoro::flow(start);
+        let section = &mut pomodoro.sections[0];
+        section.duration = end - start;
+        section.state = PomodoroState::Work;
+        section.open = false;
+        section.acknowledged = true;
+        pomodoro.active = false;
+        pomodoro.task = field(row, &task_col).map(str::to_string);
+        pomodoro.tags = field(row, &tags_col)
+            .map(|t| {
+                t.split([';', ','])
+                    .map(str::trim)
+                    .filter(|t| !t.is_empty())
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default();
+        entries.push(HistoryEntry::new(
+            end,
+            HistoryEvent::Session {
+                end,
+                pomodoro: Box::new(pomodoro),
+            },
+        ));
+        summary.imported += 1;
+    }
+    entries.sort_by_key(|e| e.time);
+    Ok((entries, summary))
+}
+
+/// The first non-empty field of a row named like one of `names`
+fn field<'a>(row: &'a HashMap<String, String>, names: &[&str]) -> Option<&'a str> {
+    names
+        .iter()
+        .filter_map(|n| row.get(*n))
+        .map(|v| v.trim())
+        .find(|v| !v.is_empty())
+}
+
+fn csv_rows(data: &str) -> Result<Vec<HashMap<String, String>>, FixMeLaterError> {
+    let csv_error =
+        |e: csv::Error| FixMeLaterError::K(ErrKind::InvalidInput, format!("Invalid CSV: {}", e));
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(data.as_bytes());
+    let header: Vec<String> = reader
+        .headers()
+        .map_err(csv_error)?
+        .iter()
+        .map(|h| h.to_lowercase())
+        .collect();
+    reader
+        .records()
+        .map(|r| {
+            let record = r.map_err(csv_error)?;
+            Ok(header
+                .iter()
+                .cloned()
+                .zip(record.iter().map(str::to_string))
+                .collect())
+        })
+        .collect()
+}
+
+fn json_rows(data: &str) -> Result<Vec<HashMap<String, String>>, FixMeLaterError> {
+    let objects: Vec<serde_json::Map<String, serde_json::Value>> = serde_json::from_str(data)
+        .map_err(|e| FixMeLaterError::K(ErrKind::InvalidInput, format!("Invalid JSON: {}", e)))?;
+    Ok(objects
+        .into_iter()
+        .map(|o| {
+            o.into_iter()
+                .map(|(k, v)| {
+                    let v = match v {
+                        serde_json::Value::String(s) => s,
+                        serde_json::Value::Array(a) => a
+                            .iter()
+                            .filter_map(|t| t.as_str())
+                            .collect::<Vec<_>>()
+                            .join(";"),
+                        serde_json::Value::Null => String::new(),
+                        v => v.to_string(),
+                    };
+                    (k.to_lowercase(), v)
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Unix timestamps in seconds or milliseconds, RFC 3339 or a local date and time
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    if let Ok(n) = s.parse::<i64>() {
+        return if n > 100_000_000_000 {
+            DateTime::from_timestamp_millis(n)
+        } else {
+            DateTime::from_timestamp(n, 0)
+        };
+    }
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Some(t.with_timezone(&Utc));
+    }
+    [
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M",
+        "%d.%m.%Y %H:%M:%S",
+        "%d.%m.%Y %H:%M",
+        "%m/%d/%Y %H:%M:%S",
+        "%m/%d/%Y %H:%M",
+    ]
+    .iter()
+    .find_map(|f| NaiveDateTime::parse_from_str(s, f).ok())
+    .and_then(|t| Local.from_local_datetime(&t).earliest())
+    .map(|t| t.with_timezone(&Utc))
+}
+
+/// Plain numbers are minutes, otherwise `hh:mm:ss`, `mm:ss` or a duration like `25m`
+fn parse_import_duration(s: &str) -> Result<Duration, FixMeLaterError> {
+    let invalid = || FixMeLaterError::K(ErrKind::InvalidInput, format!("Invalid duration '{}'", s));
+    if let Ok(minutes) = s.parse::<f64>() {
+        // `as` saturates, too many minutes end up out of range
+        return Duration::try_seconds((minutes * 60.0).round() as i64).ok_or_else(invalid);
+    }
+    if s.contains(':') {
+        let parts: Option<Vec<i64>> = s.split(':').map(|p| p.parse().ok()).collect();
+        if let Some(parts) = parts.filter(|p| p.len() <= 3) {
+            return parts
+                .iter()
+                .try_fold(0i64, |acc, p| acc.checked_mul(60)?.checked_add(*p))
+                .and_then(Duration::try_seconds)
+                .ok_or_else(invalid);
+        }
+    }
+    parse_duration(s)
+}
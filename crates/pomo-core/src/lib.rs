@@ -0,0 +1,16 @@
+pub mod backup;
+pub mod config;
+pub mod format;
+pub mod history;
+pub mod i18n;
+pub mod ical;
+pub mod import;
+pub mod pomo;
+pub mod query;
+pub mod report;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+pub mod stats;
+pub mod storage;
+pub mod team;
+pub mod util;
@@ -0,0 +1,1930 @@
+use chrono::serde::{ts_milliseconds, ts_milliseconds_option, ts_seconds, ts_seconds_option};
+use chrono::{DateTime, Duration, Local, SubsecRound, Utc};
+use core::fmt::Display;
+use serde::{Deserialize, Serialize};
+
+use crate::format::Icons;
+use crate::i18n::tr;
+use crate::util::{boot_clock, parse_duration, ErrKind, FixMeLaterError};
+
+/// What a pomodoro is generated from, it is kept in the pomodoro so it can be restarted
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PomodoroSetting {
+    #[serde(with = "ts_seconds")]
+    start: DateTime<Utc>,
+    repetitions: u32,
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    work_time: Duration,
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    break_time: Duration,
+    long_break: Option<LongBreak>,
+    /// a break before the first work section
+    #[serde_as(as = "Option<serde_with::DurationSeconds<i64>>")]
+    leading_break: Option<Duration>,
+    /// a warmup section right before the first work section
+    #[serde_as(as = "Option<serde_with::DurationSeconds<i64>>")]
+    warmup: Option<Duration>,
+}
+
+/// A longer break after every `every` work sections
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct LongBreak {
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub duration: Duration,
+    pub every: u32,
+}
+
+/// What a pomo definition that leaves out a part of it uses instead
+#[derive(Clone, Copy)]
+pub struct PomodoroDefaults {
+    pub repetitions: u32,
+    pub work_time: Duration,
+    pub break_time: Duration,
+    pub long_break: Option<LongBreak>,
+    pub warmup: Option<Duration>,
+}
+
+impl Default for PomodoroDefaults {
+    fn default() -> Self {
+        PomodoroDefaults {
+            repetitions: 4,
+            work_time: Duration::minutes(40),
+            break_time: Duration::minutes(10),
+            long_break: None,
+            warmup: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Pomodoro {
+    pub sections: Vec<PomodoroSection>,
+    #[serde(with = "ts_seconds")]
+    pub start: DateTime<Utc>,
+    pub active: bool,
+    #[serde(with = "ts_seconds_option")]
+    pub pause_started: Option<DateTime<Utc>>,
+    /// in strict mode interrupting a work section needs `--force`
+    #[serde(default)]
+    pub strict: bool,
+    /// in overtime mode work sections continue after their planned end until they are skipped
+    #[serde(default)]
+    pub overtime: bool,
+    /// in manual mode sections wait after their planned end until `pomo next` is run
+    #[serde(default)]
+    pub manual: bool,
+    /// what is worked on in this pomo
+    #[serde(default)]
+    pub task: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// checklist of what should be achieved in this pomo
+    #[serde(default)]
+    pub goals: Vec<Goal>,
+    /// what was noted down during this pomo, e.g. what got done
+    #[serde(default)]
+    pub notes: Vec<Note>,
+    /// pauses longer than this end on their own
+    #[serde(default)]
+    pub max_pause: Option<MaxPause>,
+    #[serde(default)]
+    pub pause_mode: PauseMode,
+    /// incremented on every write, the highest revision wins when synced states are merged
+    #[serde(default)]
+    pub revision: u64,
+    /// time of the last write, decides between equal revisions
+    #[serde(default, with = "ts_milliseconds_option")]
+    pub modified: Option<DateTime<Utc>>,
+    /// state transitions that happened so far in this pomodoro
+    #[serde(default)]
+    pub transitions: Vec<Transition>,
+    /// the wall clock together with the time since boot when the pomo was last written
+    #[serde(default)]
+    pub clock: Option<ClockAnchor>,
+    /// reminders to look away and stretch during work sections
+    #[serde(default)]
+    pub micro_break: Option<MicroBreak>,
+    /// 20-20-20 reminders to look into the distance during work sections
+    #[serde(default)]
+    pub eye_care: bool,
+    /// the blocklist of the config is not applied during this pomodoro
+    #[serde(default)]
+    pub unblocked: bool,
+    /// what the sections were generated from, `None` for flow pomos and older state files
+    #[serde(default)]
+    pub setting: Option<PomodoroSetting>,
+}
+
+/// A reminder every `every` of a work section to take a break of `length` without leaving it
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct MicroBreak {
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub every: Duration,
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub length: Duration,
+}
+
+/// Every 20 minutes look at something 20 feet away for 20 seconds
+pub const EYE_CARE: MicroBreak = MicroBreak {
+    every: Duration::minutes(20),
+    length: Duration::seconds(20),
+};
+
+impl MicroBreak {
+    /// Parses `<every>[/<length>]` like `10m/30s`, the length is 30 seconds if left out
+    pub fn parse(s: &str) -> Result<MicroBreak, FixMeLaterError> {
+        let (every, length) = s.split_once('/').unwrap_or((s, "30s"));
+        let micro_break = MicroBreak {
+            every: parse_duration(every)?,
+            length: parse_duration(length)?,
+        };
+        if micro_break.every <= micro_break.length || micro_break.length <= Duration::zero() {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidInput,
+                format!(
+                    "Invalid micro-breaks '{}', use e.g. 10m/30s for 30 seconds every 10 minutes",
+                    s
+                ),
+            ));
+        }
+        Ok(micro_break)
+    }
+}
+
+/// A reading of the wall clock and of the clock since boot at the same moment. The clock since
+/// boot is not changed by NTP or by setting the time, comparing both readings later shows how
+/// far the wall clock was moved in between.
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub struct ClockAnchor {
+    #[serde(with = "ts_milliseconds")]
+    pub wall: DateTime<Utc>,
+    pub boot_id: String,
+    /// milliseconds since boot, including the time the computer was asleep
+    pub since_boot: i64,
+}
+
+/// Changes of the wall clock up to this many seconds are not corrected
+const CLOCK_TOLERANCE: i64 = 2;
+
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct MaxPause {
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub duration: Duration,
+    pub action: MaxPauseAction,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum MaxPauseAction {
+    /// continue the pomo as if it was unpaused when the maximum was reached
+    #[default]
+    Unpause,
+    /// stop the pomo when the maximum was reached
+    Stop,
+}
+
+/// How a pause is recorded when the pomo is unpaused
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PauseMode {
+    /// the interrupted section is split and the pause becomes a break between the two parts
+    #[default]
+    Splice,
+    /// the interrupted section stays whole and its end and all later sections move by the pause
+    Shift,
+}
+
+impl PauseMode {
+    pub fn from_arg(s: &str) -> PauseMode {
+        match s {
+            "shift" => PauseMode::Shift,
+            _ => PauseMode::Splice,
+        }
+    }
+}
+
+/// What changes so that a pomo ends at the time given with `--until`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UntilMode {
+    /// choose the repetitions that change the work time the least, then adjust the work time
+    #[default]
+    Reps,
+    /// keep the repetitions and breaks, only the work time changes
+    Work,
+    /// keep the repetitions and work time, only the breaks change
+    Break,
+    /// scale work time and breaks by the same factor
+    Proportional,
+}
+
+impl UntilMode {
+    pub fn from_arg(s: &str) -> UntilMode {
+        match s {
+            "work" => UntilMode::Work,
+            "break" => UntilMode::Break,
+            "proportional" => UntilMode::Proportional,
+            _ => UntilMode::Reps,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Goal {
+    pub text: String,
+    pub done: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Note {
+    #[serde(with = "ts_seconds")]
+    pub time: DateTime<Utc>,
+    pub text: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct Transition {
+    #[serde(with = "ts_seconds")]
+    pub time: DateTime<Utc>,
+    pub from: PomodoroState,
+    pub to: PomodoroState,
+    pub trigger: TransitionTrigger,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub enum TransitionTrigger {
+    Start,
+    Timer,
+    Skip,
+    Pause,
+    Unpause,
+    Stop,
+}
+
+#[serde_with::serde_as]
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct PomodoroSection {
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    pub duration: Duration,
+    pub state: PomodoroState,
+    /// open-ended sections have no fixed end, they run until closed with `start_break`
+    #[serde(default)]
+    pub open: bool,
+    /// set when the section was ended by the user instead of the timer
+    #[serde(default)]
+    pub acknowledged: bool,
+    /// time the section was paused with shift semantics, it moves the end of the section
+    /// without counting as time spent in the section
+    #[serde_as(as = "serde_with::DurationSeconds<i64>")]
+    #[serde(default)]
+    pub paused: Duration,
+}
+
+impl PomodoroSection {
+    /// Time from the start to the end of the section, including the time it was paused
+    pub fn span(&self) -> Duration {
+        self.duration + self.paused
+    }
+}
+
+pub struct CurrentPomoState {
+    pub current_state: PomodoroState,
+    pub next_state: PomodoroState,
+    pub duration: Duration,
+    pub completed_repetitions: u32,
+    pub total_repetitions: u32,
+    pub pause: bool,
+    /// if set, `duration` is the time elapsed in an open-ended section instead of the time left
+    pub open_ended: bool,
+    /// if set, `duration` is the time worked past the planned end of the section
+    pub overtime: bool,
+    /// the section is over and waits for `pomo next`, `duration` is the time waited
+    pub waiting: bool,
+}
+
+#[derive(PartialEq, Serialize, Deserialize, Copy, Clone)]
+pub enum PomodoroState {
+    NotStarted,
+    /// a short section before the first work section to get ready, it doesn't count as work
+    Warmup,
+    Work,
+    Break,
+    Done,
+}
+
+/// The first multiple of `every` on the local clock at or after `t`
+fn next_mark(t: DateTime<Utc>, every: Duration) -> DateTime<Utc> {
+    let every = every.num_seconds().max(1);
+    let offset = i64::from(t.with_timezone(&Local).offset().local_minus_utc());
+    let secs = t.timestamp() + i64::from(t.timestamp_subsec_nanos() > 0);
+    let wait = (every - (secs + offset).rem_euclid(every)) % every;
+    DateTime::from_timestamp(secs + wait, 0).unwrap_or(t)
+}
+
+/// `d` rounded to the nearest whole second
+fn round_seconds(d: Duration) -> Duration {
+    Duration::seconds((d.num_milliseconds() + 500).div_euclid(1000))
+}
+
+/// Durations are stored in whole seconds, round down so a section ended at `now` stays ended
+fn whole_seconds(d: Duration) -> Duration {
+    Duration::seconds(d.num_seconds())
+}
+
+pub fn format_duration(d: Duration) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        d.num_hours(),
+        d.num_minutes() % 60,
+        d.num_seconds() % 60
+    )
+}
+
+/// Renders a duration for people, e.g. `45 s`, `23 min` or `1 h 05 min`
+pub fn format_duration_human(d: Duration) -> String {
+    let minutes = d.num_minutes();
+    if minutes == 0 {
+        format!("{} s", d.num_seconds())
+    } else if minutes < 60 {
+        format!("{} min", minutes)
+    } else {
+        format!("{} h {:02} min", d.num_hours(), minutes % 60)
+    }
+}
+
+impl PomodoroState {
+    /// The name of the state in the language of the user, `to_string` is always English
+    pub fn label(&self) -> &'static str {
+        tr(match self {
+            Self::NotStarted => "not started",
+            Self::Warmup => "warmup",
+            Self::Work => "work",
+            Self::Break => "break",
+            Self::Done => "done",
+        })
+    }
+}
+
+impl Display for PomodoroState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::NotStarted => "not started",
+            Self::Warmup => "warmup",
+            Self::Work => "work",
+            Self::Break => "break",
+            Self::Done => "done",
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+/// Errors of the time calculations of a pomodoro
+#[derive(Debug)]
+pub enum TimeError {
+    /// the requested end time is not after the start of the pomodoro
+    EndBeforeStart,
+    /// the requested end time leaves no time for work sections
+    TooShort,
+    /// a duration or repetition count does not fit into its type
+    Overflow,
+    /// the pomo or the warmup would be longer than a pomo definition may be
+    TooLong,
+}
+
+impl Display for TimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::EndBeforeStart => {
+                "the end time has to be after the start time, use a time from now like +2h"
+            }
+            Self::TooShort => "the end time is too close to the start time",
+            Self::Overflow => "the durations are out of range",
+            Self::TooLong => "the pomo and the warmup have to be shorter than 24 hours",
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+impl From<TimeError> for FixMeLaterError {
+    fn from(value: TimeError) -> Self {
+        FixMeLaterError::K(ErrKind::InvalidInput, value.to_string())
+    }
+}
+
+/// Break length in flow mode is the worked time divided by this ratio
+const FLOW_BREAK_RATIO: i32 = 5;
+
+impl Display for TransitionTrigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let str = match self {
+            Self::Start => "start",
+            Self::Timer => "timer",
+            Self::Skip => "skip",
+            Self::Pause => "pause",
+            Self::Unpause => "unpause",
+            Self::Stop => "stop",
+        };
+
+        write!(f, "{}", str)
+    }
+}
+
+/// Start and planned end of a section
+pub type Bounds = (DateTime<Utc>, DateTime<Utc>);
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum CurrentSection {
+    Inactive,
+    BeforeStart,
+    Section(usize),
+    AferEnd,
+}
+
+impl Pomodoro {
+    /// An active pomodoro starting at `start` without sections and with every option off
+    fn new(start: DateTime<Utc>) -> Pomodoro {
+        Pomodoro {
+            sections: vec![],
+            start,
+            active: true,
+            pause_started: None,
+            strict: false,
+            overtime: false,
+            manual: false,
+            task: None,
+            tags: vec![],
+            goals: vec![],
+            notes: vec![],
+            max_pause: None,
+            pause_mode: PauseMode::Splice,
+            revision: 0,
+            modified: None,
+            transitions: vec![],
+            clock: None,
+            micro_break: None,
+            eye_care: false,
+            unblocked: false,
+            setting: None,
+        }
+    }
+    /// A stopped pomodoro without sections, stands in for the current pomo before the first one
+    /// is started
+    pub fn idle(now: DateTime<Utc>) -> Pomodoro {
+        Pomodoro {
+            active: false,
+            ..Pomodoro::new(now)
+        }
+    }
+    /// Creates a flowtime pomodoro with a single open-ended work section
+    pub fn flow(start: DateTime<Utc>) -> Pomodoro {
+        Pomodoro {
+            sections: vec![PomodoroSection {
+                duration: Duration::zero(),
+                state: PomodoroState::Work,
+                open: true,
+                acknowledged: false,
+                paused: Duration::zero(),
+            }],
+            ..Pomodoro::new(start)
+        }
+    }
+    pub fn repetitions(&self) -> u32 {
+        self.sections
+            .iter()
+            .filter(|s| s.state == PomodoroState::Work)
+            .count()
+            .try_into()
+            .unwrap_or(u32::MAX)
+    }
+    /// Whether the section keeps running after its planned end until it gets skipped
+    fn holds(&self, s: &PomodoroSection) -> bool {
+        !s.acknowledged && (self.manual || (self.overtime && s.state == PomodoroState::Work))
+    }
+    /// Time spent in work sections until `until`
+    pub fn work_time(&self, until: DateTime<Utc>) -> Duration {
+        let mut total = Duration::zero();
+        for (s, (start, end)) in self.sections.iter().zip(self.section_bounds()) {
+            if start >= until {
+                break;
+            }
+            let end = if s.open { until } else { end.min(until) };
+            if s.state == PomodoroState::Work {
+                total += (end - start - s.paused).max(Duration::zero());
+            }
+        }
+        total
+    }
+    /// Number of work sections that were finished before `until`
+    pub fn completed_work_sections(&self, until: DateTime<Utc>) -> u32 {
+        let mut count = 0;
+        for (s, (_, end)) in self.sections.iter().zip(self.section_bounds()) {
+            if s.open || end > until {
+                break;
+            }
+            if s.state == PomodoroState::Work {
+                count += 1;
+            }
+        }
+        count
+    }
+    /// Number of sections in `state` that ran until their planned end before `until`
+    pub fn completed_sections(&self, until: DateTime<Utc>, state: PomodoroState) -> u32 {
+        let mut count = 0;
+        for (s, (_, end)) in self.sections.iter().zip(self.section_bounds()) {
+            if s.open || end > until {
+                break;
+            }
+            if s.state == state && !s.acknowledged {
+                count += 1;
+            }
+        }
+        count
+    }
+    pub fn end(&self) -> DateTime<Utc> {
+        self.section_bounds()
+            .last()
+            .map_or(self.start, |(_, end)| end)
+    }
+    /// Start and end of every section in order, each section starts where the one before ended
+    pub fn section_bounds(&self) -> impl Iterator<Item = Bounds> + '_ {
+        self.sections.iter().scan(self.start, |start, s| {
+            let bounds = (*start, *start + s.span());
+            *start = bounds.1;
+            Some(bounds)
+        })
+    }
+    /// Start of section `i`, the end of the pomo if there is no such section
+    fn section_start(&self, i: usize) -> DateTime<Utc> {
+        self.section_bounds()
+            .nth(i)
+            .map_or_else(|| self.end(), |(start, _)| start)
+    }
+    /// Planned end of section `i`, the end of the pomo if there is no such section
+    fn section_end(&self, i: usize) -> DateTime<Utc> {
+        self.section_bounds()
+            .nth(i)
+            .map_or_else(|| self.end(), |(_, end)| end)
+    }
+    pub fn current_section(&self, t: DateTime<Utc>) -> CurrentSection {
+        self.locate(t).0
+    }
+    /// The section running at `t` with its start and planned end. A section runs from its start
+    /// up to but not including its end, at the exact end the next section runs already.
+    fn locate(&self, t: DateTime<Utc>) -> (CurrentSection, Option<Bounds>) {
+        if !self.active {
+            return (CurrentSection::Inactive, None);
+        }
+        let time = self.pause_started.unwrap_or(t);
+        if self.start > time {
+            return (CurrentSection::BeforeStart, None);
+        }
+        for (i, (s, (start, end))) in self.sections.iter().zip(self.section_bounds()).enumerate() {
+            if start <= time && (s.open || self.holds(s) || time < end) {
+                return (CurrentSection::Section(i), Some((start, end)));
+            }
+        }
+        (CurrentSection::AferEnd, None)
+    }
+
+    pub fn state(&self, t: DateTime<Utc>) -> CurrentPomoState {
+        if self.expired_pause_end(t).is_some() {
+            let mut pomo = self.clone();
+            pomo.end_expired_pause(t);
+            return pomo.state(t);
+        }
+        let time = if let Some(pause_started) = self.pause_started {
+            pause_started
+        } else {
+            t
+        };
+        let pause = self.pause_started.is_some();
+        let (section, bounds) = self.locate(t);
+        match section {
+            CurrentSection::Inactive => CurrentPomoState {
+                current_state: PomodoroState::Done,
+                next_state: PomodoroState::Done,
+                duration: Duration::zero(),
+                completed_repetitions: 0,
+                total_repetitions: 0,
+                pause,
+                open_ended: false,
+                overtime: false,
+                waiting: false,
+            },
+            CurrentSection::BeforeStart => CurrentPomoState {
+                current_state: PomodoroState::NotStarted,
+                next_state: self
+                    .sections
+                    .first()
+                    .map_or(PomodoroState::Done, |s| s.state),
+                duration: self.start - time,
+                completed_repetitions: 0,
+                total_repetitions: self.repetitions(),
+                pause,
+                open_ended: false,
+                overtime: false,
+                waiting: false,
+            },
+            CurrentSection::Section(i) => {
+                let current_section = self.sections.get(i).unwrap();
+                let (start_time, section_end) = bounds.unwrap();
+                let next_section = self.sections.get(i + 1);
+                let completed = self
+                    .sections
+                    .iter()
+                    .take(i + 1)
+                    .filter(|s| s.state == PomodoroState::Work)
+                    .count();
+                let over = !current_section.open && time > section_end;
+                let overtime =
+                    over && self.overtime && current_section.state == PomodoroState::Work;
+                let (next_state, duration) = if current_section.open {
+                    (
+                        PomodoroState::Break,
+                        (time - start_time - current_section.paused).max(Duration::zero()),
+                    )
+                } else if over {
+                    (
+                        next_section.map_or(PomodoroState::Done, |sec| sec.state),
+                        time - section_end,
+                    )
+                } else {
+                    (
+                        next_section.map_or(PomodoroState::Done, |sec| sec.state),
+                        section_end - time,
+                    )
+                };
+                CurrentPomoState {
+                    current_state: current_section.state,
+                    next_state,
+                    duration,
+                    completed_repetitions: u32::try_from(completed).unwrap_or(u32::MAX),
+                    total_repetitions: self.repetitions(),
+                    pause,
+                    open_ended: current_section.open,
+                    overtime,
+                    waiting: over && !overtime,
+                }
+            }
+            CurrentSection::AferEnd => CurrentPomoState {
+                current_state: PomodoroState::Done,
+                next_state: PomodoroState::Done,
+                duration: Duration::zero(),
+                completed_repetitions: self.repetitions(),
+                total_repetitions: self.repetitions(),
+                pause,
+                open_ended: false,
+                overtime: false,
+                waiting: false,
+            },
+        }
+    }
+    /// End of the section that is running at `t`, `None` if there is no section with a fixed end
+    pub fn next_boundary(&self, t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.pause_started.is_some() {
+            return None;
+        }
+        match self.locate(t) {
+            (CurrentSection::BeforeStart, _) => Some(self.start),
+            (CurrentSection::Section(i), Some((_, end)))
+                if !self.sections[i].open && !self.holds(&self.sections[i]) =>
+            {
+                Some(end)
+            }
+            _ => None,
+        }
+    }
+    /// The running work section and how many micro-breaks are due in it by `t`, `None` outside of
+    /// work sections, while paused or when the section ends before the last due one is over
+    pub fn micro_breaks_due(&self, t: DateTime<Utc>) -> Option<(usize, i64)> {
+        self.reminders_due(t, self.micro_break?)
+    }
+    /// Like [`Pomodoro::micro_breaks_due`] for the 20-20-20 eye-care reminders
+    pub fn eye_care_due(&self, t: DateTime<Utc>) -> Option<(usize, i64)> {
+        self.reminders_due(t, EYE_CARE).filter(|_| self.eye_care)
+    }
+    fn reminders_due(&self, t: DateTime<Utc>, micro: MicroBreak) -> Option<(usize, i64)> {
+        if self.pause_started.is_some() {
+            return None;
+        }
+        let (CurrentSection::Section(i), Some((start, _))) = self.locate(t) else {
+            return None;
+        };
+        let section = &self.sections[i];
+        if section.state != PomodoroState::Work {
+            return None;
+        }
+        let due = (t - start - section.paused).num_seconds() / micro.every.num_seconds();
+        let last_end = micro.every * i32::try_from(due).ok()? + micro.length;
+        if !section.open && last_end > section.duration {
+            return None;
+        }
+        Some((i, due))
+    }
+    /// Time of the next transition that happens without user interaction and the state it
+    /// leads to, `None` if there is no such transition
+    pub fn next_transition(&self, t: DateTime<Utc>) -> Option<(DateTime<Utc>, PomodoroState)> {
+        let boundary = self.next_boundary(t)?;
+        let next = match self.current_section(t) {
+            CurrentSection::BeforeStart => 0,
+            CurrentSection::Section(i) => i + 1,
+            _ => return None,
+        };
+        let state = self
+            .sections
+            .get(next)
+            .map(|s| s.state)
+            .unwrap_or(PomodoroState::Done);
+        Some((boundary, state))
+    }
+    /// Moves the start so the first work section starts at the next multiple of `every` on the
+    /// local clock, and the sections after it end on the closest multiples. Sections are never
+    /// shortened below `every`.
+    pub fn align(&mut self, every: Duration) {
+        let Some(first_work) = self
+            .sections
+            .iter()
+            .position(|s| s.state == PomodoroState::Work)
+        else {
+            return;
+        };
+        let lead = self
+            .sections
+            .iter()
+            .take(first_work)
+            .map(|s| s.span())
+            .fold(Duration::zero(), |a, v| a + v);
+        let mut boundary = next_mark(self.start + lead, every);
+        self.start = boundary - lead;
+        for s in self.sections.iter_mut().skip(first_work) {
+            if s.open {
+                break;
+            }
+            let planned = boundary + s.span();
+            let closest = next_mark(planned - every / 2, every);
+            let end = closest.max(boundary + every);
+            s.duration = end - boundary - s.paused;
+            boundary = end;
+        }
+    }
+    /// Drops what the stats don't need from a pomo in the history: the notes, goals, setting,
+    /// clock and the transitions other than skips. Returns whether anything was dropped.
+    pub fn compact(&mut self) -> bool {
+        let transitions = self.transitions.len();
+        let dropped = !self.notes.is_empty()
+            || !self.goals.is_empty()
+            || self.setting.is_some()
+            || self.clock.is_some();
+        self.notes.clear();
+        self.goals.clear();
+        self.setting = None;
+        self.clock = None;
+        self.transitions
+            .retain(|t| matches!(t.trigger, TransitionTrigger::Skip));
+        dropped || self.transitions.len() != transitions
+    }
+    /// Cuts this pomodoro off at the start of `next` and continues with the sections of `next`,
+    /// so both form a single timeline.
+    pub fn queue(&mut self, next: Pomodoro) {
+        let mut start = self.start;
+        let mut keep = 0;
+        for s in self.sections.iter_mut() {
+            if start >= next.start {
+                break;
+            }
+            if s.open || start + s.span() > next.start {
+                s.duration = (next.start - start - s.paused).max(Duration::zero());
+                s.open = false;
+            }
+            start += s.span();
+            keep += 1;
+        }
+        self.sections.truncate(keep);
+        if start < next.start {
+            self.sections.push(PomodoroSection {
+                duration: next.start - start,
+                state: PomodoroState::Break,
+                open: false,
+                acknowledged: false,
+                paused: Duration::zero(),
+            });
+        }
+        self.sections.extend(next.sections);
+        self.active = true;
+        self.strict = next.strict;
+        self.overtime = next.overtime;
+        self.manual = next.manual;
+        self.task = next.task;
+        self.tags = next.tags;
+        self.goals = next.goals;
+        self.max_pause = next.max_pause;
+        self.pause_mode = next.pause_mode;
+    }
+    /// Records all section changes caused by the timer that happened until `now`
+    /// and were not recorded yet.
+    pub fn record_timer_transitions(&mut self, now: DateTime<Utc>) {
+        if !self.active {
+            return;
+        }
+        let now = self.pause_started.unwrap_or(now);
+        let last = self.transitions.last().map(|t| t.time);
+        let mut boundary = self.start;
+        let mut from = PomodoroState::NotStarted;
+        let mut trigger = TransitionTrigger::Start;
+        for (i, s) in self.sections.iter().enumerate() {
+            if boundary > now {
+                return;
+            }
+            if last.is_none_or(|l| boundary > l) {
+                self.transitions.push(Transition {
+                    time: boundary,
+                    from,
+                    to: s.state,
+                    trigger,
+                });
+            }
+            if s.open || self.holds(s) {
+                return;
+            }
+            boundary += s.span();
+            from = s.state;
+            trigger = TransitionTrigger::Timer;
+            if i == self.sections.len() - 1 && boundary <= now && last.is_none_or(|l| boundary > l)
+            {
+                self.transitions.push(Transition {
+                    time: boundary,
+                    from,
+                    to: PomodoroState::Done,
+                    trigger,
+                });
+            }
+        }
+    }
+    fn record_transition(
+        &mut self,
+        time: DateTime<Utc>,
+        to: PomodoroState,
+        trigger: TransitionTrigger,
+    ) {
+        self.record_timer_transitions(time);
+        let from = self.state(time).current_state;
+        self.transitions.push(Transition {
+            time,
+            from,
+            to,
+            trigger,
+        });
+    }
+    /// Remembers the wall clock and the clock since boot at `now`, see `reconcile_clock`
+    pub fn anchor_clock(&mut self, now: DateTime<Utc>) {
+        self.clock = boot_clock().map(|(boot_id, since_boot)| ClockAnchor {
+            wall: now,
+            boot_id,
+            since_boot: since_boot.num_milliseconds(),
+        });
+    }
+    /// Moves all times of the pomo by as much as the wall clock was changed since it was anchored,
+    /// so the time left in the current section stays the same. Returns the change, `None` if the
+    /// clock was not changed or it can't be told, e.g. after a reboot.
+    pub fn reconcile_clock(&mut self, now: DateTime<Utc>) -> Option<Duration> {
+        let (boot_id, since_boot) = boot_clock()?;
+        let anchor = self.clock.as_mut().filter(|a| a.boot_id == boot_id)?;
+        let expected = anchor.wall + (since_boot - Duration::milliseconds(anchor.since_boot));
+        let offset = now - expected;
+        if offset.num_seconds().abs() <= CLOCK_TOLERANCE {
+            return None;
+        }
+        anchor.wall += offset;
+        self.start += offset;
+        self.pause_started = self.pause_started.map(|p| p + offset);
+        for transition in self.transitions.iter_mut() {
+            transition.time += offset;
+        }
+        for note in self.notes.iter_mut() {
+            note.time += offset;
+        }
+        Some(offset)
+    }
+    pub fn stop(&mut self, now: DateTime<Utc>) {
+        if let CurrentSection::Section(_) = self.current_section(now) {
+            self.record_transition(now, PomodoroState::Done, TransitionTrigger::Stop);
+        }
+        self.active = false;
+    }
+    /// When the pomo ended: the time it was stopped or the end of its last section
+    pub fn ended_at(&self) -> DateTime<Utc> {
+        self.transitions
+            .iter()
+            .rev()
+            .find(|t| matches!(t.trigger, TransitionTrigger::Stop))
+            .map_or(self.end(), |t| t.time)
+    }
+    /// Time the pause reached the maximum pause, `None` if it did not reach it by `t`
+    fn expired_pause_end(&self, t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let max = self.max_pause?;
+        let end = self.pause_started? + max.duration;
+        (self.active && t > end).then_some(end)
+    }
+    /// Ends a pause that is longer than the maximum pause as if it was ended when the maximum
+    /// was reached. Returns the time the pause ended.
+    pub fn end_expired_pause(&mut self, t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let end = self.expired_pause_end(t)?;
+        match self.max_pause?.action {
+            MaxPauseAction::Unpause => self.set_unpause(end, false),
+            MaxPauseAction::Stop => {
+                self.stop(end);
+                self.pause_started = None;
+            }
+        }
+        Some(end)
+    }
+    pub fn set_pause(&mut self, pause_start: DateTime<Utc>) {
+        if self.pause_started.is_none() {
+            if let CurrentSection::Section(_) = self.current_section(pause_start) {
+                self.record_transition(pause_start, PomodoroState::Break, TransitionTrigger::Pause);
+            }
+        }
+        self.pause_started = Some(pause_start);
+    }
+    /// Ends the pause by splicing a break into the interrupted section, or by shifting the rest
+    /// of the pomo depending on the pause mode.
+    /// With `restart` the interrupted section starts over after the break instead of continuing.
+    pub fn set_unpause(&mut self, pause_end: DateTime<Utc>, restart: bool) {
+        if self.pause_mode == PauseMode::Shift {
+            return self.shift_unpause(pause_end, restart);
+        }
+        if let Some(pause_start) = self.pause_started {
+            if let (CurrentSection::Section(s), Some((section_start_time, _))) =
+                self.locate(pause_start)
+            {
+                // zero if the pause started in the first second of the section
+                let new_section_dur =
+                    whole_seconds(pause_start - section_start_time - self.sections[s].paused)
+                        .max(Duration::zero());
+                let split_section_old_dur;
+                let split_section_state;
+                let split_section_open;
+                let split_section_acknowledged;
+                {
+                    let split_section = self.sections.get_mut(s).unwrap();
+                    split_section_old_dur = split_section.duration;
+                    split_section.duration = new_section_dur;
+                    split_section_state = split_section.state;
+                    split_section_open = split_section.open;
+                    split_section_acknowledged = split_section.acknowledged;
+                    split_section.open = false;
+                    split_section.acknowledged = true;
+                }
+                self.sections.insert(
+                    s + 1,
+                    PomodoroSection {
+                        duration: whole_seconds(pause_end - pause_start),
+                        state: PomodoroState::Break,
+                        open: false,
+                        acknowledged: false,
+                        paused: Duration::zero(),
+                    },
+                );
+                self.sections.insert(
+                    s + 2,
+                    PomodoroSection {
+                        // paused in overtime or in an open section, nothing of the planned time is left
+                        duration: if split_section_open {
+                            Duration::zero()
+                        } else if restart {
+                            split_section_old_dur
+                        } else if split_section_old_dur < new_section_dur {
+                            Duration::zero()
+                        } else {
+                            split_section_old_dur - new_section_dur
+                        },
+                        state: split_section_state,
+                        open: split_section_open,
+                        acknowledged: split_section_acknowledged,
+                        paused: Duration::zero(),
+                    },
+                );
+                self.transitions.push(Transition {
+                    time: pause_end,
+                    from: PomodoroState::Break,
+                    to: split_section_state,
+                    trigger: TransitionTrigger::Unpause,
+                });
+            }
+            self.pause_started = None;
+        }
+    }
+    /// Ends the pause by moving the end of the interrupted section and all later sections by the
+    /// length of the pause
+    fn shift_unpause(&mut self, pause_end: DateTime<Utc>, restart: bool) {
+        if let Some(pause_start) = self.pause_started {
+            if let (CurrentSection::Section(s), Some((section_start_time, _))) =
+                self.locate(pause_start)
+            {
+                let section = self.sections.get_mut(s).unwrap();
+                if restart && !section.open {
+                    // the time spent before the pause is added, so the full duration is left
+                    section.duration +=
+                        whole_seconds(pause_start - section_start_time - section.paused);
+                }
+                section.paused += whole_seconds(pause_end - pause_start);
+                let state = section.state;
+                self.transitions.push(Transition {
+                    time: pause_end,
+                    from: PomodoroState::Break,
+                    to: state,
+                    trigger: TransitionTrigger::Unpause,
+                });
+            }
+            self.pause_started = None;
+        }
+    }
+    /// Ends the current section at `now` so the next section starts immediately
+    pub fn skip(&mut self, now: DateTime<Utc>) -> Result<(), FixMeLaterError> {
+        let i = match self.current_section(now) {
+            CurrentSection::Section(i) => i,
+            _ => {
+                return Err(FixMeLaterError::K(
+                    ErrKind::NoActivePomo,
+                    "No section running".to_string(),
+                ))
+            }
+        };
+        let section_start_time = self.section_start(i);
+        if self.pause_started.is_some() && !self.sections[i].open {
+            self.set_unpause(now, false);
+            let next_section = if self.pause_mode == PauseMode::Shift {
+                // the section ends where it was paused
+                let section = self.sections.get_mut(i).unwrap();
+                section.duration = whole_seconds(now - section_start_time - section.paused);
+                section.acknowledged = true;
+                i + 1
+            } else {
+                // drop the rest of the interrupted section that got spliced in after the pause
+                self.sections.remove(i + 2);
+                i + 2
+            };
+            let next = self
+                .sections
+                .get(next_section)
+                .map_or(PomodoroState::Done, |s| s.state);
+            if let Some(t) = self.transitions.last_mut() {
+                t.to = next;
+                t.trigger = TransitionTrigger::Skip;
+            }
+            return Ok(());
+        }
+        if self.sections[i].open {
+            return self.start_break(now);
+        }
+        let next = self
+            .sections
+            .get(i + 1)
+            .map_or(PomodoroState::Done, |s| s.state);
+        self.record_transition(now, next, TransitionTrigger::Skip);
+        self.sections[i].duration =
+            whole_seconds(now - section_start_time - self.sections[i].paused);
+        self.sections[i].acknowledged = true;
+        Ok(())
+    }
+    /// Starts the current section over at `now` with its full planned duration, the sections
+    /// after it move back by the time spent in it so far
+    pub fn restart_section(&mut self, now: DateTime<Utc>) -> Result<(), FixMeLaterError> {
+        if self.pause_started.is_some() {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "The pomo is paused, unpause it first".to_string(),
+            ));
+        }
+        let i = match self.current_section(now) {
+            CurrentSection::Section(i) if !self.sections[i].open => i,
+            _ => {
+                return Err(FixMeLaterError::K(
+                    ErrKind::InvalidState,
+                    "No section with a fixed end running".to_string(),
+                ))
+            }
+        };
+        let section_start_time = self.section_start(i);
+        let section = &mut self.sections[i];
+        section.duration += whole_seconds(now - section_start_time - section.paused);
+        Ok(())
+    }
+    /// A new run of this pomodoro starting at `now`, generated from the same setting and with
+    /// the same task, tags, goals and modes
+    pub fn restarted(&self, now: DateTime<Utc>) -> Result<Pomodoro, FixMeLaterError> {
+        let Some(setting) = &self.setting else {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "The pomo has no stored setting to restart from, start it with 'pomo start'"
+                    .to_string(),
+            ));
+        };
+        let mut pomo = PomodoroSetting {
+            start: now,
+            ..setting.clone()
+        }
+        .to_pomodoro();
+        pomo.strict = self.strict;
+        pomo.overtime = self.overtime;
+        pomo.manual = self.manual;
+        pomo.task = self.task.clone();
+        pomo.tags = self.tags.clone();
+        pomo.goals = self.goals.clone();
+        pomo.max_pause = self.max_pause;
+        pomo.pause_mode = self.pause_mode;
+        pomo.micro_break = self.micro_break;
+        pomo.eye_care = self.eye_care;
+        pomo.unblocked = self.unblocked;
+        Ok(pomo)
+    }
+    /// Ends the current break at `now`. With `rebalance` the rest of the break is spread evenly
+    /// over the later breaks, so the pomo still ends at the same time.
+    pub fn back_to_work(
+        &mut self,
+        now: DateTime<Utc>,
+        rebalance: bool,
+    ) -> Result<(), FixMeLaterError> {
+        if self.pause_started.is_some() {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "The pomo is paused, unpause it first".to_string(),
+            ));
+        }
+        let i = match self.current_section(now) {
+            CurrentSection::Section(i) if self.sections[i].state == PomodoroState::Break => i,
+            _ => {
+                return Err(FixMeLaterError::K(
+                    ErrKind::InvalidState,
+                    "No break running".to_string(),
+                ))
+            }
+        };
+        let later_breaks: Vec<usize> = (i + 1..self.sections.len())
+            .filter(|j| self.sections[*j].state == PomodoroState::Break && !self.sections[*j].open)
+            .collect();
+        if rebalance && later_breaks.is_empty() {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "This is the last break, there is no break to move the rest of it to".to_string(),
+            ));
+        }
+        let section_end = self.section_end(i);
+        let left = whole_seconds(section_end - now).max(Duration::zero());
+        self.skip(now)?;
+        if rebalance {
+            let count = later_breaks.len() as i64;
+            for (n, j) in later_breaks.into_iter().enumerate() {
+                // the seconds that don't divide evenly go to the first breaks
+                let extra = i64::from((n as i64) < left.num_seconds() % count);
+                self.sections[j].duration += Duration::seconds(left.num_seconds() / count + extra);
+            }
+        }
+        Ok(())
+    }
+    /// Makes the current section longer by `by`
+    pub fn extend(&mut self, now: DateTime<Utc>, by: Duration) -> Result<(), FixMeLaterError> {
+        if by <= Duration::zero() {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidInput,
+                "A section can only be made longer".to_string(),
+            ));
+        }
+        match self.current_section(now) {
+            CurrentSection::Section(i) if !self.sections[i].open => {
+                // the later sections move as well and have to stay in range
+                self.end()
+                    .checked_add_signed(by)
+                    .ok_or(TimeError::Overflow)?;
+                let section = &mut self.sections[i];
+                section.duration = section
+                    .duration
+                    .checked_add(&by)
+                    .ok_or(TimeError::Overflow)?;
+                Ok(())
+            }
+            _ => Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "No section with a fixed end running".to_string(),
+            )),
+        }
+    }
+    /// Index of the first section that has not started at `t`, the sections from there on
+    /// can be edited
+    fn first_upcoming(&self, t: DateTime<Utc>) -> Result<usize, FixMeLaterError> {
+        if self.pause_started.is_some() {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "The pomo is paused, unpause it first".to_string(),
+            ));
+        }
+        match self.current_section(t) {
+            CurrentSection::Inactive => Err(FixMeLaterError::K(
+                ErrKind::NoActivePomo,
+                "No active pomo".to_string(),
+            )),
+            CurrentSection::BeforeStart => Ok(0),
+            CurrentSection::Section(i) => Ok(i + 1),
+            CurrentSection::AferEnd => Ok(self.sections.len()),
+        }
+    }
+    /// The sections that have not started at `t`
+    pub fn upcoming_sections(
+        &self,
+        t: DateTime<Utc>,
+    ) -> Result<&[PomodoroSection], FixMeLaterError> {
+        Ok(&self.sections[self.first_upcoming(t)?..])
+    }
+    /// Sets the duration of all upcoming sections of `state`, returns how many were changed
+    pub fn set_upcoming_durations(
+        &mut self,
+        t: DateTime<Utc>,
+        state: PomodoroState,
+        duration: Duration,
+    ) -> Result<usize, FixMeLaterError> {
+        let first = self.first_upcoming(t)?;
+        let mut changed = 0;
+        for section in self.sections[first..].iter_mut() {
+            if section.state == state && !section.open {
+                section.duration = duration;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+    /// Removes the last `n` sections, only sections that have not started can be removed
+    pub fn drop_last(&mut self, t: DateTime<Utc>, n: usize) -> Result<(), FixMeLaterError> {
+        let first = self.first_upcoming(t)?;
+        let upcoming = self.sections.len() - first;
+        if n > upcoming {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidInput,
+                format!("Only {} sections have not started yet", upcoming),
+            ));
+        }
+        self.sections.truncate(self.sections.len() - n);
+        Ok(())
+    }
+    /// Replaces all sections that have not started with `sections`
+    pub fn replace_upcoming(
+        &mut self,
+        t: DateTime<Utc>,
+        sections: Vec<PomodoroSection>,
+    ) -> Result<(), FixMeLaterError> {
+        let first = self.first_upcoming(t)?;
+        self.sections.truncate(first);
+        self.sections.extend(sections);
+        Ok(())
+    }
+    /// Closes the open work section at `now` and schedules a break proportional to the
+    /// time worked, followed by a new open work section.
+    pub fn start_break(&mut self, now: DateTime<Utc>) -> Result<(), FixMeLaterError> {
+        let i = match self.current_section(now) {
+            CurrentSection::Section(i) if self.sections[i].open => i,
+            _ => {
+                return Err(FixMeLaterError::K(
+                    ErrKind::InvalidState,
+                    "No open-ended work section running, start one with 'pomo flow'".to_string(),
+                ))
+            }
+        };
+        if self.pause_started.is_some() {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "The pomo is paused, unpause it first".to_string(),
+            ));
+        }
+        let section_start_time = self.section_start(i);
+        let worked = whole_seconds(now - section_start_time - self.sections[i].paused);
+        self.record_transition(now, PomodoroState::Break, TransitionTrigger::Skip);
+        {
+            let section = self.sections.get_mut(i).unwrap();
+            section.duration = worked;
+            section.open = false;
+        }
+        self.sections.insert(
+            i + 1,
+            PomodoroSection {
+                duration: worked / FLOW_BREAK_RATIO,
+                state: PomodoroState::Break,
+                open: false,
+                acknowledged: false,
+                paused: Duration::zero(),
+            },
+        );
+        self.sections.insert(
+            i + 2,
+            PomodoroSection {
+                duration: Duration::zero(),
+                state: PomodoroState::Work,
+                open: true,
+                acknowledged: false,
+                paused: Duration::zero(),
+            },
+        );
+        Ok(())
+    }
+}
+
+impl CurrentPomoState {
+    /// Like `to_string`, but with durations like `23 min left` instead of `00:23:00`
+    pub fn to_human_string(&self) -> String {
+        self.render(true, None)
+    }
+
+    /// A state letter and the remaining `mm:ss`, e.g. `W 17:32`, for places with little room.
+    /// `P` is shown while paused, `U` during the warmup, `D` once done and `N` before the start. Elapsed time of open,
+    /// overtime and waiting sections is prefixed with `+`. With `icons` the glyphs replace the
+    /// letters.
+    pub(crate) fn render_short(&self, icons: Option<&Icons>) -> String {
+        let letter = match (icons, self.current_state) {
+            (Some(icons), _) if self.pause => icons.paused.as_str(),
+            (Some(icons), PomodoroState::Done) => return icons.done.clone(),
+            (Some(icons), state) => icons.for_state(state),
+            _ if self.pause => "P",
+            (None, PomodoroState::Warmup) => "U",
+            (None, PomodoroState::Work) => "W",
+            (None, PomodoroState::Break) => "B",
+            (None, PomodoroState::Done) => return "D".to_string(),
+            (None, PomodoroState::NotStarted) => "N",
+        };
+        let sign = if self.open_ended || self.overtime || self.waiting {
+            "+"
+        } else {
+            ""
+        };
+        format!(
+            "{} {}{:02}:{:02}",
+            letter,
+            sign,
+            self.duration.num_minutes(),
+            self.duration.num_seconds() % 60
+        )
+    }
+
+    /// With `icons` the states are shown as glyphs instead of their names
+    pub(crate) fn render(&self, human: bool, icons: Option<&Icons>) -> String {
+        let name = |state: PomodoroState| match icons {
+            Some(icons) => icons.for_state(state).to_string(),
+            None => state.label().to_string(),
+        };
+        let next = if self.next_state != self.current_state {
+            format!("(-> {}) ", name(self.next_state))
+        } else {
+            "".to_string()
+        };
+        let d = if human {
+            format_duration_human(self.duration)
+        } else {
+            format_duration(self.duration)
+        };
+        let duration = if self.open_ended {
+            format!("+{} ", d)
+        } else if self.overtime {
+            format!("+{} {} ", d, tr("overtime"))
+        } else if self.current_state != PomodoroState::Done {
+            if human {
+                format!("{} {} ", d, tr("left"))
+            } else {
+                format!("{} ", d)
+            }
+        } else {
+            "".to_string()
+        };
+        let pause = match icons {
+            _ if !self.pause => "".to_string(),
+            Some(icons) => format!(" {}", icons.paused),
+            None => format!(" ({})", tr("paused")),
+        };
+        if self.waiting {
+            return format!(
+                "{} {}{}/{}{}",
+                tr("waiting"),
+                next,
+                self.completed_repetitions,
+                self.total_repetitions,
+                pause
+            );
+        }
+        format!(
+            "{} {}{}{}/{}{}",
+            name(self.current_state),
+            duration,
+            next,
+            self.completed_repetitions,
+            self.total_repetitions,
+            pause,
+        )
+    }
+}
+
+impl Display for CurrentPomoState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render(false, None))
+    }
+}
+
+impl PomodoroSetting {
+    pub fn to_pomodoro(&self) -> Pomodoro {
+        let mut pomo = Pomodoro {
+            setting: Some(self.clone()),
+            ..Pomodoro::new(self.start)
+        };
+        let leading = [
+            (self.leading_break, PomodoroState::Break),
+            (self.warmup, PomodoroState::Warmup),
+        ];
+        for (duration, state) in leading {
+            if let Some(duration) = duration {
+                pomo.sections.push(PomodoroSection {
+                    duration,
+                    state,
+                    open: false,
+                    acknowledged: false,
+                    paused: Duration::zero(),
+                });
+            }
+        }
+        for i in 0..self.repetitions {
+            pomo.sections.push(PomodoroSection {
+                duration: self.work_time,
+                state: PomodoroState::Work,
+                open: false,
+                acknowledged: false,
+                paused: Duration::zero(),
+            });
+            if i < self.repetitions - 1 {
+                pomo.sections.push(PomodoroSection {
+                    duration: self.break_duration(i),
+                    state: PomodoroState::Break,
+                    open: false,
+                    acknowledged: false,
+                    paused: Duration::zero(),
+                });
+            }
+        }
+        pomo
+    }
+    /// calculate new durations or repetitions based on end time, `mode` decides what changes
+    pub fn adjust_end_to(
+        &mut self,
+        end_time: DateTime<Utc>,
+        mode: UntilMode,
+    ) -> Result<(), TimeError> {
+        // base formula of total duration, with r = repetitions, w = work time, b = break time:
+        // d = rw + (r-1)b
+        if end_time <= self.start {
+            return Err(TimeError::EndBeforeStart);
+        }
+        // all breaks are equal so the pomo ends exactly at the end time
+        self.long_break = None;
+        // the leading break and the warmup keep their durations
+        // the start is stored in whole seconds as well
+        let d = end_time
+            - self.start.trunc_subsecs(0)
+            - self.leading_break.unwrap_or(Duration::zero())
+            - self.warmup.unwrap_or(Duration::zero());
+        if d <= Duration::zero() {
+            return Err(TimeError::TooShort);
+        }
+        let r = i32::try_from(self.repetitions).map_err(|_| TimeError::Overflow)?;
+        match mode {
+            UntilMode::Reps => self.adjust_repetitions(d)?,
+            UntilMode::Work => {
+                // w = (d - (r-1)b) / r
+                let breaks = self
+                    .break_time
+                    .checked_mul(r - 1)
+                    .ok_or(TimeError::Overflow)?;
+                let w = (d - breaks).checked_div(r).ok_or(TimeError::Overflow)?;
+                if w <= Duration::zero() {
+                    return Err(TimeError::TooShort);
+                }
+                self.work_time = w;
+            }
+            UntilMode::Break => {
+                // b = (d - rw) / (r-1), a single repetition has no breaks to change
+                if r == 1 {
+                    return self.adjust_end_to(end_time, UntilMode::Work);
+                }
+                let work = self.work_time.checked_mul(r).ok_or(TimeError::Overflow)?;
+                let b = (d - work).checked_div(r - 1).ok_or(TimeError::Overflow)?;
+                if b <= Duration::zero() {
+                    return Err(TimeError::TooShort);
+                }
+                self.break_time = b;
+            }
+            UntilMode::Proportional => {
+                let total = self.duration().ok_or(TimeError::Overflow)?;
+                let scale = |x: Duration| {
+                    let ms = x.num_milliseconds() as f64 * d.num_milliseconds() as f64
+                        / total.num_milliseconds() as f64;
+                    Duration::milliseconds(ms as i64)
+                };
+                self.work_time = scale(self.work_time);
+                self.break_time = scale(self.break_time);
+                // rounding errors are spread over the work sections
+                let rest = d - self.duration().ok_or(TimeError::Overflow)?;
+                self.work_time += rest / r;
+            }
+        }
+        // the sections are stored in whole seconds
+        self.work_time = round_seconds(self.work_time);
+        self.break_time = round_seconds(self.break_time);
+        if self.work_time <= Duration::zero() {
+            return Err(TimeError::TooShort);
+        }
+        // the same limit as for a definition, so a far end can't create millions of sections
+        if self
+            .duration()
+            .filter(|d| *d < Duration::hours(24))
+            .is_none()
+        {
+            return Err(TimeError::TooLong);
+        }
+        Ok(())
+    }
+    fn adjust_repetitions(&mut self, d: Duration) -> Result<(), TimeError> {
+        // rewrite the duration in terms of work time:
+        // f(r) = w = (d/r) - ((r-1)b/r)
+
+        let f = |r: i32| {
+            let breaks = self.break_time.checked_mul(r - 1)?.checked_div(r)?;
+            d.checked_div(r)?.checked_sub(&breaks)
+        };
+
+        // the difference between the calculated and the specified work time is the smallest
+        // next to r = (d+b)/(w+b), where the work time stays as it is
+        let per_rep = (self.work_time + self.break_time).num_milliseconds().max(1);
+        let estimate = (d + self.break_time).num_milliseconds() / per_rep;
+        let mut reps: i32 = 1;
+        let mut w_delta = i64::MAX;
+        for r in (estimate - 1).max(1)..=estimate + 1 {
+            let r = i32::try_from(r).map_err(|_| TimeError::Overflow)?;
+            let new_w_delta = f(r)
+                .ok_or(TimeError::Overflow)?
+                .checked_sub(&self.work_time)
+                .ok_or(TimeError::Overflow)?
+                .num_seconds()
+                .abs();
+            // on a tie the work sections get shorter
+            if new_w_delta <= w_delta {
+                reps = r;
+                w_delta = new_w_delta;
+            }
+        }
+        let new_w = f(reps).ok_or(TimeError::Overflow)?;
+        if new_w <= Duration::zero() {
+            return Err(TimeError::TooShort);
+        }
+        self.repetitions = u32::try_from(reps).map_err(|_| TimeError::Overflow)?;
+        self.work_time = new_w;
+        Ok(())
+    }
+    /// Starts the pomo with a break as long as the other breaks
+    pub fn start_with_break(&mut self) {
+        self.leading_break = Some(self.break_time);
+    }
+    /// Sets the warmup before the first work section, none if `duration` is zero
+    pub fn set_warmup(&mut self, duration: Duration) -> Result<(), TimeError> {
+        // the same limit as for the definition
+        if duration >= Duration::hours(24) {
+            return Err(TimeError::TooLong);
+        }
+        self.warmup = Some(duration).filter(|d| *d > Duration::zero());
+        Ok(())
+    }
+    /// Total duration of the repetitions without a leading break or warmup, `None` if it overflows
+    fn duration(&self) -> Option<Duration> {
+        let r = i32::try_from(self.repetitions).ok()?;
+        let long = self
+            .long_break
+            .filter(|l| l.every > 0)
+            .map_or(0, |l| (self.repetitions - 1) / l.every);
+        let long = i32::try_from(long).ok()?;
+        let long_time = self.long_break.map_or(Duration::zero(), |l| l.duration);
+        self.work_time
+            .checked_mul(r)?
+            .checked_add(&self.break_time.checked_mul(r - 1 - long)?)?
+            .checked_add(&long_time.checked_mul(long)?)
+    }
+    /// Duration of the break after the work section `i`, counted from 0
+    fn break_duration(&self, i: u32) -> Duration {
+        match self.long_break {
+            Some(long) if (i + 1).is_multiple_of(long.every) => long.duration,
+            _ => self.break_time,
+        }
+    }
+    /// Parses a string in the format "4p45b15" into the Pomodoro
+    /// repetitions: 4, work_time: 45min, break_time: 15min
+    /// The parts that are left out are taken from `defaults`.
+    pub fn from_string(
+        s: &str,
+        start: DateTime<Utc>,
+        defaults: &PomodoroDefaults,
+    ) -> Result<PomodoroSetting, FixMeLaterError> {
+        let digits = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let repetitions = match &s[..digits] {
+            "" => defaults.repetitions,
+            reps => reps
+                .parse()
+                .map_err(|_| definition_error(s, 0, digits, "too many repetitions"))?,
+        };
+        if repetitions == 0 {
+            return Err(definition_error(
+                s,
+                0,
+                digits,
+                "at least one repetition is needed",
+            ));
+        }
+        let mut pos = digits;
+        // durations without a unit are minutes
+        let mut segment = |prefix: char, default: Duration| {
+            if !s[pos..].starts_with(prefix) {
+                return Ok(default);
+            }
+            let from = pos;
+            let len = s[from + 1..]
+                .find(|c: char| !c.is_ascii_digit() && !"hms".contains(c))
+                .unwrap_or(s.len() - from - 1);
+            pos = from + 1 + len;
+            let invalid = |message: &str| definition_error(s, from, len + 1, message);
+            match parse_duration(&s[from + 1..pos]) {
+                Err(_) => Err(invalid("invalid duration, use e.g. 45, 45m, 1h30m or 90s")),
+                Ok(d) if d <= Duration::zero() => {
+                    Err(invalid("durations have to be longer than 0"))
+                }
+                Ok(d) => Ok(d),
+            }
+        };
+        let work_time = segment('p', defaults.work_time)?;
+        let break_time = segment('b', defaults.break_time)?;
+        if pos < s.len() {
+            let c = s[pos..].chars().next().unwrap();
+            return Err(definition_error(
+                s,
+                pos,
+                s.len() - pos,
+                &format!("unexpected '{}', expected p<work> or b<break>", c),
+            ));
+        }
+        let setting = PomodoroSetting {
+            start,
+            repetitions,
+            work_time,
+            break_time,
+            long_break: defaults.long_break,
+            leading_break: None,
+            warmup: defaults.warmup,
+        };
+        if setting
+            .duration()
+            .filter(|d| *d < Duration::hours(24))
+            .is_none()
+        {
+            return Err(definition_error(
+                s,
+                0,
+                s.len(),
+                "the pomo has to be shorter than 24 hours",
+            ));
+        }
+        Ok(setting)
+    }
+}
+
+/// An error about a pomo definition with the part from `at` of `len` bytes underlined
+fn definition_error(s: &str, at: usize, len: usize, message: &str) -> FixMeLaterError {
+    let indent = s[..at].chars().count();
+    let width = s[at..at + len].chars().count().max(1);
+    FixMeLaterError::K(
+        ErrKind::InvalidInput,
+        format!(
+            "Invalid pomo definition, {}\n  {}\n  {}{}",
+            message,
+            s,
+            " ".repeat(indent),
+            "^".repeat(width)
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn start() -> DateTime<Utc> {
+        DateTime::from_timestamp(1_700_000_000, 0).unwrap()
+    }
+
+    /// 4 repetitions of 25 minutes with 5 minute breaks
+    fn setting() -> PomodoroSetting {
+        PomodoroSetting {
+            start: start(),
+            repetitions: 4,
+            work_time: Duration::minutes(25),
+            break_time: Duration::minutes(5),
+            long_break: None,
+            leading_break: None,
+            warmup: None,
+        }
+    }
+
+    const MODES: [UntilMode; 4] = [
+        UntilMode::Reps,
+        UntilMode::Work,
+        UntilMode::Break,
+        UntilMode::Proportional,
+    ];
+
+    /// work 0:00-0:25, break 0:25-0:30, work 0:30-0:55
+    fn pomo() -> Pomodoro {
+        let mut s = setting();
+        s.repetitions = 2;
+        s.to_pomodoro()
+    }
+
+    fn at(minutes: i64, seconds: i64) -> DateTime<Utc> {
+        start() + Duration::minutes(minutes) + Duration::seconds(seconds)
+    }
+
+    #[test]
+    fn sections_at_their_bounds() {
+        let p = pomo();
+        let expected = [
+            (at(0, -1), CurrentSection::BeforeStart),
+            (at(0, 0), CurrentSection::Section(0)),
+            (at(0, 1), CurrentSection::Section(0)),
+            (at(25, -1), CurrentSection::Section(0)),
+            (at(25, 0), CurrentSection::Section(1)),
+            (at(25, 1), CurrentSection::Section(1)),
+            (at(30, -1), CurrentSection::Section(1)),
+            (at(30, 0), CurrentSection::Section(2)),
+            (at(30, 1), CurrentSection::Section(2)),
+            (at(55, -1), CurrentSection::Section(2)),
+            (at(55, 0), CurrentSection::AferEnd),
+            (at(55, 1), CurrentSection::AferEnd),
+        ];
+        for (t, section) in expected {
+            assert!(p.current_section(t) == section, "wrong section at {}", t);
+        }
+        assert_eq!(p.end(), at(55, 0));
+    }
+
+    #[test]
+    fn state_at_the_bounds() {
+        let p = pomo();
+        let expected = [
+            (at(0, 0), PomodoroState::Work, Duration::minutes(25), 1),
+            (at(25, -1), PomodoroState::Work, Duration::seconds(1), 1),
+            (at(25, 0), PomodoroState::Break, Duration::minutes(5), 1),
+            (at(25, 1), PomodoroState::Break, at(30, 0) - at(25, 1), 1),
+            (at(30, -1), PomodoroState::Break, Duration::seconds(1), 1),
+            (at(30, 0), PomodoroState::Work, Duration::minutes(25), 2),
+            (at(55, -1), PomodoroState::Work, Duration::seconds(1), 2),
+            (at(55, 0), PomodoroState::Done, Duration::zero(), 2),
+            (at(55, 1), PomodoroState::Done, Duration::zero(), 2),
+        ];
+        // the count includes the work section that is running
+        for (t, state, remaining, completed) in expected {
+            let s = p.state(t);
+            assert!(s.current_state == state, "wrong state at {}", t);
+            assert_eq!(s.duration, remaining, "wrong time left at {}", t);
+            assert_eq!(s.completed_repetitions, completed, "wrong count at {}", t);
+        }
+    }
+
+    #[test]
+    fn boundaries_are_exact() {
+        let p = pomo();
+        assert_eq!(p.next_boundary(at(0, 0)), Some(at(25, 0)));
+        assert_eq!(p.next_boundary(at(25, -1)), Some(at(25, 0)));
+        assert_eq!(p.next_boundary(at(25, 0)), Some(at(30, 0)));
+        assert_eq!(p.next_boundary(at(30, 0)), Some(at(55, 0)));
+        let bounds: Vec<Bounds> = p.section_bounds().collect();
+        assert_eq!(
+            bounds,
+            vec![
+                (at(0, 0), at(25, 0)),
+                (at(25, 0), at(30, 0)),
+                (at(30, 0), at(55, 0))
+            ]
+        );
+    }
+
+    #[test]
+    fn end_at_start_is_refused() {
+        for mode in MODES {
+            let result = setting().adjust_end_to(start(), mode);
+            assert!(matches!(result, Err(TimeError::EndBeforeStart)));
+        }
+    }
+
+    #[test]
+    fn end_before_start_is_refused() {
+        for mode in MODES {
+            let result = setting().adjust_end_to(start() - Duration::seconds(1), mode);
+            assert!(matches!(result, Err(TimeError::EndBeforeStart)));
+        }
+    }
+
+    #[test]
+    fn one_second_span() {
+        let end = start() + Duration::seconds(1);
+        let mut s = setting();
+        s.adjust_end_to(end, UntilMode::Reps).unwrap();
+        assert_eq!(s.repetitions, 1);
+        assert_eq!(s.work_time, Duration::seconds(1));
+        for mode in [UntilMode::Work, UntilMode::Break] {
+            let result = setting().adjust_end_to(end, mode);
+            assert!(matches!(result, Err(TimeError::TooShort)));
+        }
+    }
+
+    #[test]
+    fn span_after_warmup_is_too_short() {
+        let mut s = setting();
+        s.set_warmup(Duration::minutes(5)).unwrap();
+        let result = s.adjust_end_to(start() + Duration::minutes(5), UntilMode::Reps);
+        assert!(matches!(result, Err(TimeError::TooShort)));
+    }
+
+    #[test]
+    fn huge_span_overflows_the_repetitions() {
+        let end = start() + Duration::days(365 * 200_000);
+        let result = setting().adjust_end_to(end, UntilMode::Reps);
+        assert!(matches!(result, Err(TimeError::Overflow)));
+    }
+
+    #[test]
+    fn long_span_is_too_long() {
+        for hours in [25, 2000, 99_999_999] {
+            for mode in MODES {
+                let result = setting().adjust_end_to(start() + Duration::hours(hours), mode);
+                assert!(matches!(result, Err(TimeError::TooLong)), "{} h", hours);
+            }
+        }
+        let mut s = setting();
+        s.adjust_end_to(start() + Duration::hours(23), UntilMode::Reps)
+            .unwrap();
+        assert_eq!(s.repetitions, 46);
+    }
+
+    #[test]
+    fn long_warmup_is_too_long() {
+        let mut s = setting();
+        let result = s.set_warmup(Duration::hours(24));
+        assert!(matches!(result, Err(TimeError::TooLong)));
+        assert_eq!(s.warmup, None);
+        s.set_warmup(Duration::hours(23)).unwrap();
+        assert_eq!(s.warmup, Some(Duration::hours(23)));
+    }
+
+    #[test]
+    fn huge_repetitions_overflow() {
+        let mut s = setting();
+        s.repetitions = u32::MAX;
+        for mode in MODES {
+            let result = s.clone().adjust_end_to(start() + Duration::hours(2), mode);
+            assert!(matches!(result, Err(TimeError::Overflow)));
+        }
+    }
+
+    #[test]
+    fn durations_are_whole_seconds() {
+        // like `start 1p1 --until` right after a full second
+        let mut s = setting();
+        s.start = start() + Duration::milliseconds(250);
+        s.repetitions = 1;
+        s.work_time = Duration::minutes(1);
+        s.adjust_end_to(start() + Duration::hours(1), UntilMode::Work)
+            .unwrap();
+        assert_eq!(s.work_time, Duration::hours(1));
+        for mode in MODES {
+            let mut s = setting();
+            s.adjust_end_to(start() + Duration::seconds(3 * 3600 + 1), mode)
+                .unwrap();
+            assert_eq!(s.work_time.subsec_nanos(), 0);
+            assert_eq!(s.break_time.subsec_nanos(), 0);
+        }
+    }
+
+    #[test]
+    fn end_is_kept_in_every_mode() {
+        let end = start() + Duration::hours(3);
+        for mode in MODES {
+            let mut s = setting();
+            s.adjust_end_to(end, mode).unwrap();
+            // each work section may be off by half a second after rounding
+            let off = (s.to_pomodoro().end() - end).num_seconds().abs();
+            assert!(off <= i64::from(s.repetitions) / 2);
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use chrono::{Local, NaiveDate, SecondsFormat};
+use serde::Serialize;
+
+use crate::history::Session;
+
+/// What the sessions of a query are grouped by
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupBy {
+    Session,
+    Day,
+    Tag,
+    Task,
+}
+
+impl GroupBy {
+    pub fn from_arg(s: &str) -> GroupBy {
+        match s {
+            "day" => GroupBy::Day,
+            "tag" => GroupBy::Tag,
+            "task" => GroupBy::Task,
+            _ => GroupBy::Session,
+        }
+    }
+}
+
+/// Result of `pomo history query`, serialized as the documented json schema.
+/// Only add fields to it, scripts rely on the existing ones.
+#[derive(Serialize)]
+pub struct QueryResult {
+    pub version: u32,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub group_by: GroupBy,
+    pub groups: Vec<Group>,
+}
+
+#[derive(Serialize)]
+pub struct Group {
+    /// start of the session, day, tag or task, `None` for sessions without tag or task
+    pub key: Option<String>,
+    pub sessions: u32,
+    pub focus_seconds: i64,
+    pub work_sections: u32,
+}
+
+/// Groups the sessions that started between `from` and `to` (both inclusive, local time)
+pub fn query(
+    sessions: &[Session],
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    group_by: GroupBy,
+) -> QueryResult {
+    let mut groups: Vec<Group> = vec![];
+    for session in sessions {
+        let pomo = session.pomodoro;
+        let start = pomo.start.with_timezone(&Local);
+        let day = start.date_naive();
+        if from.is_some_and(|f| day < f) || to.is_some_and(|t| day > t) {
+            continue;
+        }
+        let keys = match group_by {
+            GroupBy::Session => vec![Some(start.to_rfc3339_opts(SecondsFormat::Secs, false))],
+            GroupBy::Day => vec![Some(day.to_string())],
+            GroupBy::Task => vec![pomo.task.clone()],
+            GroupBy::Tag if pomo.tags.is_empty() => vec![None],
+            GroupBy::Tag => pomo.tags.iter().cloned().map(Some).collect(),
+        };
+        for key in keys {
+            let i = match groups.iter().position(|g| g.key == key) {
+                Some(i) => i,
+                None => {
+                    groups.push(Group {
+                        key,
+                        sessions: 0,
+                        focus_seconds: 0,
+                        work_sections: 0,
+                    });
+                    groups.len() - 1
+                }
+            };
+            groups[i].sessions += 1;
+            groups[i].focus_seconds += pomo.work_time(session.end).num_seconds();
+            groups[i].work_sections += pomo.completed_work_sections(session.end);
+        }
+    }
+    QueryResult {
+        version: 1,
+        from,
+        to,
+        group_by,
+        groups,
+    }
+}
+
+impl std::fmt::Display for QueryResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for g in self.groups.iter() {
+            writeln!(
+                f,
+                "{:<25} {:>3} sessions  {:>3}h {:02}m  {} work sections",
+                g.key.as_deref().unwrap_or("-"),
+                g.sessions,
+                g.focus_seconds / 3600,
+                g.focus_seconds / 60 % 60,
+                g.work_sections
+            )?;
+        }
+        Ok(())
+    }
+}
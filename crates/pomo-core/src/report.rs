@@ -0,0 +1,175 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Timelike, Utc, Weekday};
+use std::io::Write;
+use std::process::Stdio;
+
+use crate::config::ReportConfig;
+use crate::history::{HistoryEntry, Session};
+use crate::query::{query, GroupBy};
+use crate::stats::Stats;
+use crate::util::{shell_command, ErrKind, FixMeLaterError};
+
+const DEFAULT_EMAIL_COMMAND: &str = "sendmail -t";
+
+fn format_focus(d: Duration) -> String {
+    format!("{}h {:02}m", d.num_hours(), d.num_minutes() % 60)
+}
+
+/// Renders the focus time, finished work sections and goals of the last seven days
+pub fn weekly_report(sessions: &[Session], now: DateTime<Utc>) -> String {
+    let today = now.with_timezone(&Local).date_naive();
+    let first = today - Duration::days(6);
+    let mut days = [(Duration::zero(), 0); 7];
+    for session in sessions {
+        let day = session.pomodoro.start.with_timezone(&Local).date_naive();
+        if day < first || day > today {
+            continue;
+        }
+        let i = (day - first).num_days() as usize;
+        days[i].0 += session.pomodoro.work_time(session.end);
+        days[i].1 += session.pomodoro.completed_work_sections(session.end);
+    }
+
+    let mut report = format!("Pomodoro report {} - {}\n\n", first, today);
+    for (i, (time, count)) in days.iter().enumerate() {
+        let day = first + Duration::days(i as i64);
+        report += &format!(
+            "{} {}  {:>8}  {} work sections\n",
+            day.format("%a"),
+            day,
+            format_focus(*time),
+            count
+        );
+    }
+    let total_time = days
+        .iter()
+        .map(|d| d.0)
+        .fold(Duration::zero(), |a, d| a + d);
+    let total_count: u32 = days.iter().map(|d| d.1).sum();
+    report += &format!(
+        "\nTotal           {:>8}  {} work sections\n",
+        format_focus(total_time),
+        total_count
+    );
+
+    let goals: Vec<String> = sessions
+        .iter()
+        .filter(|s| s.pomodoro.start.with_timezone(&Local).date_naive() >= first)
+        .flat_map(|s| {
+            let day = s.pomodoro.start.with_timezone(&Local).format("%a");
+            s.pomodoro
+                .goals
+                .iter()
+                .map(move |g| format!("{} [{}] {}\n", day, if g.done { "x" } else { " " }, g.text))
+        })
+        .collect();
+    if !goals.is_empty() {
+        report += "\nGoals\n";
+        report += &goals.concat();
+    }
+    report
+}
+
+/// The sessions that started on `day` in local time
+fn sessions_on<'a>(sessions: &[Session<'a>], day: NaiveDate) -> Vec<Session<'a>> {
+    sessions
+        .iter()
+        .filter(|s| s.pomodoro.start.with_timezone(&Local).date_naive() == day)
+        .copied()
+        .collect()
+}
+
+/// Renders the totals of `day`, the most work sections finished in one session and the focus
+/// time of every tag
+pub fn daily_summary(entries: &[HistoryEntry], sessions: &[Session], day: NaiveDate) -> String {
+    let sessions = sessions_on(sessions, day);
+    let stats = Stats::new(entries, &sessions);
+    let longest_run = sessions
+        .iter()
+        .map(|s| s.pomodoro.completed_work_sections(s.end))
+        .max()
+        .unwrap_or(0);
+    let mut summary = format!("Summary of {}\n\n", day);
+    summary += &format!("Sessions       {}\n", stats.sessions);
+    summary += &format!("Focus time     {}\n", format_focus(stats.focus));
+    summary += &format!("Work sections  {}\n", stats.work_sections);
+    summary += &format!("Breaks taken   {}\n", stats.breaks);
+    summary += &format!("Longest run    {} work sections\n", longest_run);
+    summary += &format!(
+        "Interruptions  {} internal, {} external\n",
+        stats.internal_interruptions, stats.external_interruptions
+    );
+    let tags = query(&sessions, None, None, GroupBy::Tag);
+    if !tags.groups.is_empty() {
+        summary += &format!("\n{}", tags);
+    }
+    summary
+}
+
+/// The summary of `day` in one line for a notification
+pub fn daily_summary_line(
+    entries: &[HistoryEntry],
+    sessions: &[Session],
+    day: NaiveDate,
+) -> String {
+    let stats = Stats::new(entries, &sessions_on(sessions, day));
+    format!(
+        "{} work sections, {} focus, {} breaks, {} interruptions",
+        stats.work_sections,
+        format_focus(stats.focus),
+        stats.breaks,
+        stats.internal_interruptions + stats.external_interruptions
+    )
+}
+
+/// The totals of `day` as JSON
+pub fn daily_summary_json(
+    entries: &[HistoryEntry],
+    sessions: &[Session],
+    day: NaiveDate,
+) -> serde_json::Value {
+    let stats = Stats::new(entries, &sessions_on(sessions, day));
+    serde_json::json!({
+        "day": day.to_string(),
+        "sessions": stats.sessions,
+        "focus_seconds": stats.focus.num_seconds(),
+        "work_sections": stats.work_sections,
+        "breaks": stats.breaks,
+        "interruptions": stats.internal_interruptions + stats.external_interruptions,
+    })
+}
+
+/// The scheduled report is sent on sunday evenings
+pub fn report_due(now: DateTime<Utc>) -> bool {
+    let local = now.with_timezone(&Local);
+    local.weekday() == Weekday::Sun && local.hour() >= 18
+}
+
+/// Pipes the report as an email to the configured sendmail compatible command
+pub fn send_report(config: &ReportConfig, report: &str) -> Result<(), FixMeLaterError> {
+    let to = match &config.email_to {
+        Some(to) => to,
+        None => return Err(FixMeLaterError::K(
+            ErrKind::Config,
+            "No email address configured, set email_to in the [report] section of the config file"
+                .to_string(),
+        )),
+    };
+    let command = config
+        .email_command
+        .as_deref()
+        .unwrap_or(DEFAULT_EMAIL_COMMAND);
+    let mut child = shell_command(command).stdin(Stdio::piped()).spawn()?;
+    let mail = format!(
+        "To: {}\nSubject: Pomodoro weekly report\nContent-Type: text/plain; charset=utf-8\n\n{}",
+        to, report
+    );
+    child.stdin.take().unwrap().write_all(mail.as_bytes())?;
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(FixMeLaterError::S(format!(
+            "'{}' failed with {}",
+            command, status
+        )));
+    }
+    Ok(())
+}
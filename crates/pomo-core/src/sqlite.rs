@@ -0,0 +1,219 @@
+use chrono::{DateTime, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use tracing::debug;
+
+use crate::history::{HistoryEntry, HistoryEvent};
+use crate::storage::{HistoryFilter, HistoryStore, Pruned};
+use crate::util::{ErrKind, FixMeLaterError};
+
+/// How long a write waits for another pomo that is writing to the database at the same time
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS history (
+    id INTEGER PRIMARY KEY,
+    time INTEGER NOT NULL,
+    type TEXT NOT NULL,
+    start INTEGER NOT NULL,
+    task TEXT,
+    entry TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS history_start ON history (start);
+CREATE INDEX IF NOT EXISTS history_task ON history (task, start);
+CREATE INDEX IF NOT EXISTS history_time ON history (time);
+";
+
+impl From<rusqlite::Error> for FixMeLaterError {
+    fn from(value: rusqlite::Error) -> Self {
+        FixMeLaterError::K(ErrKind::Io, format!("{:?}", value))
+    }
+}
+
+/// The history in a SQLite database. Every entry is stored as the same json as in the history
+/// file, next to the columns the queries filter by.
+pub struct SqliteHistory {
+    path: String,
+    connection: Connection,
+}
+
+impl SqliteHistory {
+    pub fn open(path: &Path) -> Result<SqliteHistory, FixMeLaterError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let connection = Connection::open(path).map_err(|e| {
+            FixMeLaterError::K(
+                ErrKind::Io,
+                format!("Can't open the history database {}: {}", path.display(), e),
+            )
+        })?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(SqliteHistory {
+            path: path.to_string_lossy().to_string(),
+            connection,
+        })
+    }
+
+    fn parse(&self, id: i64, json: &str) -> Result<HistoryEntry, FixMeLaterError> {
+        serde_json::from_str(json).map_err(|e| {
+            FixMeLaterError::S(format!(
+                "Error in history database {} in entry {}: {}",
+                self.path, id, e
+            ))
+        })
+    }
+}
+
+/// The stored times are whole seconds, `time < whole_seconds_before(t)` is the same as `time < t`
+fn whole_seconds_before(t: DateTime<Utc>) -> i64 {
+    t.timestamp() + i64::from(t.timestamp_subsec_nanos() > 0)
+}
+
+impl HistoryStore for SqliteHistory {
+    fn append(&self, entry: &HistoryEntry) -> Result<(), FixMeLaterError> {
+        debug!("Appending to history {}", self.path);
+        let kind = serde_json::to_value(entry)?["type"].take();
+        self.connection.execute(
+            "INSERT INTO history (time, type, start, task, entry) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                entry.time.timestamp(),
+                kind.as_str().unwrap_or_default(),
+                entry.start().timestamp(),
+                entry.task(),
+                serde_json::to_string(entry)?,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// All in one transaction, one per entry would take ages for a long history
+    fn append_all(&self, entries: &[HistoryEntry]) -> Result<(), FixMeLaterError> {
+        let transaction = self.connection.unchecked_transaction()?;
+        for entry in entries {
+            self.append(entry)?;
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn read(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, FixMeLaterError> {
+        let mut conditions = vec![];
+        let mut values = vec![];
+        if let Some(from) = filter.from {
+            conditions.push("start >= ?");
+            values.push(Value::Integer(from.timestamp()));
+        }
+        if let Some(to) = filter.to {
+            // the rest of the second is left to `matches` below
+            conditions.push("start <= ?");
+            values.push(Value::Integer(to.timestamp()));
+        }
+        if let Some(task) = &filter.task {
+            conditions.push("task = ?");
+            values.push(Value::Text(task.clone()));
+        }
+        let mut sql = "SELECT id, entry FROM history".to_string();
+        if !conditions.is_empty() {
+            sql += " WHERE ";
+            sql += &conditions.join(" AND ");
+        }
+        sql += " ORDER BY id";
+        let mut statement = self.connection.prepare(&sql)?;
+        let rows = statement.query_map(params_from_iter(values), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut entries = vec![];
+        for row in rows {
+            let (id, json) = row?;
+            let entry = self.parse(id, &json)?;
+            if filter.matches(&entry) {
+                entries.push(entry);
+            }
+        }
+        Ok(entries)
+    }
+
+    fn remove_sessions(&self, start: DateTime<Utc>) -> Result<(), FixMeLaterError> {
+        let removed = self.connection.execute(
+            "DELETE FROM history WHERE type = 'session' AND start = ?1",
+            params![start.timestamp()],
+        )?;
+        if removed > 0 {
+            debug!("Removing {} sessions from the history", removed);
+        }
+        Ok(())
+    }
+
+    fn prune(
+        &self,
+        remove_before: Option<DateTime<Utc>>,
+        compact_before: Option<DateTime<Utc>>,
+        dry_run: bool,
+    ) -> Result<Pruned, FixMeLaterError> {
+        let transaction = self.connection.unchecked_transaction()?;
+        let removed_before = remove_before.map_or(i64::MIN, whole_seconds_before);
+        let mut pruned = Pruned {
+            removed: transaction.query_row(
+                "SELECT COUNT(*) FROM history WHERE time < ?1",
+                params![removed_before],
+                |row| row.get(0),
+            )?,
+            compacted: 0,
+        };
+        if let Some(before) = compact_before {
+            let mut statement = transaction.prepare(
+                "SELECT id, entry FROM history WHERE type = 'session' AND time >= ?1 AND time < ?2",
+            )?;
+            let rows = statement
+                .query_map(
+                    params![removed_before, whole_seconds_before(before)],
+                    |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+                )?
+                .collect::<Result<Vec<_>, _>>()?;
+            for (id, json) in rows {
+                let mut entry = self.parse(id, &json)?;
+                let HistoryEvent::Session { pomodoro, .. } = &mut entry.event else {
+                    continue;
+                };
+                if !pomodoro.compact() {
+                    continue;
+                }
+                pruned.compacted += 1;
+                if !dry_run {
+                    transaction.execute(
+                        "UPDATE history SET entry = ?1 WHERE id = ?2",
+                        params![serde_json::to_string(&entry)?, id],
+                    )?;
+                }
+            }
+        }
+        if !dry_run && (pruned.removed > 0 || pruned.compacted > 0) {
+            debug!(
+                "Removing {} entries and compacting {} sessions of the history",
+                pruned.removed, pruned.compacted
+            );
+            transaction.execute(
+                "DELETE FROM history WHERE time < ?1",
+                params![removed_before],
+            )?;
+            transaction.commit()?;
+        }
+        Ok(pruned)
+    }
+
+    fn is_empty(&self) -> Result<bool, FixMeLaterError> {
+        let count: i64 = self
+            .connection
+            .query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))?;
+        Ok(count == 0)
+    }
+
+    fn location(&self) -> String {
+        self.path.clone()
+    }
+}
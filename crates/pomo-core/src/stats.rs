@@ -0,0 +1,394 @@
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::history::{HistoryEntry, HistoryEvent, InterruptionKind, Session};
+use crate::pomo::{PomodoroState, TransitionTrigger};
+use crate::query::{query, GroupBy};
+
+const POINTS_WORK: i64 = 10;
+const POINTS_BREAK: i64 = 5;
+const POINTS_SKIP: i64 = -5;
+const POINTS_OVERRIDE: i64 = -10;
+/// Points needed for every level
+const LEVEL_POINTS: i64 = 100;
+
+/// Totals over the whole history
+pub struct Stats {
+    pub sessions: usize,
+    pub focus: Duration,
+    pub work_sections: u32,
+    pub breaks: u32,
+    pub skips: u32,
+    pub overrides: u32,
+    pub internal_interruptions: u32,
+    pub external_interruptions: u32,
+}
+
+impl Stats {
+    pub fn new(entries: &[HistoryEntry], sessions: &[Session]) -> Stats {
+        let mut stats = Stats {
+            sessions: sessions.len(),
+            focus: Duration::zero(),
+            work_sections: 0,
+            breaks: 0,
+            skips: 0,
+            overrides: 0,
+            internal_interruptions: 0,
+            external_interruptions: 0,
+        };
+        for session in sessions {
+            let pomo = session.pomodoro;
+            stats.focus += pomo.work_time(session.end);
+            stats.work_sections += pomo.completed_work_sections(session.end);
+            stats.breaks += pomo.completed_sections(session.end, PomodoroState::Break);
+            stats.skips += pomo
+                .transitions
+                .iter()
+                .filter(|t| matches!(t.trigger, TransitionTrigger::Skip))
+                .count() as u32;
+        }
+        stats.overrides = entries
+            .iter()
+            .filter(|e| matches!(e.event, HistoryEvent::StrictOverride { .. }))
+            .count() as u32;
+        // interruptions count for the sessions they happened in
+        let starts: BTreeSet<_> = sessions.iter().map(|s| s.pomodoro.start).collect();
+        for entry in entries {
+            let HistoryEvent::Interruption {
+                kind, pomo_start, ..
+            } = entry.event
+            else {
+                continue;
+            };
+            if !starts.contains(&pomo_start) {
+                continue;
+            }
+            match kind {
+                InterruptionKind::Internal => stats.internal_interruptions += 1,
+                InterruptionKind::External => stats.external_interruptions += 1,
+            }
+        }
+        stats
+    }
+
+    /// Finished work sections and breaks earn points, skips and strict mode overrides cost points
+    pub fn points(&self) -> i64 {
+        self.work_sections as i64 * POINTS_WORK
+            + self.breaks as i64 * POINTS_BREAK
+            + self.skips as i64 * POINTS_SKIP
+            + self.overrides as i64 * POINTS_OVERRIDE
+    }
+
+    pub fn level(&self) -> i64 {
+        self.points().max(0) / LEVEL_POINTS + 1
+    }
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Sessions       {}", self.sessions)?;
+        writeln!(
+            f,
+            "Focus time     {}h {:02}m",
+            self.focus.num_hours(),
+            self.focus.num_minutes() % 60
+        )?;
+        writeln!(f, "Work sections  {}", self.work_sections)?;
+        writeln!(f, "Breaks taken   {}", self.breaks)?;
+        writeln!(f, "Skips          {}", self.skips)?;
+        writeln!(
+            f,
+            "Interruptions  {} internal, {} external",
+            self.internal_interruptions, self.external_interruptions
+        )
+    }
+}
+
+/// Focus time of every day from `from` to `to` (both inclusive, local time), days without
+/// focus time are included with zero
+pub fn daily_focus(
+    sessions: &[Session],
+    from: NaiveDate,
+    to: NaiveDate,
+) -> Vec<(NaiveDate, Duration)> {
+    let mut days: Vec<(NaiveDate, Duration)> = from
+        .iter_days()
+        .take_while(|d| *d <= to)
+        .map(|d| (d, Duration::zero()))
+        .collect();
+    for session in sessions {
+        let day = session.pomodoro.start.with_timezone(&Local).date_naive();
+        if day < from || day > to {
+            continue;
+        }
+        days[(day - from).num_days() as usize].1 += session.pomodoro.work_time(session.end);
+    }
+    days
+}
+
+/// Shades of the cells of the heatmap, from no focus time to the most of all days
+const SHADES: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// Renders a grid with one cell per day of the last `weeks` weeks up to `today`, one row per
+/// weekday, shaded by the focus time relative to the best day
+pub fn heatmap(sessions: &[Session], today: NaiveDate, weeks: u32) -> String {
+    let first = today.week(Weekday::Mon).first_day() - Duration::weeks(weeks as i64 - 1);
+    let days = daily_focus(sessions, first, today);
+    let max = days.iter().map(|d| d.1).max().unwrap_or(Duration::zero());
+    let shade = |focus: Duration| {
+        if focus <= Duration::zero() {
+            return SHADES[0];
+        }
+        let level = focus.num_seconds() * (SHADES.len() as i64 - 1) / max.num_seconds().max(1);
+        SHADES[(level as usize).clamp(1, SHADES.len() - 1)]
+    };
+
+    // the name of a month is written above its first week, or later if the previous name is
+    // still in the way
+    let mut months = String::new();
+    let mut month = None;
+    for week in 0..weeks as i64 {
+        let monday = first + Duration::weeks(week);
+        let column = 4 + week as usize * 2;
+        if month == Some(monday.month()) || months.chars().count() > column {
+            continue;
+        }
+        months += &" ".repeat(column - months.chars().count());
+        months += &monday.format("%b").to_string();
+        month = Some(monday.month());
+    }
+    let mut grid = format!("{}\n", months);
+    for weekday in 0..7 {
+        let label = match weekday {
+            0 => "Mon ",
+            2 => "Wed ",
+            4 => "Fri ",
+            _ => "    ",
+        };
+        grid += label;
+        let cells: Vec<String> = days
+            .iter()
+            .skip(weekday)
+            .step_by(7)
+            .map(|(_, focus)| shade(*focus).to_string())
+            .collect();
+        grid += &cells.join(" ");
+        grid += "\n";
+    }
+    grid += &format!(
+        "\n    less {} more, most {}h {:02}m\n",
+        SHADES.iter().collect::<String>(),
+        max.num_hours(),
+        max.num_minutes() % 60
+    );
+    grid
+}
+
+const SPARKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// One character per day of the last `days` days up to `today`, higher for more focus time
+pub fn sparkline(sessions: &[Session], today: NaiveDate, days: u32) -> String {
+    let first = today - Duration::days(days as i64 - 1);
+    let days = daily_focus(sessions, first, today);
+    let max = days.iter().map(|d| d.1).max().unwrap_or(Duration::zero());
+    days.iter()
+        .map(|(_, focus)| {
+            let level = focus.num_seconds() * (SPARKS.len() as i64 - 1) / max.num_seconds().max(1);
+            SPARKS[level.clamp(0, SPARKS.len() as i64 - 1) as usize]
+        })
+        .collect()
+}
+
+/// Estimated and finished work sections of a task
+#[derive(Serialize)]
+pub struct Estimate {
+    pub task: String,
+    pub estimated: u32,
+    pub actual: u32,
+}
+
+/// The latest estimate of every task compared with the work sections finished in `sessions`
+pub fn estimates(entries: &[HistoryEntry], sessions: &[Session]) -> Vec<Estimate> {
+    let mut latest: BTreeMap<&str, u32> = BTreeMap::new();
+    for entry in entries {
+        if let HistoryEvent::Estimate {
+            task,
+            work_sections,
+        } = &entry.event
+        {
+            latest.insert(task, *work_sections);
+        }
+    }
+    let tasks = query(sessions, None, None, GroupBy::Task).groups;
+    latest
+        .into_iter()
+        .map(|(task, estimated)| Estimate {
+            task: task.to_string(),
+            estimated,
+            actual: tasks
+                .iter()
+                .find(|g| g.key.as_deref() == Some(task))
+                .map_or(0, |g| g.work_sections),
+        })
+        .collect()
+}
+
+/// How close the estimates came on average, 100% if every task took exactly as many work
+/// sections as estimated
+pub fn estimate_accuracy(estimates: &[Estimate]) -> Option<f64> {
+    let ratios: Vec<f64> = estimates
+        .iter()
+        .filter(|e| e.estimated > 0 && e.actual > 0)
+        .map(|e| e.estimated.min(e.actual) as f64 / e.estimated.max(e.actual) as f64)
+        .collect();
+    if ratios.is_empty() {
+        return None;
+    }
+    Some(ratios.iter().sum::<f64>() / ratios.len() as f64 * 100.0)
+}
+
+/// Renders the estimates with the difference to the finished work sections
+pub fn estimates_report(estimates: &[Estimate]) -> String {
+    let mut report = String::new();
+    for e in estimates {
+        report += &format!(
+            "{:<25} {:>3} estimated  {:>3} done  {:>+4}\n",
+            e.task,
+            e.estimated,
+            e.actual,
+            e.actual as i64 - e.estimated as i64
+        );
+    }
+    if let Some(accuracy) = estimate_accuracy(estimates) {
+        report += &format!("Estimate accuracy {:.0}%\n", accuracy);
+    }
+    report
+}
+
+/// Progress toward the daily goal of work sections
+pub struct GoalProgress {
+    pub goal: u32,
+    /// work sections finished today
+    pub today: u32,
+    /// days in a row the goal was met, up to today or yesterday
+    pub streak: u32,
+    pub longest_streak: u32,
+}
+
+impl GoalProgress {
+    pub fn new(sessions: &[Session], goal: u32, today: NaiveDate) -> GoalProgress {
+        let days = query(sessions, None, Some(today), GroupBy::Day).groups;
+        let met: BTreeSet<NaiveDate> = days
+            .iter()
+            .filter(|g| g.work_sections >= goal)
+            .filter_map(|g| g.key.as_ref()?.parse().ok())
+            .collect();
+        let today_sections = days
+            .iter()
+            .find(|g| g.key == Some(today.to_string()))
+            .map_or(0, |g| g.work_sections);
+
+        let mut longest_streak = 0;
+        let mut run = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for day in met.iter() {
+            run = match previous {
+                Some(p) if p.succ_opt() == Some(*day) => run + 1,
+                _ => 1,
+            };
+            longest_streak = longest_streak.max(run);
+            previous = Some(*day);
+        }
+        // the streak isn't broken before the end of the day
+        let mut day = if met.contains(&today) {
+            Some(today)
+        } else {
+            today.pred_opt()
+        };
+        let mut streak = 0;
+        while let Some(d) = day.filter(|d| met.contains(d)) {
+            streak += 1;
+            day = d.pred_opt();
+        }
+        GoalProgress {
+            goal,
+            today: today_sections,
+            streak,
+            longest_streak,
+        }
+    }
+
+    pub fn reached(&self) -> bool {
+        self.today >= self.goal
+    }
+}
+
+impl std::fmt::Display for GoalProgress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Daily goal     {}/{} today", self.today, self.goal)?;
+        writeln!(
+            f,
+            "Streak         {} days, longest {}",
+            self.streak, self.longest_streak
+        )
+    }
+}
+
+/// The totals, the focus time per day, tag and task, the estimates and the daily goal as JSON.
+/// Like the output of `pomo history query`, fields are only ever added.
+pub fn stats_json(
+    stats: &Stats,
+    sessions: &[Session],
+    estimates: &[Estimate],
+    goal: Option<&GoalProgress>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "version": 1,
+        "totals": {
+            "sessions": stats.sessions,
+            "focus_seconds": stats.focus.num_seconds(),
+            "work_sections": stats.work_sections,
+            "breaks": stats.breaks,
+            "skips": stats.skips,
+            "strict_overrides": stats.overrides,
+            "interruptions": {
+                "internal": stats.internal_interruptions,
+                "external": stats.external_interruptions,
+            },
+            "points": stats.points(),
+            "level": stats.level(),
+        },
+        "days": query(sessions, None, None, GroupBy::Day).groups,
+        "tags": query(sessions, None, None, GroupBy::Tag).groups,
+        "tasks": query(sessions, None, None, GroupBy::Task).groups,
+        "estimates": estimates,
+        "daily_goal": goal.map(|g| serde_json::json!({
+            "goal": g.goal,
+            "today": g.today,
+            "streak": g.streak,
+            "longest_streak": g.longest_streak,
+        })),
+    })
+}
+
+/// Renders the points with how they were earned
+pub fn points_report(stats: &Stats) -> String {
+    let points = stats.points();
+    let mut report = format!(
+        "Level {} with {} points, {} points to the next level\n\n",
+        stats.level(),
+        points,
+        LEVEL_POINTS - points.max(0) % LEVEL_POINTS
+    );
+    let lines = [
+        ("work sections", stats.work_sections, POINTS_WORK),
+        ("breaks taken", stats.breaks, POINTS_BREAK),
+        ("skips", stats.skips, POINTS_SKIP),
+        ("strict mode overrides", stats.overrides, POINTS_OVERRIDE),
+    ];
+    for (name, count, each) in lines {
+        report += &format!("{:>5} {:<22} {:>+6}\n", count, name, count as i64 * each);
+    }
+    report
+}
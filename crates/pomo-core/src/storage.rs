@@ -0,0 +1,621 @@
+
+use notify::{RecursiveMode, Event, EventKind, Config, RecommendedWatcher, Watcher};
+
+use crate::util::FixMeLaterError;
+use crate::util::ErrKind;
+use crate::util::hostname;
+use directories::ProjectDirs;
+use tracing::{debug, trace};
+use crate::config::{load_config, HistoryBackend, SyncConfig};
+use crate::history::{HistoryEntry, HistoryEvent};
+use crate::pomo::Pomodoro;
+use chrono::{DateTime, Utc};
+use std::env;
+use std::fs;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::sync::OnceLock;
+
+/// Number of entries after which the journal of a device is started over
+const JOURNAL_LENGTH: usize = 100;
+
+/// Number of previous states of the current pomo that can be restored with `undo_current_pomo`
+const UNDO_LENGTH: usize = 10;
+
+/// Path of the current pomo file set with `--state-file`
+static STATE_FILE: OnceLock<String> = OnceLock::new();
+
+/// Uses `path` as the current pomo file instead of the default location
+pub fn set_state_file(path: &str) {
+    let path = absolute_path(path);
+    // commands started by pomo, e.g. from integrations, use the same file
+    env::set_var("POMO_STATE_FILE", &path);
+    let _ = STATE_FILE.set(path);
+}
+
+fn absolute_path(path: &str) -> String {
+    let path = shellexpand::tilde(path).to_string();
+    match std::path::absolute(&path) {
+        Ok(p) => p.to_string_lossy().to_string(),
+        Err(_) => path,
+    }
+}
+
+/// Folder of the current pomo and the history, `~/.local/state/pomocl` on Linux, the local
+/// application data folder on macOS and Windows
+pub(crate) fn state_dir() -> String {
+    match ProjectDirs::from("", "", "pomocl") {
+        Some(dirs) => dirs
+            .state_dir()
+            .unwrap_or(dirs.data_local_dir())
+            .to_string_lossy()
+            .to_string(),
+        None => "~/.local/state/pomocl".to_string(),
+    }
+}
+
+/// Path of a file in the state folder, e.g. for integrations that clean up after a crash
+pub fn state_path(name: &str) -> PathBuf {
+    Path::new(&state_dir()).join(name)
+}
+
+fn history_file() -> String {
+    Path::new(&state_dir())
+        .join("history")
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Path of the current pomo file: the one set with `set_state_file`, `$POMO_STATE_FILE`, or the
+/// default location namespaced by the login session if `per_session` is configured
+pub fn current_file() -> Result<String, FixMeLaterError> {
+    if let Some(path) = STATE_FILE.get() {
+        return Ok(path.clone());
+    }
+    if let Ok(path) = env::var("POMO_STATE_FILE") {
+        if !path.is_empty() {
+            return Ok(absolute_path(&path));
+        }
+    }
+    let default = Path::new(&state_dir())
+        .join("current_pomo")
+        .to_string_lossy()
+        .to_string();
+    if load_config()?.per_session {
+        if let Ok(id) = env::var("XDG_SESSION_ID") {
+            if !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Ok(format!("{}.{}", default, id));
+            }
+        }
+    }
+    Ok(default)
+}
+
+/// Takes the lock of the watcher that sends the notifications of the current pomo, the other
+/// watchers of the same pomo only display it. `None` while another watcher holds the lock, it is
+/// released when the file is closed.
+pub fn try_lock_notifier() -> Result<Option<File>, FixMeLaterError> {
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(format!("{}.notifier", current_file()?))?;
+    match file.try_lock() {
+        Ok(()) => Ok(Some(file)),
+        Err(fs::TryLockError::WouldBlock) => Ok(None),
+        Err(fs::TryLockError::Error(e)) => Err(e.into()),
+    }
+}
+
+pub fn current_pomo() -> Result<Pomodoro, FixMeLaterError> {
+    let no_pomo = FixMeLaterError::K(
+        ErrKind::NoActivePomo,
+        "No pomo has been started yet, start one with 'pomo start'".to_string(),
+    );
+    let mut pomo = match stored_pomo(&load_config()?.sync)? {
+        Some(pomo) => pomo,
+        None => return Err(no_pomo),
+    };
+    debug!("Read revision {} of the current pomo", pomo.revision);
+    if let Some(offset) = pomo.reconcile_clock(Utc::now()) {
+        debug!(
+            "The clock was changed by {}s since the last write",
+            offset.num_seconds()
+        );
+    }
+    end_expired_pause(&mut pomo)?;
+    Ok(pomo)
+}
+
+/// The current pomo as it is stored, without ending an expired pause
+fn stored_pomo(sync: &SyncConfig) -> Result<Option<Pomodoro>, FixMeLaterError> {
+    if sync.enabled {
+        return Ok(newest_journal_entry(&current_file()?));
+    }
+    let path = shellexpand::tilde(&current_file()?).to_string();
+    if !Path::new(&path).exists() {
+        return Ok(None);
+    }
+    let file = open_file(&current_file()?, FileMode::Read)?;
+    Ok(Some(serde_json::from_reader(&file)?))
+}
+
+/// In sync mode every device only writes its own journal next to the current pomo file, so
+/// syncing the folder never causes conflicts. The journals are merged when they are read.
+fn journal_file(current: &str, config: &SyncConfig) -> String {
+    let device = config.device.clone().unwrap_or_else(hostname);
+    let device: String = device
+        .trim()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+    let device = if device.is_empty() {
+        "default".to_string()
+    } else {
+        device
+    };
+    format!("{}.journal.{}", current, device)
+}
+
+/// The newest state in the journals of all devices: the highest revision, or the latest
+/// modification if two devices wrote the same revision
+fn newest_journal_entry(current: &str) -> Option<Pomodoro> {
+    let path = shellexpand::tilde(current).to_string();
+    let path = Path::new(&path);
+    let prefix = format!("{}.journal.", path.file_name()?.to_string_lossy());
+    let mut newest: Option<Pomodoro> = None;
+    for entry in fs::read_dir(path.parent()?).ok()?.flatten() {
+        if !entry.file_name().to_string_lossy().starts_with(&prefix) {
+            continue;
+        }
+        let content = fs::read_to_string(entry.path()).unwrap_or_default();
+        // a line that is still being synced might be incomplete, use the last complete one
+        let last = content
+            .lines()
+            .rev()
+            .find_map(|l| serde_json::from_str::<Pomodoro>(l).ok());
+        if let Some(pomo) = last {
+            trace!("{:?} is at revision {}", entry.path(), pomo.revision);
+            if newest
+                .as_ref()
+                .is_none_or(|n| (pomo.revision, pomo.modified) > (n.revision, n.modified))
+            {
+                newest = Some(pomo);
+            }
+        }
+    }
+    newest
+}
+
+/// Appends the state to the journal of this device, which is started over once it gets long
+fn append_journal(file: &str, pomo: &Pomodoro) -> Result<(), FixMeLaterError> {
+    let path = shellexpand::tilde(file).to_string();
+    let lines = fs::read_to_string(&path).map_or(0, |c| c.lines().count());
+    let mode = if lines >= JOURNAL_LENGTH {
+        debug!("Starting {} over after {} entries", file, lines);
+        FileMode::Write
+    } else {
+        FileMode::Append
+    };
+    let mut file = open_file(file, mode)?;
+    let mut line = serde_json::to_string(pomo)?;
+    line.push('\n');
+    file.write_all(line.as_bytes())?;
+    Ok(())
+}
+
+/// Saves the end of a pause that reached the maximum pause, so it is recorded once
+fn end_expired_pause(pomo: &mut Pomodoro) -> Result<(), FixMeLaterError> {
+    let pause_start = match (pomo.pause_started, pomo.max_pause) {
+        (Some(p), Some(_)) => p,
+        _ => return Ok(()),
+    };
+    let end = match pomo.end_expired_pause(Utc::now()) {
+        Some(end) => end,
+        None => return Ok(()),
+    };
+    append_history(HistoryEntry::new(
+        end,
+        HistoryEvent::PauseExpired {
+            duration: end - pause_start,
+            stopped: !pomo.active,
+        },
+    ))?;
+    if !pomo.active {
+        // the abandoned pomo ends where it was paused
+        append_history(HistoryEntry::new(
+            end,
+            HistoryEvent::Session {
+                end: pause_start.min(pomo.end()).max(pomo.start),
+                pomodoro: Box::new(pomo.clone()),
+            },
+        ))?;
+    }
+    write_current_pomo(pomo.clone())
+}
+
+pub fn write_current_pomo(pomo: Pomodoro) -> Result<(), FixMeLaterError> {
+    let config = load_config()?;
+    if let Some(previous) = stored_pomo(&config.sync)? {
+        push_undo(&previous)?;
+    }
+    write_pomo(pomo, &config.sync)
+}
+
+/// Restores the state of the current pomo before the last write and returns it. Sessions in the
+/// history that archived the restored pomo are removed, it gets archived again when it ends.
+pub fn undo_current_pomo() -> Result<Pomodoro, FixMeLaterError> {
+    let config = load_config()?;
+    let file = undo_file()?;
+    let mut lines = read_lines(&file);
+    let mut pomo: Pomodoro = match lines.pop() {
+        Some(line) => serde_json::from_str(&line)?,
+        None => {
+            return Err(FixMeLaterError::K(
+                ErrKind::InvalidState,
+                "Nothing to undo".to_string(),
+            ))
+        }
+    };
+    write_lines(&file, &lines)?;
+    if pomo.active {
+        remove_sessions(&pomo)?;
+    }
+    // the restored state has to be newer than the current one for the sync mode
+    if let Some(current) = stored_pomo(&config.sync)? {
+        pomo.revision = pomo.revision.max(current.revision);
+    }
+    write_pomo(pomo.clone(), &config.sync)?;
+    Ok(pomo)
+}
+
+fn undo_file() -> Result<String, FixMeLaterError> {
+    Ok(format!("{}.undo", current_file()?))
+}
+
+/// Remembers a previous state, only the last `UNDO_LENGTH` states are kept
+fn push_undo(pomo: &Pomodoro) -> Result<(), FixMeLaterError> {
+    let file = undo_file()?;
+    let mut lines = read_lines(&file);
+    lines.push(serde_json::to_string(pomo)?);
+    let skip = lines.len().saturating_sub(UNDO_LENGTH);
+    trace!("Remembering revision {} in {}", pomo.revision, file);
+    write_lines(&file, &lines[skip..])
+}
+
+/// Removes the sessions of the history that archived `pomo`
+fn remove_sessions(pomo: &Pomodoro) -> Result<(), FixMeLaterError> {
+    configured_store()?.remove_sessions(pomo.start)
+}
+
+/// What pruning the history changed
+pub struct Pruned {
+    pub removed: usize,
+    pub compacted: usize,
+}
+
+/// Deletes the entries of the history from before `remove_before` and compacts the sessions from
+/// before `compact_before`, see `Pomodoro::compact`. With `dry_run` the history is left unchanged.
+pub fn prune_history(
+    remove_before: Option<DateTime<Utc>>,
+    compact_before: Option<DateTime<Utc>>,
+    dry_run: bool,
+) -> Result<Pruned, FixMeLaterError> {
+    configured_store()?.prune(remove_before, compact_before, dry_run)
+}
+
+/// The lines of a file, none if it does not exist
+fn read_lines(file: &str) -> Vec<String> {
+    fs::read_to_string(shellexpand::tilde(file).to_string())
+        .map(|c| c.lines().map(|l| l.to_string()).collect())
+        .unwrap_or_default()
+}
+
+fn write_lines(file: &str, lines: &[String]) -> Result<(), FixMeLaterError> {
+    let mut file = open_file(file, FileMode::Write)?;
+    for line in lines {
+        file.write_all(line.as_bytes())?;
+        file.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn write_pomo(mut pomo: Pomodoro, sync: &SyncConfig) -> Result<(), FixMeLaterError> {
+    let now = Utc::now();
+    pomo.reconcile_clock(now);
+    pomo.anchor_clock(now);
+    pomo.revision += 1;
+    pomo.modified = Some(now);
+    if sync.enabled {
+        let journal = journal_file(&current_file()?, sync);
+        debug!("Appending revision {} to {}", pomo.revision, journal);
+        return append_journal(&journal, &pomo);
+    }
+    debug!("Writing revision {} to {}", pomo.revision, current_file()?);
+    let file = open_file(&current_file()?, FileMode::Write)?;
+    serde_json::to_writer_pretty(&file, &pomo)?;
+    Ok(())
+}
+
+/// Which entries of the history to read, all of them by default
+#[derive(Default, Clone)]
+pub struct HistoryFilter {
+    /// entries that started at or after this time
+    pub from: Option<DateTime<Utc>>,
+    /// entries that started before this time
+    pub to: Option<DateTime<Utc>>,
+    /// entries of this task, see `HistoryEntry::task`
+    pub task: Option<String>,
+}
+
+impl HistoryFilter {
+    pub fn matches(&self, entry: &HistoryEntry) -> bool {
+        let start = entry.start();
+        self.from.is_none_or(|from| start >= from)
+            && self.to.is_none_or(|to| start < to)
+            && self
+                .task
+                .as_deref()
+                .is_none_or(|task| entry.task() == Some(task))
+    }
+}
+
+/// Where the history is kept, the json lines file or a SQLite database
+pub trait HistoryStore {
+    fn append(&self, entry: &HistoryEntry) -> Result<(), FixMeLaterError>;
+    fn append_all(&self, entries: &[HistoryEntry]) -> Result<(), FixMeLaterError> {
+        entries.iter().try_for_each(|e| self.append(e))
+    }
+    /// The entries matching `filter`, in the order they were appended
+    fn read(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, FixMeLaterError>;
+    /// Removes the sessions of the pomo that started at `start`
+    fn remove_sessions(&self, start: DateTime<Utc>) -> Result<(), FixMeLaterError>;
+    /// See `prune_history`
+    fn prune(
+        &self,
+        remove_before: Option<DateTime<Utc>>,
+        compact_before: Option<DateTime<Utc>>,
+        dry_run: bool,
+    ) -> Result<Pruned, FixMeLaterError>;
+    fn is_empty(&self) -> Result<bool, FixMeLaterError>;
+    /// Where the store is, for the messages
+    fn location(&self) -> String;
+}
+
+/// The store of `backend`
+pub fn history_store(backend: HistoryBackend) -> Result<Box<dyn HistoryStore>, FixMeLaterError> {
+    match backend {
+        HistoryBackend::File => Ok(Box::new(FileHistory {
+            path: history_file(),
+        })),
+        #[cfg(feature = "sqlite")]
+        HistoryBackend::Sqlite => Ok(Box::new(crate::sqlite::SqliteHistory::open(
+            &state_path("history.sqlite3"),
+        )?)),
+        #[cfg(not(feature = "sqlite"))]
+        HistoryBackend::Sqlite => Err(FixMeLaterError::K(
+            ErrKind::Config,
+            "pomo was built without the sqlite feature, remove 'backend' from the [history] section of the config file".to_string(),
+        )),
+    }
+}
+
+/// The store set with `backend` in the [history] section of the config
+fn configured_store() -> Result<Box<dyn HistoryStore>, FixMeLaterError> {
+    history_store(load_config()?.history.backend)
+}
+
+/// Appends an entry to the history
+pub fn append_history(entry: HistoryEntry) -> Result<(), FixMeLaterError> {
+    configured_store()?.append(&entry)
+}
+
+/// Reads all entries of the history, an empty history if there is none yet
+pub fn read_history() -> Result<Vec<HistoryEntry>, FixMeLaterError> {
+    query_history(&HistoryFilter::default())
+}
+
+/// Reads the entries of the history matching `filter`, the SQLite backend only loads those
+pub fn query_history(filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, FixMeLaterError> {
+    configured_store()?.read(filter)
+}
+
+/// The history file, one json object per line
+struct FileHistory {
+    path: String,
+}
+
+impl HistoryStore for FileHistory {
+    fn append(&self, entry: &HistoryEntry) -> Result<(), FixMeLaterError> {
+        debug!("Appending to history {}", self.path);
+        let mut file = open_file(&self.path, FileMode::Append)?;
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    fn read(&self, filter: &HistoryFilter) -> Result<Vec<HistoryEntry>, FixMeLaterError> {
+        let path = &self.path;
+        if !Path::new(&shellexpand::tilde(path).to_string()).exists() {
+            return Ok(vec![]);
+        }
+        let file = open_file(path, FileMode::Read)?;
+        let mut entries = vec![];
+        for (i, line) in BufReader::new(file).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) if filter.matches(&entry) => entries.push(entry),
+                Ok(_) => (),
+                Err(e) => {
+                    return Err(FixMeLaterError::S(format!(
+                        "Error in history file {} on line {}: {}",
+                        path,
+                        i + 1,
+                        e
+                    )))
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    fn remove_sessions(&self, start: DateTime<Utc>) -> Result<(), FixMeLaterError> {
+        let lines = read_lines(&self.path);
+        let kept: Vec<String> = lines
+            .iter()
+            .filter(|l| {
+                !matches!(
+                    serde_json::from_str::<HistoryEntry>(l),
+                    Ok(HistoryEntry { event: HistoryEvent::Session { pomodoro, .. }, .. })
+                        if pomodoro.start == start
+                )
+            })
+            .cloned()
+            .collect();
+        if kept.len() != lines.len() {
+            debug!(
+                "Removing {} sessions from the history",
+                lines.len() - kept.len()
+            );
+            write_lines(&self.path, &kept)?;
+        }
+        Ok(())
+    }
+
+    fn prune(
+        &self,
+        remove_before: Option<DateTime<Utc>>,
+        compact_before: Option<DateTime<Utc>>,
+        dry_run: bool,
+    ) -> Result<Pruned, FixMeLaterError> {
+        let lines = read_lines(&self.path);
+        let mut pruned = Pruned {
+            removed: 0,
+            compacted: 0,
+        };
+        let mut kept = vec![];
+        for line in lines.iter() {
+            // lines that can't be read are kept as they are
+            let Ok(mut entry) = serde_json::from_str::<HistoryEntry>(line) else {
+                kept.push(line.clone());
+                continue;
+            };
+            if remove_before.is_some_and(|t| entry.time < t) {
+                pruned.removed += 1;
+                continue;
+            }
+            if let HistoryEvent::Session { pomodoro, .. } = &mut entry.event {
+                if compact_before.is_some_and(|t| entry.time < t) && pomodoro.compact() {
+                    pruned.compacted += 1;
+                    kept.push(serde_json::to_string(&entry)?);
+                    continue;
+                }
+            }
+            kept.push(line.clone());
+        }
+        if !dry_run && (pruned.removed > 0 || pruned.compacted > 0) {
+            debug!(
+                "Removing {} entries and compacting {} sessions of the history",
+                pruned.removed, pruned.compacted
+            );
+            write_lines(&self.path, &kept)?;
+        }
+        Ok(pruned)
+    }
+
+    fn is_empty(&self) -> Result<bool, FixMeLaterError> {
+        Ok(read_lines(&self.path).iter().all(|l| l.trim().is_empty()))
+    }
+
+    fn location(&self) -> String {
+        self.path.clone()
+    }
+}
+
+pub fn subscribe_current_pomo() -> Result<(Receiver<Result<Event, notify::Error>>, RecommendedWatcher), FixMeLaterError> {
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    let mut watcher = match notify::RecommendedWatcher::new(tx, Config::default()) {
+        Ok(w) => w,
+        Err(err) => return Err(FixMeLaterError::S(format!("Error when subscribing to pomo file: {:?}", err))),
+    };
+
+    // watch the folder rather than the file, the file can be replaced or not exist yet
+    let file = shellexpand::tilde(&current_file()?).to_string();
+    let folder = parent_folder(&file)?;
+    fs::create_dir_all(folder)?;
+    match watcher.watch(folder, RecursiveMode::NonRecursive) {
+        Ok(_) => (),
+        Err(err) => return Err(FixMeLaterError::S(format!("{}", err))),
+    }
+
+    Ok((rx, watcher))
+}
+
+/// Whether a file system event from `subscribe_current_pomo` changed the current pomo file
+pub fn changes_current_pomo(event: &Event) -> Result<bool, FixMeLaterError> {
+    if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+        return Ok(false);
+    }
+    let file = shellexpand::tilde(&current_file()?).to_string();
+    let Some(name) = Path::new(&file).file_name() else {
+        return Ok(false);
+    };
+    let name = name.to_string_lossy().to_string();
+    // also matches the journals of the sync mode
+    Ok(event.paths.iter().any(|p| {
+        p.file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with(&name))
+    }))
+}
+
+enum FileMode {
+    Read,
+    Write,
+    Append,
+}
+
+/// The folder of `file`, an error for paths like `/` that can't be a file, e.g. from `--state-file`
+fn parent_folder(file: &str) -> Result<&Path, FixMeLaterError> {
+    let path = Path::new(file);
+    match (path.parent(), path.file_name()) {
+        (Some(folder), Some(_)) => Ok(folder),
+        _ => Err(FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            format!("'{}' is not a path to a file", file),
+        )),
+    }
+}
+
+fn open_file(file: &str, mode: FileMode) -> Result<File, FixMeLaterError> {
+    let folder = shellexpand::tilde(&parent_folder(file)?.to_string_lossy()).to_string();
+    let file = shellexpand::tilde(file).to_string();
+
+    if let Err(err) = fs::create_dir_all(&folder) {
+        if err.kind() != ErrorKind::AlreadyExists {
+            return Err(FixMeLaterError::S(format!(
+                "Error creating directory {}: {:?}",
+                folder, err
+            )));
+        }
+    }
+    let f = match mode {
+        FileMode::Read => File::open(&file),
+        FileMode::Write => File::create(&file),
+        FileMode::Append => OpenOptions::new().create(true).append(true).open(&file),
+    };
+    match f {
+        Ok(f) => Ok(f),
+        Err(e) => Err(FixMeLaterError::K(
+            ErrKind::Io,
+            format!("Can't create file {}: {}", file, e),
+        )),
+    }
+}
@@ -0,0 +1,161 @@
+use chrono::serde::{ts_seconds, ts_seconds_option};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::pomo::{CurrentPomoState, Pomodoro};
+use crate::util::{ErrKind, FixMeLaterError};
+
+/// Statuses that were not updated for this long belong to a watch that is gone
+const STALE_AFTER: i64 = 5;
+
+/// What the pomo of a team member is doing, as published by their watch or daemon
+#[derive(Serialize, Deserialize, Clone)]
+pub struct MemberStatus {
+    pub name: String,
+    /// `work`, `break`, `warmup`, `not started`, `done`, `inactive` or `offline` once their watch
+    /// exited
+    pub state: String,
+    #[serde(default)]
+    pub paused: bool,
+    /// end of the current section while it is counting down
+    #[serde(default, with = "ts_seconds_option")]
+    pub until: Option<DateTime<Utc>>,
+    /// only published with `share_task`
+    #[serde(default)]
+    pub task: Option<String>,
+    #[serde(with = "ts_seconds")]
+    pub updated: DateTime<Utc>,
+}
+
+impl MemberStatus {
+    pub fn new(
+        name: &str,
+        pomo: &Pomodoro,
+        state: &CurrentPomoState,
+        now: DateTime<Utc>,
+        share_task: bool,
+    ) -> MemberStatus {
+        let counting_down = !state.pause && !state.open_ended && !state.overtime && !state.waiting;
+        MemberStatus {
+            name: name.to_string(),
+            state: if pomo.active {
+                state.current_state.to_string()
+            } else {
+                "inactive".to_string()
+            },
+            paused: state.pause,
+            until: (pomo.active && counting_down).then(|| now + state.duration),
+            task: pomo.task.clone().filter(|_| share_task && pomo.active),
+            updated: now,
+        }
+    }
+
+    pub fn offline(name: &str, now: DateTime<Utc>) -> MemberStatus {
+        MemberStatus {
+            name: name.to_string(),
+            state: "offline".to_string(),
+            paused: false,
+            until: None,
+            task: None,
+            updated: now,
+        }
+    }
+
+    /// Whether the member is in a work section right now and shouldn't be disturbed
+    pub fn is_focused(&self, now: DateTime<Utc>) -> bool {
+        self.state == "work" && !self.paused && !self.is_stale(now)
+    }
+
+    fn is_stale(&self, now: DateTime<Utc>) -> bool {
+        now - self.updated > Duration::minutes(STALE_AFTER)
+    }
+
+    /// e.g. `alice  work, 12 min left (writing the report)`
+    pub fn render(&self, now: DateTime<Utc>, width: usize) -> String {
+        let mut line = format!("{:width$}  ", self.name, width = width);
+        if self.is_stale(now) {
+            line += "offline";
+            return line;
+        }
+        line += &self.state;
+        if self.paused {
+            line += ", paused";
+        }
+        if let Some(until) = self.until.filter(|u| *u > now) {
+            let minutes = ((until - now).num_seconds() + 59) / 60;
+            line += &format!(", {} min left", minutes);
+        }
+        if let Some(task) = &self.task {
+            line += &format!(" ({})", task);
+        }
+        line
+    }
+}
+
+/// Name of the file of a member in the team folder, characters that don't belong in file names
+/// are replaced
+fn file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || "-_.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}.json", name.trim_start_matches('.'))
+}
+
+/// Writes the status to `<name>.json` in `dir`. The file is replaced at once so readers on other
+/// machines never see half of it.
+pub fn write_member(dir: &Path, status: &MemberStatus) -> Result<(), FixMeLaterError> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(file_name(&status.name));
+    let tmp = dir.join(format!(".{}.tmp", file_name(&status.name)));
+    fs::write(&tmp, serde_json::to_vec(status)?)?;
+    fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+/// The statuses of all members in `dir` sorted by name, files that can't be read are skipped
+pub fn read_members(dir: &Path) -> Result<Vec<MemberStatus>, FixMeLaterError> {
+    let entries = fs::read_dir(dir).map_err(|e| {
+        FixMeLaterError::K(
+            ErrKind::Io,
+            format!("Can't read the team folder {}: {}", dir.display(), e),
+        )
+    })?;
+    let mut members: Vec<MemberStatus> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.extension().is_some_and(|e| e == "json")
+                && !p
+                    .file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with('.'))
+        })
+        .filter_map(|p| fs::read(p).ok())
+        .filter_map(|data| serde_json::from_slice(&data).ok())
+        .collect();
+    members.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(members)
+}
+
+/// One line per member, the focused ones first
+pub fn render_members(members: &[MemberStatus], now: DateTime<Utc>) -> String {
+    let width = members
+        .iter()
+        .map(|m| m.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    let (focused, others): (Vec<_>, Vec<_>) = members.iter().partition(|m| m.is_focused(now));
+    focused
+        .iter()
+        .chain(others.iter())
+        .map(|m| m.render(now, width) + "\n")
+        .collect()
+}
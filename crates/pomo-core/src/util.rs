@@ -0,0 +1,213 @@
+use chrono::{DateTime, Duration, Local, LocalResult, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use std::process::Command;
+use std::{env, fs};
+
+#[derive(Debug)]
+pub enum FixMeLaterError {
+    S(String),
+    /// An error of a kind that scripts might want to tell apart
+    K(ErrKind, String),
+}
+
+/// Machine readable category of an error, also decides the exit code
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrKind {
+    Other,
+    InvalidInput,
+    NoActivePomo,
+    InvalidState,
+    StrictMode,
+    Config,
+    Io,
+}
+
+impl ErrKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Self::Other => 1,
+            // same as clap for invalid arguments
+            Self::InvalidInput => 2,
+            Self::NoActivePomo => 3,
+            Self::InvalidState => 4,
+            Self::StrictMode => 5,
+            Self::Config => 6,
+            Self::Io => 7,
+        }
+    }
+}
+
+impl FixMeLaterError {
+    pub fn kind(&self) -> ErrKind {
+        match self {
+            Self::S(_) => ErrKind::Other,
+            Self::K(kind, _) => *kind,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Self::S(message) | Self::K(_, message) => message,
+        }
+    }
+}
+
+/// Parses a point in time relative to `now`: `+90m` or `+2h` from now, a time of the day like
+/// `17:30`, `noon` or `midnight`, or a full date and time like `2024-05-01 17:30`.
+/// A time of the day that already passed today is tomorrow with `roll_over`, an error otherwise.
+pub fn parse_time_string(
+    s: &str,
+    now: DateTime<Utc>,
+    roll_over: bool,
+) -> Result<DateTime<Utc>, FixMeLaterError> {
+    let s = s.trim();
+    if s == "now" {
+        return Ok(now);
+    }
+    if let Some(duration) = s.strip_prefix('+') {
+        return now
+            .checked_add_signed(parse_duration(duration)?)
+            .ok_or_else(|| {
+                FixMeLaterError::K(
+                    ErrKind::InvalidInput,
+                    format!("The time '{}' is too far from now", s),
+                )
+            });
+    }
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M"] {
+        if let Ok(date_time) = NaiveDateTime::parse_from_str(s, format) {
+            return local_to_utc(&date_time);
+        }
+    }
+    let time = match s {
+        "noon" => NaiveTime::from_hms_opt(12, 0, 0).unwrap(),
+        "midnight" => NaiveTime::MIN,
+        _ => NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| {
+            FixMeLaterError::K(
+                ErrKind::InvalidInput,
+                format!(
+                    "Invalid time '{}', use e.g. 17:30, +90m, noon, midnight or 2024-05-01 17:30",
+                    s
+                ),
+            )
+        })?,
+    };
+    let today = now.with_timezone(&Local).date_naive();
+    let date_time = local_to_utc(&NaiveDateTime::new(today, time))?;
+    if date_time > now {
+        return Ok(date_time);
+    }
+    if !roll_over {
+        return Err(FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            format!(
+                "{} has already passed today, use a time from now like +2h or a date like {} {}",
+                s,
+                today.succ_opt().unwrap_or(today),
+                time.format("%H:%M")
+            ),
+        ));
+    }
+    local_to_utc(&NaiveDateTime::new(today.succ_opt().unwrap_or(today), time))
+}
+
+fn local_to_utc(date_time: &NaiveDateTime) -> Result<DateTime<Utc>, FixMeLaterError> {
+    match Local.from_local_datetime(date_time) {
+        LocalResult::None => Err(FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            "Could not find datetime".to_string(),
+        )),
+        LocalResult::Single(s) => Ok(s.with_timezone(&Utc)),
+        LocalResult::Ambiguous(_, _) => Err(FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            "No unambiguous datetime".to_string(),
+        )),
+    }
+}
+
+/// Parses durations like `15`, `15m`, `90s` or `1h30m`, a plain number is minutes
+pub fn parse_duration(s: &str) -> Result<Duration, FixMeLaterError> {
+    let invalid = || {
+        FixMeLaterError::K(
+            ErrKind::InvalidInput,
+            format!("Invalid duration '{}', use e.g. 15m, 90s or 1h30m", s),
+        )
+    };
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(invalid());
+    }
+    if let Ok(minutes) = s.parse::<i64>() {
+        return Duration::try_minutes(minutes).ok_or_else(invalid);
+    }
+    let mut total = Duration::zero();
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .ok_or_else(invalid)?;
+        let value: i64 = rest[..digits].parse().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_len] {
+            "h" => 3600,
+            "m" | "min" => 60,
+            "s" => 1,
+            _ => return Err(invalid()),
+        };
+        let part = value
+            .checked_mul(seconds)
+            .and_then(Duration::try_seconds)
+            .ok_or_else(invalid)?;
+        total = total.checked_add(&part).ok_or_else(invalid)?;
+        rest = &rest[unit_len..];
+    }
+    Ok(total)
+}
+
+impl From<std::io::Error> for FixMeLaterError {
+    fn from(value: std::io::Error) -> Self {
+        FixMeLaterError::K(ErrKind::Io, format!("{:?}", value))
+    }
+}
+
+impl From<serde_json::Error> for FixMeLaterError {
+    fn from(value: serde_json::Error) -> Self {
+        FixMeLaterError::S(format!("{:?}", value))
+    }
+}
+
+/// Name of this machine, empty if it can't be found
+pub fn hostname() -> String {
+    fs::read_to_string("/etc/hostname")
+        .ok()
+        .or_else(|| env::var("HOSTNAME").ok())
+        .or_else(|| env::var("COMPUTERNAME").ok())
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// Id of the current boot and the time since boot including suspend, only known on Linux
+pub fn boot_clock() -> Option<(String, Duration)> {
+    let boot_id = fs::read_to_string("/proc/sys/kernel/random/boot_id").ok()?;
+    let uptime = fs::read_to_string("/proc/uptime").ok()?;
+    let seconds: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+    Some((
+        boot_id.trim().to_string(),
+        Duration::milliseconds((seconds * 1000.0) as i64),
+    ))
+}
+
+/// Runs `command` with `sh` on unix and with `cmd` on Windows
+pub fn shell_command(command: &str) -> Command {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut cmd = Command::new(shell);
+    cmd.arg(flag).arg(command);
+    cmd
+}
@@ -0,0 +1,144 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::Integration;
+use pomo_core::config::BlockConfig;
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use pomo_core::util::shell_command;
+use tracing::{debug, info, warn};
+
+const BEGIN: &str = "# pomo focus guard begin";
+const END: &str = "# pomo focus guard end";
+
+/// Blocks the sites of the config during work sections, by default with entries in the hosts
+/// file, and removes the block during breaks and when the pomo stops
+pub struct Block {
+    config: BlockConfig,
+    blocked: bool,
+}
+
+impl Block {
+    pub fn new(config: BlockConfig) -> Block {
+        let mut block = Block {
+            config,
+            blocked: false,
+        };
+        // entries left over from a run that was killed
+        block.set(false);
+        block
+    }
+
+    fn set(&mut self, blocked: bool) {
+        info!(
+            "{} the sites of the blocklist",
+            if blocked { "Blocking" } else { "Unblocking" }
+        );
+        let command = if blocked {
+            &self.config.command
+        } else {
+            &self.config.unblock_command
+        };
+        match command {
+            Some(command) => {
+                let status = shell_command(command)
+                    .env("POMO_BLOCKLIST", self.config.sites.join(" "))
+                    .status();
+                if !status.is_ok_and(|s| s.success()) {
+                    warn!("Could not run '{}'", command);
+                }
+            }
+            None if self.config.command.is_none() => self.write_hosts(blocked),
+            None => (),
+        }
+        self.blocked = blocked;
+    }
+
+    fn write_hosts(&self, blocked: bool) {
+        let path = self.config.hosts_file();
+        let hosts = match fs::read_to_string(path) {
+            Ok(hosts) => hosts,
+            Err(e) => {
+                warn!("Could not read {}: {}", path, e);
+                return;
+            }
+        };
+        let mut lines = vec![];
+        // the lines since the last BEGIN, only dropped once their END is found
+        let mut guarded: Option<Vec<String>> = None;
+        for line in hosts.lines() {
+            match line {
+                BEGIN => {
+                    // a BEGIN without an END is kept like any other line
+                    lines.extend(guarded.replace(vec![line.to_string()]).unwrap_or_default());
+                }
+                END if guarded.is_some() => guarded = None,
+                _ => match guarded.as_mut() {
+                    Some(block) => block.push(line.to_string()),
+                    None => lines.push(line.to_string()),
+                },
+            }
+        }
+        lines.extend(guarded.unwrap_or_default());
+        if blocked {
+            lines.push(BEGIN.to_string());
+            for site in self.config.sites.iter() {
+                let site = site.trim().trim_start_matches("www.");
+                for host in [site.to_string(), format!("www.{}", site)] {
+                    lines.push(format!("0.0.0.0 {}", host));
+                    lines.push(format!(":: {}", host));
+                }
+            }
+            lines.push(END.to_string());
+        }
+        let new_hosts = lines.join("\n") + "\n";
+        if new_hosts != hosts {
+            if let Err(e) = replace_file(Path::new(path), &new_hosts) {
+                warn!(
+                    "Could not write {}, blocking sites needs write access to it: {}",
+                    path, e
+                );
+            }
+        }
+    }
+}
+
+/// Writes `content` to a file next to `path` and renames it over `path`, so a crash leaves
+/// either the old or the new file. Hosts files that can only be written in place, like the
+/// bind-mounted one in a container, are written in place.
+fn replace_file(path: &Path, content: &str) -> io::Result<()> {
+    let path = fs::canonicalize(path)?;
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".pomo-tmp");
+    let tmp = path.with_file_name(name);
+    let replaced = fs::write(&tmp, content)
+        .and_then(|_| fs::set_permissions(&tmp, fs::metadata(&path)?.permissions()))
+        .and_then(|_| fs::rename(&tmp, &path));
+    if let Err(e) = replaced {
+        let _ = fs::remove_file(&tmp);
+        debug!(
+            "Could not replace {}, writing it in place: {}",
+            path.display(),
+            e
+        );
+        return fs::write(&path, content);
+    }
+    Ok(())
+}
+
+impl Integration for Block {
+    fn transition(&mut self, _from: PomodoroState, _state: &CurrentPomoState) {}
+
+    fn tick(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) {
+        let block = pomo.active && !pomo.unblocked && state.current_state == PomodoroState::Work;
+        if block != self.blocked {
+            self.set(block);
+        }
+    }
+
+    fn cleanup(&mut self) {
+        if self.blocked {
+            self.set(false);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::Integration;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use pomo_core::config::CaldavConfig;
+use pomo_core::ical::{calendar, event_uid, pomo_event};
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use tracing::{debug, info, warn};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Uploads an event for every finished pomo to a CalDAV calendar, and optionally the planned
+/// block when it starts. Both use the same event, so the planned block is replaced by the
+/// session. The requests are sent from a separate thread.
+pub struct Caldav {
+    planned: bool,
+    /// (uid, iCalendar file) to upload
+    sender: Option<Sender<(String, String)>>,
+    worker: Option<JoinHandle<()>>,
+    /// start of the pomo that is running and whether its session was uploaded
+    current: Option<(DateTime<Utc>, bool)>,
+}
+
+impl Caldav {
+    pub fn new(config: CaldavConfig) -> Caldav {
+        let planned = config.planned;
+        let (sender, receiver) = mpsc::channel::<(String, String)>();
+        let worker = thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+            for (uid, event) in receiver {
+                if let Err(e) = upload(&agent, &config, &uid, &event) {
+                    warn!("Could not upload the session to CalDAV: {}", e);
+                }
+            }
+        });
+        Caldav {
+            planned,
+            sender: Some(sender),
+            worker: Some(worker),
+            current: None,
+        }
+    }
+
+    fn send(&self, pomo: &Pomodoro, end: DateTime<Utc>) {
+        if let Some(sender) = &self.sender {
+            let event = calendar(&[pomo_event(pomo, end)]);
+            let _ = sender.send((event_uid(pomo), event));
+        }
+    }
+}
+
+fn upload(
+    agent: &ureq::Agent,
+    config: &CaldavConfig,
+    uid: &str,
+    event: &str,
+) -> Result<(), String> {
+    let url = format!(
+        "{}/{}.ics",
+        config
+            .url
+            .as_deref()
+            .unwrap_or_default()
+            .trim_end_matches('/'),
+        uid
+    );
+    debug!("Uploading {}", url);
+    let mut request = agent
+        .put(&url)
+        .set("Content-Type", "text/calendar; charset=utf-8");
+    if let Some(username) = &config.username {
+        let credentials = format!(
+            "{}:{}",
+            username,
+            config.password.as_deref().unwrap_or_default()
+        );
+        request = request.set(
+            "Authorization",
+            &format!(
+                "Basic {}",
+                base64::engine::general_purpose::STANDARD.encode(credentials)
+            ),
+        );
+    }
+    request.send_string(event).map_err(|e| e.to_string())?;
+    info!("Uploaded {} to CalDAV", uid);
+    Ok(())
+}
+
+impl Integration for Caldav {
+    fn transition(&mut self, _from: PomodoroState, _state: &CurrentPomoState) {}
+
+    fn tick(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) {
+        let finished = !pomo.active || state.current_state == PomodoroState::Done;
+        match self.current {
+            Some((start, uploaded)) if start == pomo.start => {
+                if finished && !uploaded {
+                    self.send(pomo, pomo.ended_at());
+                    self.current = Some((start, true));
+                }
+            }
+            // a pomo that already finished before watch started is not uploaded
+            _ if finished => (),
+            _ => {
+                if self.planned {
+                    self.send(pomo, pomo.end());
+                }
+                self.current = Some((pomo.start, false));
+            }
+        }
+    }
+
+    fn cleanup(&mut self) {
+        // wait until the uploads are done
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
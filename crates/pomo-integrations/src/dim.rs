@@ -0,0 +1,102 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Child, Command};
+
+use crate::Integration;
+use pomo_core::config::{DimBackend, DimConfig};
+use pomo_core::pomo::{CurrentPomoState, PomodoroState};
+use pomo_core::storage::state_path;
+use tracing::{debug, warn};
+
+/// Dims and warms the screen with gammastep or redshift during breaks and resets it afterwards
+pub struct Dim {
+    config: DimConfig,
+    program: &'static str,
+    /// the process keeping the screen dimmed on Wayland, on X11 it exits right away
+    child: Option<Child>,
+    dimmed: bool,
+}
+
+/// Written while the screen is dimmed with the id of the process, so the screen can be reset
+/// after watch or daemon was killed
+fn marker() -> PathBuf {
+    state_path("dim")
+}
+
+impl Dim {
+    pub fn new(config: DimConfig) -> Dim {
+        let program = match config.backend {
+            DimBackend::Gammastep => "gammastep",
+            DimBackend::Redshift => "redshift",
+            DimBackend::Auto => {
+                if Command::new("gammastep").arg("-V").output().is_ok() {
+                    "gammastep"
+                } else {
+                    "redshift"
+                }
+            }
+        };
+        let mut dim = Dim {
+            config,
+            program,
+            child: None,
+            dimmed: false,
+        };
+        if let Ok(pid) = fs::read_to_string(marker()) {
+            debug!("Resetting the screen dimmed by a previous run");
+            let _ = Command::new("kill").arg(pid.trim()).output();
+            dim.dimmed = true;
+            dim.restore();
+        }
+        dim
+    }
+
+    fn dim(&mut self) {
+        let child = Command::new(self.program)
+            .arg("-P")
+            .arg("-O")
+            .arg(self.config.temperature().to_string())
+            .arg("-b")
+            .arg(self.config.brightness().to_string())
+            .spawn();
+        match child {
+            Ok(child) => {
+                if let Err(e) = fs::write(marker(), child.id().to_string()) {
+                    warn!("Could not write {}: {}", marker().display(), e);
+                }
+                self.child = Some(child);
+                self.dimmed = true;
+            }
+            Err(e) => warn!("Could not run {}: {}", self.program, e),
+        }
+    }
+
+    fn restore(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        if let Err(e) = Command::new(self.program).arg("-x").output() {
+            warn!("Could not run {}: {}", self.program, e);
+        }
+        let _ = fs::remove_file(marker());
+        self.dimmed = false;
+    }
+}
+
+impl Integration for Dim {
+    fn transition(&mut self, _from: PomodoroState, state: &CurrentPomoState) {
+        let dim = state.current_state == PomodoroState::Break;
+        if dim && !self.dimmed {
+            self.dim();
+        } else if !dim && self.dimmed {
+            self.restore();
+        }
+    }
+
+    fn cleanup(&mut self) {
+        if self.dimmed {
+            self.restore();
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::io::{self, Read, Write};
+use std::process;
+use std::time::{Duration, Instant};
+
+use crate::Integration;
+use chrono::Utc;
+use pomo_core::config::DiscordConfig;
+use pomo_core::format::{render_template, FormatOptions};
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+/// Time between attempts to reach the Discord client
+const RECONNECT_AFTER: Duration = Duration::from_secs(30);
+const DEFAULT_DETAILS: &str = "{icon} {state} {paused}";
+const DEFAULT_STATE: &str = "{completed}/{total} {task}";
+
+/// Shows the state of the pomo as Rich Presence in the profile of the local Discord client,
+/// with a countdown to the end of the section
+pub struct Discord {
+    config: DiscordConfig,
+    client: Option<Ipc>,
+    last_attempt: Option<Instant>,
+    /// last activity sent without the end of the section
+    last: Option<serde_json::Value>,
+    last_end: Option<i64>,
+}
+
+impl Discord {
+    pub fn new(config: DiscordConfig) -> Discord {
+        Discord {
+            config,
+            client: None,
+            last_attempt: None,
+            last: None,
+            last_end: None,
+        }
+    }
+
+    fn connect(&mut self) {
+        if self.client.is_some()
+            || self
+                .last_attempt
+                .is_some_and(|t| t.elapsed() < RECONNECT_AFTER)
+        {
+            return;
+        }
+        self.last_attempt = Some(Instant::now());
+        let client_id = self.config.client_id.clone().unwrap_or_default();
+        match Ipc::connect(&client_id) {
+            Ok(client) => {
+                info!("Connected to Discord");
+                self.client = Some(client);
+                self.last = None;
+                self.last_end = None;
+            }
+            Err(e) => debug!("Could not connect to Discord: {}", e),
+        }
+    }
+
+    /// The activity for the pomo and the end of the section in unix seconds, `None` clears it
+    fn activity(
+        &self,
+        pomo: &Pomodoro,
+        state: &CurrentPomoState,
+    ) -> (Option<serde_json::Value>, Option<i64>) {
+        if !pomo.active || state.current_state == PomodoroState::Done {
+            return (None, None);
+        }
+        let render = |template: Option<&str>, default| {
+            let text = render_template(
+                template.unwrap_or(default),
+                pomo,
+                state,
+                FormatOptions::default(),
+            );
+            // Discord refuses texts shorter than 2 characters
+            format!(
+                "{:<2}",
+                text.split_whitespace().collect::<Vec<_>>().join(" ")
+            )
+        };
+        let activity = json!({
+            "details": render(self.config.details.as_deref(), DEFAULT_DETAILS),
+            "state": render(self.config.state.as_deref(), DEFAULT_STATE),
+        });
+        let counting_down = !state.pause && !state.open_ended && !state.waiting;
+        let end = counting_down.then(|| (Utc::now() + state.duration).timestamp());
+        (Some(activity), end)
+    }
+
+    fn publish(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) -> io::Result<()> {
+        if self.client.is_none() {
+            return Ok(());
+        }
+        let (activity, end) = self.activity(pomo, state);
+        // the end moves by a second now and then while the time is rounded
+        let end_moved = match (end, self.last_end) {
+            (Some(end), Some(last)) => (end - last).abs() > 2,
+            (end, last) => end != last,
+        };
+        if self.last == activity && !end_moved {
+            return Ok(());
+        }
+        let mut payload = activity.clone();
+        if let (Some(payload), Some(end)) = (payload.as_mut(), end) {
+            payload["timestamps"] = json!({ "end": end });
+        }
+        if let Some(client) = self.client.as_mut() {
+            client.set_activity(payload)?;
+        }
+        self.last = activity;
+        self.last_end = end;
+        Ok(())
+    }
+}
+
+impl Integration for Discord {
+    fn transition(&mut self, _from: PomodoroState, _state: &CurrentPomoState) {}
+
+    fn tick(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) {
+        self.connect();
+        if let Err(e) = self.publish(pomo, state) {
+            warn!("Lost connection to Discord: {}", e);
+            self.client = None;
+        }
+    }
+
+    fn cleanup(&mut self) {
+        if let Some(mut client) = self.client.take() {
+            let _ = client.set_activity(None);
+        }
+    }
+}
+
+/// The local IPC socket of the Discord client, messages are framed with their opcode and
+/// length followed by JSON
+struct Ipc {
+    stream: Box<dyn Stream>,
+    nonce: u64,
+}
+
+trait Stream: Read + Write {}
+impl<T: Read + Write> Stream for T {}
+
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+
+impl Ipc {
+    fn connect(client_id: &str) -> io::Result<Ipc> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "Discord is not running");
+        for i in 0..10 {
+            match open(i) {
+                Ok(stream) => {
+                    let mut ipc = Ipc { stream, nonce: 0 };
+                    ipc.send(OP_HANDSHAKE, json!({ "v": 1, "client_id": client_id }))?;
+                    let ready = ipc.receive()?;
+                    if ready["evt"] != "READY" {
+                        return Err(io::Error::other(format!(
+                            "handshake failed: {}",
+                            ready["message"].as_str().unwrap_or("unknown error")
+                        )));
+                    }
+                    return Ok(ipc);
+                }
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    fn set_activity(&mut self, activity: Option<serde_json::Value>) -> io::Result<()> {
+        self.nonce += 1;
+        self.send(
+            OP_FRAME,
+            json!({
+                "cmd": "SET_ACTIVITY",
+                "args": { "pid": process::id(), "activity": activity },
+                "nonce": self.nonce.to_string(),
+            }),
+        )?;
+        let response = self.receive()?;
+        if response["evt"] == "ERROR" {
+            warn!(
+                "Discord refused the activity: {}",
+                response["data"]["message"].as_str().unwrap_or_default()
+            );
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, op: u32, payload: serde_json::Value) -> io::Result<()> {
+        let payload = payload.to_string();
+        let mut packet = op.to_le_bytes().to_vec();
+        packet.extend((payload.len() as u32).to_le_bytes());
+        packet.extend(payload.as_bytes());
+        self.stream.write_all(&packet)
+    }
+
+    fn receive(&mut self) -> io::Result<serde_json::Value> {
+        let mut header = [0; 8];
+        self.stream.read_exact(&mut header)?;
+        let len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+        let mut payload = vec![0; len as usize];
+        self.stream.read_exact(&mut payload)?;
+        serde_json::from_slice(&payload).map_err(io::Error::other)
+    }
+}
+
+#[cfg(unix)]
+fn open(i: u8) -> io::Result<Box<dyn Stream>> {
+    use std::os::unix::net::UnixStream;
+    let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(|v| std::env::var(v).ok())
+        .unwrap_or_else(|| "/tmp".to_string());
+    let stream = UnixStream::connect(format!("{}/discord-ipc-{}", dir, i))?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    Ok(Box::new(stream))
+}
+
+#[cfg(windows)]
+fn open(i: u8) -> io::Result<Box<dyn Stream>> {
+    let pipe = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(format!(r"\\.\pipe\discord-ipc-{}", i))?;
+    Ok(Box::new(pipe))
+}
@@ -0,0 +1,109 @@
+use std::process::Command;
+
+use crate::Integration;
+use pomo_core::config::DndBackend;
+use pomo_core::pomo::{CurrentPomoState, PomodoroState};
+use tracing::warn;
+
+/// Enables do-not-disturb of the notification daemon during work sections
+/// and restores the previous setting afterwards
+pub struct Dnd {
+    backend: DndBackend,
+    /// setting before do-not-disturb was enabled, `None` while it is not enabled by pomo
+    previous: Option<String>,
+}
+
+fn output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn run(program: &str, args: &[&str]) {
+    if let Err(e) = Command::new(program).args(args).output() {
+        warn!("Could not run {}: {}", program, e);
+    }
+}
+
+const GNOME_SCHEMA: &str = "org.gnome.desktop.notifications";
+const MAKO_MODE: &str = "do-not-disturb";
+
+impl Dnd {
+    pub fn new(backend: DndBackend) -> Dnd {
+        let backend = match backend {
+            DndBackend::Auto => {
+                if output("dunstctl", &["is-paused"]).is_some() {
+                    DndBackend::Dunst
+                } else if output("makoctl", &["mode"]).is_some() {
+                    DndBackend::Mako
+                } else {
+                    DndBackend::Gnome
+                }
+            }
+            b => b,
+        };
+        Dnd {
+            backend,
+            previous: None,
+        }
+    }
+
+    fn query(&self) -> Option<String> {
+        match self.backend {
+            DndBackend::Dunst => output("dunstctl", &["is-paused"]),
+            DndBackend::Mako => output("makoctl", &["mode"]),
+            DndBackend::Gnome | DndBackend::Auto => {
+                output("gsettings", &["get", GNOME_SCHEMA, "show-banners"])
+            }
+        }
+    }
+
+    fn enable(&self) {
+        match self.backend {
+            DndBackend::Dunst => run("dunstctl", &["set-paused", "true"]),
+            DndBackend::Mako => run("makoctl", &["mode", "-a", MAKO_MODE]),
+            DndBackend::Gnome | DndBackend::Auto => {
+                run("gsettings", &["set", GNOME_SCHEMA, "show-banners", "false"])
+            }
+        }
+    }
+
+    fn restore(&self, previous: &str) {
+        match self.backend {
+            DndBackend::Dunst => run("dunstctl", &["set-paused", previous]),
+            DndBackend::Mako => {
+                if !previous.lines().any(|m| m == MAKO_MODE) {
+                    run("makoctl", &["mode", "-r", MAKO_MODE]);
+                }
+            }
+            DndBackend::Gnome | DndBackend::Auto => run(
+                "gsettings",
+                &["set", GNOME_SCHEMA, "show-banners", previous],
+            ),
+        }
+    }
+}
+
+impl Integration for Dnd {
+    fn transition(&mut self, _from: PomodoroState, state: &CurrentPomoState) {
+        let work = state.current_state == PomodoroState::Work;
+        if work && self.previous.is_none() {
+            self.previous = self.query();
+            if self.previous.is_some() {
+                self.enable();
+            }
+        } else if !work {
+            if let Some(previous) = self.previous.take() {
+                self.restore(&previous);
+            }
+        }
+    }
+
+    fn cleanup(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            self.restore(&previous);
+        }
+    }
+}
@@ -0,0 +1,319 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::Integration;
+use chrono::{DateTime, SecondsFormat, Utc};
+use pomo_core::config::{config_path, GoogleCalendarConfig};
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use pomo_core::storage::state_path;
+use pomo_core::util::shell_command;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const API: &str = "https://www.googleapis.com/calendar/v3";
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Number of pomos whose event id is remembered
+const KEEP_EVENTS: usize = 20;
+
+/// The OAuth client and the refresh token from `pomo google-login`, stored next to the config
+/// file
+#[derive(Serialize, Deserialize)]
+struct Credentials {
+    client_id: String,
+    client_secret: String,
+    refresh_token: String,
+}
+
+fn credentials_file() -> std::path::PathBuf {
+    config_path("google-calendar.json")
+}
+
+/// Authorizes pomo to create events in the Google Calendar of the user with the OAuth client
+/// of a desktop app, the user confirms in the browser
+pub fn login(client_id: &str, client_secret: &str) -> Result<(), String> {
+    let listener = TcpListener::bind("127.0.0.1:0").map_err(|e| e.to_string())?;
+    let redirect = format!(
+        "http://127.0.0.1:{}",
+        listener.local_addr().map_err(|e| e.to_string())?.port()
+    );
+    let url = url::Url::parse_with_params(
+        AUTH_URL,
+        &[
+            ("client_id", client_id),
+            ("redirect_uri", &redirect),
+            ("response_type", "code"),
+            ("scope", SCOPE),
+            ("access_type", "offline"),
+            ("prompt", "consent"),
+        ],
+    )
+    .map_err(|e| e.to_string())?;
+    println!(
+        "Open this page to allow pomo to add events to your calendar:\n\n{}\n",
+        url
+    );
+    let opener = if cfg!(windows) {
+        "start"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    let _ = shell_command(&format!("{} '{}'", opener, url)).output();
+
+    let (mut stream, _) = listener.accept().map_err(|e| e.to_string())?;
+    let mut request = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut request)
+        .map_err(|e| e.to_string())?;
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+    let query = url::Url::parse(&format!("{}{}", redirect, path)).map_err(|e| e.to_string())?;
+    let param = |name: &str| {
+        query
+            .query_pairs()
+            .find(|(k, _)| k == name)
+            .map(|(_, v)| v.to_string())
+    };
+    let _ = stream.write_all(
+        b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nConnection: close\r\n\r\nDone, you can close this page.",
+    );
+    let code = match (param("code"), param("error")) {
+        (Some(code), _) => code,
+        (None, error) => return Err(format!("Not allowed: {}", error.unwrap_or_default())),
+    };
+
+    let response: serde_json::Value = ureq::post(TOKEN_URL)
+        .timeout(TIMEOUT)
+        .send_form(&[
+            ("client_id", client_id),
+            ("client_secret", client_secret),
+            ("code", &code),
+            ("redirect_uri", &redirect),
+            ("grant_type", "authorization_code"),
+        ])
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    let refresh_token = response["refresh_token"]
+        .as_str()
+        .ok_or("Google did not return a refresh token")?;
+    let credentials = Credentials {
+        client_id: client_id.to_string(),
+        client_secret: client_secret.to_string(),
+        refresh_token: refresh_token.to_string(),
+    };
+    let file = credentials_file();
+    fs::write(&file, serde_json::to_string_pretty(&credentials).unwrap())
+        .map_err(|e| format!("Could not write {}: {}", file.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = fs::set_permissions(&file, fs::Permissions::from_mode(0o600));
+    }
+    println!("Saved the credentials to {}", file.display());
+    Ok(())
+}
+
+/// The planned time of a pomo
+#[derive(Clone, PartialEq)]
+struct Block {
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    description: String,
+}
+
+/// Creates an event in Google Calendar for every pomo and moves its end when the pomo is
+/// extended or stopped early. The requests are sent from a separate thread.
+pub struct GoogleCalendar {
+    sender: Option<Sender<Block>>,
+    worker: Option<JoinHandle<()>>,
+    last: Option<Block>,
+}
+
+impl GoogleCalendar {
+    pub fn new(config: GoogleCalendarConfig) -> GoogleCalendar {
+        let (sender, receiver) = mpsc::channel::<Block>();
+        let worker = thread::spawn(move || {
+            let mut client = match Client::new(config) {
+                Ok(client) => client,
+                Err(e) => {
+                    warn!("Google Calendar is not available: {}", e);
+                    return;
+                }
+            };
+            for block in receiver {
+                if let Err(e) = client.save(&block) {
+                    warn!("Could not update the Google Calendar event: {}", e);
+                }
+            }
+        });
+        GoogleCalendar {
+            sender: Some(sender),
+            worker: Some(worker),
+            last: None,
+        }
+    }
+}
+
+impl Integration for GoogleCalendar {
+    fn transition(&mut self, _from: PomodoroState, _state: &CurrentPomoState) {}
+
+    fn tick(&mut self, pomo: &Pomodoro, _state: &CurrentPomoState) {
+        let same_pomo = self.last.as_ref().is_some_and(|b| b.start == pomo.start);
+        let end = if pomo.active {
+            pomo.end()
+        } else if same_pomo {
+            pomo.ended_at()
+        } else {
+            // an old pomo, e.g. when watch starts without an active pomo
+            return;
+        };
+        let block = Block {
+            start: pomo.start,
+            end,
+            description: pomo.task.clone().unwrap_or_default(),
+        };
+        // the end of open sections moves all the time, a minute is precise enough
+        let changed = self.last.as_ref().is_none_or(|last| {
+            !same_pomo
+                || last.description != block.description
+                || (last.end - block.end).num_seconds().abs() >= 60
+        });
+        if changed {
+            if let Some(sender) = &self.sender {
+                let _ = sender.send(block.clone());
+            }
+            self.last = Some(block);
+        }
+    }
+
+    fn cleanup(&mut self) {
+        // wait until the last change is saved
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+struct Client {
+    config: GoogleCalendarConfig,
+    credentials: Credentials,
+    agent: ureq::Agent,
+    token: Option<(String, Instant)>,
+    /// ids of the events by the start of their pomo, kept in the state folder so a restart of
+    /// watch updates the same event
+    events: BTreeMap<i64, String>,
+}
+
+impl Client {
+    fn new(config: GoogleCalendarConfig) -> Result<Client, String> {
+        let file = credentials_file();
+        let credentials = fs::read_to_string(&file)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .ok_or(format!(
+                "no credentials in {}, run 'pomo google-login' first",
+                file.display()
+            ))?;
+        let events = fs::read_to_string(state_path("google-calendar"))
+            .ok()
+            .and_then(|e| serde_json::from_str(&e).ok())
+            .unwrap_or_default();
+        Ok(Client {
+            config,
+            credentials,
+            agent: ureq::AgentBuilder::new().timeout(TIMEOUT).build(),
+            token: None,
+            events,
+        })
+    }
+
+    /// A valid access token, refreshed with the refresh token when it expired
+    fn token(&mut self) -> Result<String, String> {
+        if let Some((token, expires)) = &self.token {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+        debug!("Refreshing the Google access token");
+        let response: serde_json::Value = self
+            .agent
+            .post(TOKEN_URL)
+            .send_form(&[
+                ("client_id", self.credentials.client_id.as_str()),
+                ("client_secret", &self.credentials.client_secret),
+                ("refresh_token", &self.credentials.refresh_token),
+                ("grant_type", "refresh_token"),
+            ])
+            .map_err(|e| e.to_string())?
+            .into_json()
+            .map_err(|e| e.to_string())?;
+        let token = response["access_token"]
+            .as_str()
+            .ok_or("no access token, run 'pomo google-login' again")?
+            .to_string();
+        let expires_in = response["expires_in"].as_u64().unwrap_or(3600);
+        let expires = Instant::now() + Duration::from_secs(expires_in.saturating_sub(60));
+        self.token = Some((token.clone(), expires));
+        Ok(token)
+    }
+
+    fn save(&mut self, block: &Block) -> Result<(), String> {
+        let token = format!("Bearer {}", self.token()?);
+        let time =
+            |t: DateTime<Utc>| json!({ "dateTime": t.to_rfc3339_opts(SecondsFormat::Secs, true) });
+        let event = json!({
+            "summary": self.config.summary(),
+            "description": block.description,
+            "start": time(block.start),
+            "end": time(block.end),
+        });
+        let events = format!(
+            "{}/calendars/{}/events",
+            API,
+            url::form_urlencoded::byte_serialize(self.config.calendar().as_bytes())
+                .collect::<String>()
+        );
+        let key = block.start.timestamp();
+        let response = match self.events.get(&key) {
+            Some(id) => self
+                .agent
+                .request("PATCH", &format!("{}/{}", events, id))
+                .set("Authorization", &token)
+                .send_json(event),
+            None => self
+                .agent
+                .post(&events)
+                .set("Authorization", &token)
+                .send_json(event),
+        };
+        let response: serde_json::Value = response
+            .map_err(|e| e.to_string())?
+            .into_json()
+            .map_err(|e| e.to_string())?;
+        if let Some(id) = response["id"].as_str() {
+            if !self.events.contains_key(&key) {
+                info!("Created Google Calendar event {}", id);
+            }
+            self.events.insert(key, id.to_string());
+            while self.events.len() > KEEP_EVENTS {
+                self.events.pop_first();
+            }
+            let _ = fs::write(
+                state_path("google-calendar"),
+                serde_json::to_string(&self.events).unwrap(),
+            );
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,108 @@
+use chrono::Duration;
+use std::env;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver};
+use std::thread;
+use tracing::warn;
+
+/// Detects how long the user has been idle.
+/// On Wayland `swayidle` is used which implements the idle-notify protocol,
+/// on X11 `xprintidle` which reads the XScreenSaver idle time.
+pub enum IdleDetector {
+    Wayland {
+        child: Child,
+        rx: Receiver<bool>,
+        idle: bool,
+        threshold: Duration,
+    },
+    X11 {
+        threshold: Duration,
+    },
+}
+
+impl IdleDetector {
+    pub fn new(threshold: Duration) -> Option<IdleDetector> {
+        if env::var("WAYLAND_DISPLAY").is_ok() {
+            let mut child = match Command::new("swayidle")
+                .arg("-w")
+                .arg("timeout")
+                .arg(threshold.num_seconds().to_string())
+                .arg("echo idle")
+                .arg("resume")
+                .arg("echo active")
+                .stdout(Stdio::piped())
+                .spawn()
+            {
+                Ok(c) => c,
+                Err(e) => {
+                    warn!("Idle detection needs swayidle: {}", e);
+                    return None;
+                }
+            };
+            let stdout = child.stdout.take().unwrap();
+            let (tx, rx) = channel();
+            thread::spawn(move || {
+                for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                    if tx.send(line.trim() == "idle").is_err() {
+                        break;
+                    }
+                }
+            });
+            Some(IdleDetector::Wayland {
+                child,
+                rx,
+                idle: false,
+                threshold,
+            })
+        } else if env::var("DISPLAY").is_ok() {
+            Some(IdleDetector::X11 { threshold })
+        } else {
+            warn!("Idle detection needs a Wayland or X11 session");
+            None
+        }
+    }
+
+    /// The time the user has been idle, if it is longer than the threshold
+    pub fn idle_time(&mut self) -> Option<Duration> {
+        match self {
+            IdleDetector::Wayland {
+                rx,
+                idle,
+                threshold,
+                ..
+            } => {
+                if let Some(i) = rx.try_iter().last() {
+                    *idle = i;
+                }
+                if *idle {
+                    Some(*threshold)
+                } else {
+                    None
+                }
+            }
+            IdleDetector::X11 { threshold } => {
+                let output = Command::new("xprintidle").output().ok()?;
+                let millis: i64 = String::from_utf8_lossy(&output.stdout)
+                    .trim()
+                    .parse()
+                    .ok()?;
+                let idle = Duration::milliseconds(millis);
+                if idle >= *threshold {
+                    Some(idle)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl Drop for IdleDetector {
+    fn drop(&mut self) {
+        if let IdleDetector::Wayland { child, .. } = self {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use std::thread;
+
+use chrono::{DateTime, Utc};
+use pomo_core::config::Config;
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use pomo_core::util::shell_command;
+use tracing::{debug, warn};
+
+#[cfg(feature = "dnd")]
+pub mod dnd;
+#[cfg(feature = "block")]
+pub mod block;
+#[cfg(feature = "caldav")]
+pub mod caldav;
+#[cfg(feature = "dim")]
+pub mod dim;
+#[cfg(feature = "discord")]
+pub mod discord;
+#[cfg(feature = "google")]
+pub mod google;
+pub mod idle;
+#[cfg(feature = "lock")]
+pub mod lock;
+#[cfg(feature = "lua")]
+pub mod lua;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+#[cfg(feature = "music")]
+pub mod music;
+#[cfg(feature = "plugins")]
+pub mod plugin;
+#[cfg(feature = "slack")]
+pub mod slack;
+#[cfg(feature = "team")]
+pub mod team;
+#[cfg(feature = "webhook")]
+pub mod webhook;
+
+/// Reacts to state changes of the pomo while watch or daemon is running
+pub trait Integration {
+    /// called when the pomo changes from the state `from` to the state in `state`
+    fn transition(&mut self, from: PomodoroState, state: &CurrentPomoState);
+    /// called on every update of watch or daemon
+    fn tick(&mut self, _pomo: &Pomodoro, _state: &CurrentPomoState) {}
+    /// when `tick` has to be called next at the latest, watch otherwise only updates when what
+    /// it shows changes and on every full minute
+    fn next_tick(&self) -> Option<DateTime<Utc>> {
+        None
+    }
+    /// may change the status line watch prints and writes to its files
+    fn format_status(
+        &mut self,
+        _pomo: &Pomodoro,
+        _state: &CurrentPomoState,
+        line: String,
+    ) -> String {
+        line
+    }
+    /// called when watch or daemon exits, undoes changes to the system
+    fn cleanup(&mut self) {}
+}
+
+/// All integrations enabled in the config
+#[cfg_attr(
+    not(all(
+        feature = "music",
+        feature = "dnd",
+        feature = "block",
+        feature = "caldav",
+        feature = "dim",
+        feature = "discord",
+        feature = "google",
+        feature = "lock",
+        feature = "lua",
+        feature = "mqtt",
+        feature = "plugins",
+        feature = "slack",
+        feature = "team",
+        feature = "webhook"
+    )),
+    allow(unused_mut, unused_variables)
+)]
+pub fn integrations(config: &Config) -> Vec<Box<dyn Integration>> {
+    let mut integrations: Vec<Box<dyn Integration>> = vec![];
+    #[cfg(feature = "music")]
+    if config.music.is_enabled() {
+        integrations.push(Box::new(music::Music::new(config.music.clone())));
+    }
+    #[cfg(feature = "dnd")]
+    if config.dnd.enabled {
+        integrations.push(Box::new(dnd::Dnd::new(config.dnd.backend)));
+    }
+    #[cfg(feature = "block")]
+    if config.block.is_enabled() {
+        integrations.push(Box::new(block::Block::new(config.block.clone())));
+    }
+    #[cfg(feature = "dim")]
+    if config.dim.enabled {
+        integrations.push(Box::new(dim::Dim::new(config.dim)));
+    }
+    #[cfg(feature = "lock")]
+    if config.lock.is_enabled() {
+        integrations.push(Box::new(lock::Lock::new(config.lock.clone())));
+    }
+    #[cfg(feature = "mqtt")]
+    if config.mqtt.broker.is_some() {
+        integrations.push(Box::new(mqtt::Mqtt::new(config.mqtt.clone())));
+    }
+    #[cfg(feature = "slack")]
+    if config.slack.token.is_some() {
+        integrations.push(Box::new(slack::Slack::new(config.slack.clone())));
+    }
+    #[cfg(feature = "discord")]
+    if config.discord.client_id.is_some() {
+        integrations.push(Box::new(discord::Discord::new(config.discord.clone())));
+    }
+    #[cfg(feature = "google")]
+    if config.google_calendar.enabled {
+        integrations.push(Box::new(google::GoogleCalendar::new(
+            config.google_calendar.clone(),
+        )));
+    }
+    #[cfg(feature = "caldav")]
+    if config.caldav.url.is_some() {
+        integrations.push(Box::new(caldav::Caldav::new(config.caldav.clone())));
+    }
+    #[cfg(feature = "lua")]
+    if let Some(hooks) = lua::LuaHooks::load() {
+        integrations.push(Box::new(hooks));
+    }
+    #[cfg(feature = "plugins")]
+    {
+        let plugins = plugin::discover();
+        if !plugins.is_empty() {
+            integrations.push(Box::new(plugin::Plugins::new(plugins, &config.report)));
+        }
+    }
+    #[cfg(feature = "team")]
+    if config.team.is_enabled() {
+        integrations.push(Box::new(team::Team::new(config.team.clone())));
+    }
+    #[cfg(feature = "webhook")]
+    if !config.webhook.urls.is_empty() {
+        integrations.push(Box::new(webhook::Webhook::new(config.webhook.clone())));
+    }
+    integrations
+}
+
+/// Runs a shell command in the background, its output is discarded
+pub fn run_shell(command: &str) {
+    debug!("Running '{}'", command);
+    match shell_command(command).spawn() {
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => warn!("Could not run '{}': {}", command, e),
+    }
+}
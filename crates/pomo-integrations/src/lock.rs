@@ -0,0 +1,76 @@
+use std::process::Command;
+
+use crate::{run_shell, Integration};
+use chrono::{DateTime, Utc};
+use pomo_core::config::LockConfig;
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use tracing::{info, warn};
+
+/// Locks the screen a little after the start of the configured sections, e.g. to make sure
+/// breaks are spent away from the computer
+pub struct Lock {
+    config: LockConfig,
+    /// when the screen gets locked and the state it is locked for
+    pending: Option<(DateTime<Utc>, PomodoroState)>,
+}
+
+impl Lock {
+    pub fn new(config: LockConfig) -> Lock {
+        Lock {
+            config,
+            pending: None,
+        }
+    }
+
+    fn lock(&self) {
+        info!("Locking the screen");
+        if let Some(command) = &self.config.command {
+            run_shell(command);
+            return;
+        }
+        let lockers: &[(&str, &[&str])] = if cfg!(windows) {
+            &[("rundll32.exe", &["user32.dll,LockWorkStation"])]
+        } else if cfg!(target_os = "macos") {
+            &[("pmset", &["displaysleepnow"])]
+        } else {
+            &[
+                ("loginctl", &["lock-session"]),
+                ("xdg-screensaver", &["lock"]),
+            ]
+        };
+        let locked = lockers.iter().any(|(program, args)| {
+            Command::new(program)
+                .args(*args)
+                .status()
+                .is_ok_and(|s| s.success())
+        });
+        if !locked {
+            warn!("Could not lock the screen, set a command in the [lock] section of the config");
+        }
+    }
+}
+
+impl Integration for Lock {
+    fn transition(&mut self, _from: PomodoroState, state: &CurrentPomoState) {
+        self.pending = self
+            .config
+            .for_state(state.current_state)
+            .then(|| (Utc::now() + self.config.delay(), state.current_state));
+    }
+
+    fn tick(&mut self, _pomo: &Pomodoro, state: &CurrentPomoState) {
+        let Some((at, locked_state)) = self.pending else {
+            return;
+        };
+        if state.current_state != locked_state {
+            self.pending = None;
+        } else if Utc::now() >= at && !state.pause {
+            self.pending = None;
+            self.lock();
+        }
+    }
+
+    fn next_tick(&self) -> Option<DateTime<Utc>> {
+        self.pending.map(|(at, _)| at)
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use std::fs;
+
+use crate::Integration;
+use chrono::{DateTime, SecondsFormat, Utc};
+use mlua::{FromLuaMulti, Function, Lua, LuaSerdeExt};
+use pomo_core::config::config_path;
+use pomo_core::format::{state_json, FormatOptions};
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use serde_json::json;
+use tracing::{info, warn};
+
+/// Runs the functions of `hooks.lua` in the config folder: `on_section_start(section)` when a
+/// section starts, `on_done(summary)` when the pomo is over and `format_status(state)`, which
+/// returns the line watch shows or `nil` to keep it
+pub struct LuaHooks {
+    lua: Lua,
+    /// state and start of the pomo on the last tick
+    last: Option<(PomodoroState, DateTime<Utc>)>,
+    /// functions that failed are not called again, so the error isn't logged on every tick
+    failed: HashSet<&'static str>,
+}
+
+impl LuaHooks {
+    /// Loads `hooks.lua`, `None` if there is none or it fails to run
+    pub fn load() -> Option<LuaHooks> {
+        let path = config_path("hooks.lua");
+        let script = fs::read_to_string(&path).ok()?;
+        let lua = Lua::new();
+        if let Err(e) = lua
+            .load(&script)
+            .set_name(path.display().to_string())
+            .exec()
+        {
+            warn!("Could not load {}: {}", path.display(), e);
+            return None;
+        }
+        info!("Loaded {}", path.display());
+        Some(LuaHooks {
+            lua,
+            last: None,
+            failed: HashSet::new(),
+        })
+    }
+
+    /// Calls the global function `name` with `arg` as a table, `None` if the script doesn't
+    /// define it or it failed
+    fn call<R>(&mut self, name: &'static str, arg: serde_json::Value) -> Option<R>
+    where
+        R: for<'lua> FromLuaMulti<'lua>,
+    {
+        if self.failed.contains(name) {
+            return None;
+        }
+        match call(&self.lua, name, arg) {
+            Ok(result) => result,
+            Err(e) => {
+                warn!("{} in hooks.lua failed: {}", name, e);
+                self.failed.insert(name);
+                None
+            }
+        }
+    }
+}
+
+fn call<R>(lua: &Lua, name: &str, arg: serde_json::Value) -> mlua::Result<Option<R>>
+where
+    R: for<'lua> FromLuaMulti<'lua>,
+{
+    match lua.globals().get::<_, Option<Function>>(name)? {
+        Some(function) => function.call(lua.to_value(&arg)?).map(Some),
+        None => Ok(None),
+    }
+}
+
+impl Integration for LuaHooks {
+    fn transition(&mut self, _from: PomodoroState, _state: &CurrentPomoState) {}
+
+    fn tick(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) {
+        let current = (state.current_state, pomo.start);
+        let Some(last) = self.last.replace(current).filter(|last| *last != current) else {
+            // nothing is called for the section watch finds when it starts
+            return;
+        };
+        match state.current_state {
+            PomodoroState::Warmup | PomodoroState::Work | PomodoroState::Break => {
+                let mut section = state_json(pomo, state, FormatOptions::default());
+                section["previous"] = json!(last.0.to_string());
+                self.call::<()>("on_section_start", section);
+            }
+            PomodoroState::Done if last.1 == pomo.start => {
+                let now = Utc::now();
+                let summary = json!({
+                    "task": pomo.task,
+                    "tags": pomo.tags,
+                    "start": pomo.start.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    "end": pomo.ended_at().to_rfc3339_opts(SecondsFormat::Secs, true),
+                    "work_sections": pomo.completed_work_sections(now),
+                    "focus_seconds": pomo.work_time(now).num_seconds(),
+                    "notes": pomo.notes.iter().map(|n| n.text.clone()).collect::<Vec<_>>(),
+                });
+                self.call::<()>("on_done", summary);
+            }
+            _ => (),
+        }
+    }
+
+    fn format_status(&mut self, pomo: &Pomodoro, state: &CurrentPomoState, line: String) -> String {
+        let mut arg = state_json(pomo, state, FormatOptions::default());
+        arg["line"] = json!(line);
+        self.call::<Option<String>>("format_status", arg)
+            .flatten()
+            .unwrap_or(line)
+    }
+
+    /// `format_status` may show anything, so it is called on every tick
+    fn next_tick(&self) -> Option<DateTime<Utc>> {
+        let defined = !self.failed.contains("format_status")
+            && self
+                .lua
+                .globals()
+                .contains_key("format_status")
+                .unwrap_or(false);
+        defined.then(Utc::now)
+    }
+}
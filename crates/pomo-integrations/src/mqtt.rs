@@ -0,0 +1,405 @@
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::{env, process};
+use std::time::{Duration, Instant};
+
+use crate::Integration;
+use chrono::{DateTime, Utc};
+use pomo_core::config::MqttConfig;
+use pomo_core::format::{state_json, FormatOptions};
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use pomo_core::util::hostname;
+use tracing::{info, warn};
+
+/// Seconds the broker waits for a packet before it considers the client gone
+const KEEP_ALIVE: u16 = 60;
+/// Time between attempts to reach the broker
+const RECONNECT_AFTER: Duration = Duration::from_secs(30);
+
+/// Publishes the state of the pomo to an MQTT broker:
+/// `<prefix>/state`, `<prefix>/paused`, `<prefix>/remaining` (seconds), `<prefix>/status` (JSON)
+/// and `<prefix>/available` (`online` or `offline`). `pause`, `unpause` and `skip` sent to
+/// `<prefix>/command` are run like the commands of the same name.
+pub struct Mqtt {
+    config: MqttConfig,
+    client: Option<Client>,
+    last_attempt: Option<Instant>,
+    /// last published status without the remaining time
+    last: Option<serde_json::Value>,
+    last_remaining: Option<i64>,
+}
+
+impl Mqtt {
+    pub fn new(config: MqttConfig) -> Mqtt {
+        Mqtt {
+            config,
+            client: None,
+            last_attempt: None,
+            last: None,
+            last_remaining: None,
+        }
+    }
+
+    fn topic(&self, name: &str) -> String {
+        format!("{}/{}", self.config.prefix(), name)
+    }
+
+    fn connect(&mut self) {
+        if self.client.is_some()
+            || self
+                .last_attempt
+                .is_some_and(|t| t.elapsed() < RECONNECT_AFTER)
+        {
+            return;
+        }
+        self.last_attempt = Some(Instant::now());
+        let broker = self.config.broker.clone().unwrap_or_default();
+        let available = self.topic("available");
+        match Client::connect(&broker, &self.config, &available) {
+            Ok(mut client) => match self.announce(&mut client) {
+                Ok(()) => {
+                    info!("Connected to MQTT broker {}", broker);
+                    self.client = Some(client);
+                    // everything is published again, the broker might have lost it
+                    self.last = None;
+                    self.last_remaining = None;
+                }
+                Err(e) => warn!("Could not publish to MQTT broker {}: {}", broker, e),
+            },
+            Err(e) => warn!("Could not connect to MQTT broker {}: {}", broker, e),
+        }
+    }
+
+    /// Publishes what doesn't change while connected and subscribes to the command topic
+    fn announce(&self, client: &mut Client) -> io::Result<()> {
+        client.publish(&self.topic("available"), "online", true)?;
+        client.subscribe(&self.topic("command"))?;
+        if self.config.discovery {
+            for (component, name, config) in self.discovery() {
+                let topic = format!(
+                    "{}/{}/{}/{}/config",
+                    self.config.discovery_prefix(),
+                    component,
+                    client_id().replace('-', "_"),
+                    name
+                );
+                client.publish(&topic, &config.to_string(), true)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Home Assistant discovery configs as (component, object id, config)
+    fn discovery(&self) -> Vec<(&str, &str, serde_json::Value)> {
+        let id = client_id().replace('-', "_");
+        let device = serde_json::json!({
+            "identifiers": [id],
+            "name": format!("Pomodoro {}", hostname()).trim(),
+            "manufacturer": "pomo",
+            "sw_version": env!("CARGO_PKG_VERSION"),
+        });
+        let entity = |name: &str, object: &str| {
+            serde_json::json!({
+                "name": name,
+                "unique_id": format!("{}_{}", id, object),
+                "availability_topic": self.topic("available"),
+                "device": device,
+            })
+        };
+        let mut state = entity("State", "state");
+        state["state_topic"] = self.topic("state").into();
+        state["icon"] = "mdi:timer-outline".into();
+        let mut remaining = entity("Remaining", "remaining");
+        remaining["state_topic"] = self.topic("remaining").into();
+        remaining["value_template"] = "{{ (value | int / 60) | round(0, 'ceil') | int }}".into();
+        remaining["unit_of_measurement"] = "min".into();
+        remaining["icon"] = "mdi:timer-sand".into();
+        let mut buttons = vec![];
+        for (command, name, icon) in [
+            ("pause", "Pause", "mdi:pause"),
+            ("unpause", "Unpause", "mdi:play"),
+            ("skip", "Skip", "mdi:skip-next"),
+        ] {
+            let mut button = entity(name, command);
+            button["command_topic"] = self.topic("command").into();
+            button["payload_press"] = command.into();
+            button["icon"] = icon.into();
+            buttons.push(("button", command, button));
+        }
+        let mut configs = vec![
+            ("sensor", "state", state),
+            ("sensor", "remaining", remaining),
+        ];
+        configs.extend(buttons);
+        configs
+    }
+
+    /// Runs the commands received on `<prefix>/command` with the pomo binary
+    fn run_commands(&self) {
+        let Some(client) = self.client.as_ref() else {
+            return;
+        };
+        let command_topic = self.topic("command");
+        for (topic, payload) in client.messages.try_iter() {
+            if topic != command_topic {
+                continue;
+            }
+            let command = payload.trim();
+            if !["pause", "unpause", "skip"].contains(&command) {
+                warn!("Unknown MQTT command '{}'", command);
+                continue;
+            }
+            let exe = env::current_exe().unwrap_or_else(|_| "pomo".into());
+            match process::Command::new(exe).arg(command).output() {
+                Ok(output) if !output.status.success() => warn!(
+                    "'pomo {}' failed: {}",
+                    command,
+                    String::from_utf8_lossy(&output.stdout).trim()
+                ),
+                Ok(_) => (),
+                Err(e) => warn!("Could not run 'pomo {}': {}", command, e),
+            }
+        }
+    }
+
+    fn publish(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) -> io::Result<()> {
+        let Some(client) = self.client.as_mut() else {
+            return Ok(());
+        };
+        let prefix = self.config.prefix();
+        let status = state_json(pomo, state, FormatOptions::default());
+        let mut key = status.clone();
+        let remaining = key["remaining_seconds"].take().as_i64();
+        if self.last.as_ref() != Some(&key) {
+            let current = if pomo.active {
+                state.current_state.to_string()
+            } else {
+                "inactive".to_string()
+            };
+            client.publish(&format!("{}/state", prefix), &current, true)?;
+            client.publish(
+                &format!("{}/paused", prefix),
+                &state.pause.to_string(),
+                true,
+            )?;
+            client.publish(&format!("{}/status", prefix), &status.to_string(), true)?;
+            self.last = Some(key);
+        }
+        if self.last_remaining != remaining {
+            let value = remaining.unwrap_or_default().to_string();
+            client.publish(&format!("{}/remaining", prefix), &value, true)?;
+            self.last_remaining = remaining;
+        }
+        client.keep_alive()
+    }
+}
+
+impl Integration for Mqtt {
+    fn transition(&mut self, _from: PomodoroState, _state: &CurrentPomoState) {}
+
+    fn tick(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) {
+        self.connect();
+        self.run_commands();
+        if let Err(e) = self.publish(pomo, state) {
+            warn!("Lost connection to MQTT broker: {}", e);
+            self.client = None;
+        }
+    }
+
+    /// the remaining seconds are published and the commands run on every tick while connected
+    fn next_tick(&self) -> Option<DateTime<Utc>> {
+        self.client.as_ref().map(|_| Utc::now())
+    }
+
+    fn cleanup(&mut self) {
+        let available = self.topic("available");
+        if let Some(mut client) = self.client.take() {
+            let _ = client.publish(&available, "offline", true);
+            client.disconnect();
+        }
+    }
+}
+
+/// Minimal MQTT 3.1.1 client that publishes with QoS 0
+struct Client {
+    stream: TcpStream,
+    last_sent: Instant,
+    /// messages on subscribed topics as (topic, payload)
+    messages: Receiver<(String, String)>,
+}
+
+/// Identifies this machine to the broker, at most 23 characters as required by MQTT 3.1.1
+fn client_id() -> String {
+    format!("pomo-{}", hostname())
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .take(23)
+        .collect()
+}
+
+fn encode_string(packet: &mut Vec<u8>, s: &str) {
+    packet.extend((s.len() as u16).to_be_bytes());
+    packet.extend(s.as_bytes());
+}
+
+/// Prepends the fixed header with the packet type and the remaining length
+fn frame(header: u8, body: Vec<u8>) -> Vec<u8> {
+    let mut packet = vec![header];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        packet.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    packet.extend(body);
+    packet
+}
+
+fn decode_string(body: &[u8]) -> Option<(String, &[u8])> {
+    let len = u16::from_be_bytes([*body.first()?, *body.get(1)?]) as usize;
+    let s = body.get(2..2 + len)?;
+    Some((String::from_utf8_lossy(s).to_string(), &body[2 + len..]))
+}
+
+/// Reads one packet, returns its header and body
+fn read_packet(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut byte = [0u8];
+    stream.read_exact(&mut byte)?;
+    let header = byte[0];
+    let mut len = 0usize;
+    let mut shift = 0;
+    loop {
+        stream.read_exact(&mut byte)?;
+        len |= ((byte[0] & 0x7f) as usize) << shift;
+        shift += 7;
+        if byte[0] & 0x80 == 0 || shift > 21 {
+            break;
+        }
+    }
+    let mut body = vec![0u8; len];
+    stream.read_exact(&mut body)?;
+    Ok((header, body))
+}
+
+impl Client {
+    /// Connects to `broker` with a last will that sets `available` to `offline`
+    fn connect(broker: &str, config: &MqttConfig, available: &str) -> io::Result<Client> {
+        let mut stream = TcpStream::connect(broker)?;
+        stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+
+        // clean session, retained last will with QoS 0
+        let mut flags = 0x02 | 0x04 | 0x20;
+        if config.username.is_some() {
+            flags |= 0x80;
+        }
+        if config.password.is_some() {
+            flags |= 0x40;
+        }
+        let mut body = vec![];
+        encode_string(&mut body, "MQTT");
+        body.push(4);
+        body.push(flags);
+        body.extend(KEEP_ALIVE.to_be_bytes());
+        encode_string(&mut body, &client_id());
+        encode_string(&mut body, available);
+        encode_string(&mut body, "offline");
+        if let Some(username) = &config.username {
+            encode_string(&mut body, username);
+        }
+        if let Some(password) = &config.password {
+            encode_string(&mut body, password);
+        }
+        stream.write_all(&frame(0x10, body))?;
+
+        let (header, body) = read_packet(&mut stream)?;
+        if header >> 4 != 2 || body.len() < 2 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "expected CONNACK",
+            ));
+        }
+        if body[1] != 0 {
+            let reason = match body[1] {
+                1 => "unacceptable protocol version",
+                2 => "client identifier rejected",
+                3 => "server unavailable",
+                4 => "bad user name or password",
+                5 => "not authorized",
+                _ => "unknown reason",
+            };
+            return Err(io::Error::new(io::ErrorKind::ConnectionRefused, reason));
+        }
+
+        stream.set_read_timeout(None)?;
+        let mut reader = stream.try_clone()?;
+        let (tx, messages) = mpsc::channel();
+        thread::spawn(move || {
+            while let Ok((header, body)) = read_packet(&mut reader) {
+                // everything except messages on subscribed topics, like answers to pings, is ignored
+                if header >> 4 != 3 {
+                    continue;
+                }
+                let Some((topic, mut payload)) = decode_string(&body) else {
+                    continue;
+                };
+                // QoS 1 and 2 messages have a packet identifier before the payload
+                if header & 0x06 != 0 {
+                    payload = payload.get(2..).unwrap_or_default();
+                }
+                let payload = String::from_utf8_lossy(payload).to_string();
+                if tx.send((topic, payload)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Client {
+            stream,
+            last_sent: Instant::now(),
+            messages,
+        })
+    }
+
+    fn send(&mut self, packet: &[u8]) -> io::Result<()> {
+        self.stream.write_all(packet)?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+
+    fn publish(&mut self, topic: &str, payload: &str, retain: bool) -> io::Result<()> {
+        let mut body = vec![];
+        encode_string(&mut body, topic);
+        body.extend(payload.as_bytes());
+        self.send(&frame(0x30 | retain as u8, body))
+    }
+
+    fn subscribe(&mut self, topic: &str) -> io::Result<()> {
+        let mut body = vec![];
+        // packet identifier, the acknowledgment is not checked
+        body.extend(1u16.to_be_bytes());
+        encode_string(&mut body, topic);
+        body.push(0);
+        self.send(&frame(0x82, body))
+    }
+
+    /// Pings the broker if nothing was sent for half the keep alive time
+    fn keep_alive(&mut self) -> io::Result<()> {
+        if self.last_sent.elapsed() > Duration::from_secs(KEEP_ALIVE as u64 / 2) {
+            self.send(&frame(0xc0, vec![]))?;
+        }
+        Ok(())
+    }
+
+    fn disconnect(mut self) {
+        let _ = self.send(&frame(0xe0, vec![]));
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
@@ -0,0 +1,22 @@
+use crate::{run_shell, Integration};
+use pomo_core::config::StateCommands;
+use pomo_core::pomo::{CurrentPomoState, PomodoroState};
+
+/// Starts, stops or switches music with the configured commands when the state changes
+pub struct Music {
+    commands: StateCommands,
+}
+
+impl Music {
+    pub fn new(commands: StateCommands) -> Music {
+        Music { commands }
+    }
+}
+
+impl Integration for Music {
+    fn transition(&mut self, _from: PomodoroState, state: &CurrentPomoState) {
+        if let Some(command) = self.commands.for_state(state.current_state) {
+            run_shell(command);
+        }
+    }
+}
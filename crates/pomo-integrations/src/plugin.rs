@@ -0,0 +1,260 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::Integration;
+use chrono::{DateTime, Local, NaiveTime, SecondsFormat, Utc};
+use pomo_core::config::{config_path, ReportConfig};
+use pomo_core::format::{state_json, FormatOptions};
+use pomo_core::history::sessions;
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use pomo_core::report::daily_summary_json;
+use pomo_core::storage::read_history;
+use serde_json::json;
+use tracing::{debug, info, warn};
+
+/// Time a plugin has to answer `info`
+const INFO_TIMEOUT: Duration = Duration::from_secs(5);
+pub const EVENTS: [&str; 4] = ["start", "transition", "stop", "daily-summary"];
+
+/// An executable in the `plugins` folder next to the config file
+pub struct Plugin {
+    pub name: String,
+    pub path: PathBuf,
+    /// the events of `EVENTS` the plugin wants to receive
+    pub events: Vec<String>,
+}
+
+/// Finds the plugins and asks each of them which events it wants with `<plugin> info`, which
+/// prints `{"name": "...", "events": ["start", ...]}`. Plugins that don't answer are skipped.
+pub fn discover() -> Vec<Plugin> {
+    let Ok(dir) = fs::read_dir(config_path("plugins")) else {
+        return vec![];
+    };
+    let mut paths: Vec<PathBuf> = dir
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| is_executable(p))
+        .collect();
+    paths.sort();
+    paths
+        .into_iter()
+        .filter_map(|path| match info(&path) {
+            Ok(plugin) => Some(plugin),
+            Err(e) => {
+                warn!("Skipping the plugin {}: {}", path.display(), e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn is_executable(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+    let Ok(metadata) = fs::metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    let executable = {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.permissions().mode() & 0o111 != 0
+    };
+    #[cfg(not(unix))]
+    let executable = true;
+    !hidden && metadata.is_file() && executable
+}
+
+fn info(path: &Path) -> Result<Plugin, String> {
+    let mut child = Command::new(path)
+        .arg("info")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    let started = Instant::now();
+    while child.try_wait().map_err(|e| e.to_string())?.is_none() {
+        if started.elapsed() > INFO_TIMEOUT {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("'info' did not answer in time".to_string());
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    let output = child.wait_with_output().map_err(|e| e.to_string())?;
+    let info: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("'info' did not print JSON: {}", e))?;
+    let name = info["name"]
+        .as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| {
+            path.file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        });
+    let events = info["events"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.as_str())
+        .filter(|e| EVENTS.contains(e))
+        .map(str::to_string)
+        .collect();
+    Ok(Plugin {
+        name,
+        path: path.to_path_buf(),
+        events,
+    })
+}
+
+/// What the plugins last saw of the pomo
+#[derive(Clone, Copy, PartialEq)]
+struct Seen {
+    start: DateTime<Utc>,
+    active: bool,
+    state: PomodoroState,
+}
+
+/// Sends the events to the plugins that want them. Every event runs `<plugin> event` with the
+/// event as JSON on stdin, in the background.
+pub struct Plugins {
+    plugins: Vec<Plugin>,
+    /// the daily summary is sent when a pomo ends after this local time
+    summary_after: Option<NaiveTime>,
+    last: Option<Seen>,
+}
+
+impl Plugins {
+    pub fn new(plugins: Vec<Plugin>, report: &ReportConfig) -> Plugins {
+        for plugin in plugins.iter() {
+            info!(
+                "Loaded the plugin {} for {}",
+                plugin.name,
+                plugin.events.join(", ")
+            );
+        }
+        Plugins {
+            plugins,
+            summary_after: report
+                .summary_after
+                .as_ref()
+                .and_then(|t| NaiveTime::parse_from_str(t, "%H:%M").ok()),
+            last: None,
+        }
+    }
+
+    fn wants(&self, event: &str) -> bool {
+        self.plugins
+            .iter()
+            .any(|p| p.events.iter().any(|e| e == event))
+    }
+
+    fn send(&self, event: &str, mut payload: serde_json::Value) {
+        payload["event"] = json!(event);
+        payload["time"] = json!(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
+        let payload = payload.to_string();
+        for plugin in self
+            .plugins
+            .iter()
+            .filter(|p| p.events.iter().any(|e| e == event))
+        {
+            debug!("Sending {} to the plugin {}", event, plugin.name);
+            let path = plugin.path.clone();
+            let name = plugin.name.clone();
+            let payload = payload.clone();
+            thread::spawn(move || {
+                if let Err(e) = run(&path, &payload) {
+                    warn!("The plugin {} failed: {}", name, e);
+                }
+            });
+        }
+    }
+
+    fn daily_summary(&self, pomo: &Pomodoro) -> Result<serde_json::Value, String> {
+        let history = read_history().map_err(|e| e.message().to_string())?;
+        let sessions = sessions(&history, Some(pomo), Utc::now());
+        Ok(daily_summary_json(
+            &history,
+            &sessions,
+            Local::now().date_naive(),
+        ))
+    }
+}
+
+fn run(path: &Path, payload: &str) -> Result<(), String> {
+    let mut child = Command::new(path)
+        .arg("event")
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // the plugin may exit without reading everything
+        let _ = writeln!(stdin, "{}", payload);
+    }
+    let status = child.wait().map_err(|e| e.to_string())?;
+    if !status.success() {
+        return Err(status.to_string());
+    }
+    Ok(())
+}
+
+impl Integration for Plugins {
+    fn transition(&mut self, _from: PomodoroState, _state: &CurrentPomoState) {}
+
+    fn tick(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) {
+        let seen = Seen {
+            start: pomo.start,
+            active: pomo.active,
+            state: state.current_state,
+        };
+        let last = match self.last.replace(seen) {
+            Some(last) if last != seen => last,
+            // nothing is sent for the state watch finds when it starts
+            _ => return,
+        };
+        let status = state_json(pomo, state, FormatOptions::default());
+        if seen.start != last.start && seen.active {
+            self.send("start", json!({ "status": status }));
+        }
+        if seen.state != last.state {
+            self.send(
+                "transition",
+                json!({
+                    "from": last.state.to_string(),
+                    "to": seen.state.to_string(),
+                    "status": status,
+                }),
+            );
+        }
+        let ended = seen.state == PomodoroState::Done || !seen.active;
+        if ended && seen.start == last.start && last.state != PomodoroState::Done {
+            let now = Utc::now();
+            self.send(
+                "stop",
+                json!({
+                    "finished": seen.active,
+                    "start": pomo.start.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    "end": pomo.ended_at().to_rfc3339_opts(SecondsFormat::Secs, true),
+                    "work_sections": pomo.completed_work_sections(now),
+                    "focus_seconds": pomo.work_time(now).num_seconds(),
+                    "status": status,
+                }),
+            );
+            let summary_due = self
+                .summary_after
+                .is_some_and(|after| Local::now().time() >= after);
+            if summary_due && self.wants("daily-summary") {
+                match self.daily_summary(pomo) {
+                    Ok(summary) => self.send("daily-summary", json!({ "summary": summary })),
+                    Err(e) => warn!("Could not summarize the day for the plugins: {}", e),
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::Integration;
+use chrono::{DateTime, Local, Utc};
+use pomo_core::config::SlackConfig;
+use pomo_core::i18n::tr;
+use pomo_core::pomo::{CurrentPomoState, PomodoroState};
+use serde_json::json;
+use tracing::{debug, warn};
+
+const API: &str = "https://slack.com/api";
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Sets the Slack status and pauses Slack notifications during work sections. The requests are
+/// sent from a separate thread so a slow connection doesn't hold up watch.
+pub struct Slack {
+    /// the end of the work section to show, `None` clears the status
+    sender: Option<Sender<Option<DateTime<Utc>>>>,
+    worker: Option<JoinHandle<()>>,
+    focusing: bool,
+}
+
+impl Slack {
+    pub fn new(config: SlackConfig) -> Slack {
+        let (sender, receiver) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+            for end in receiver {
+                if let Err(e) = update(&agent, &config, end) {
+                    warn!("Could not update the Slack status: {}", e);
+                }
+            }
+        });
+        Slack {
+            sender: Some(sender),
+            worker: Some(worker),
+            focusing: false,
+        }
+    }
+
+    fn send(&mut self, end: Option<DateTime<Utc>>) {
+        self.focusing = end.is_some();
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(end);
+        }
+    }
+}
+
+fn update(
+    agent: &ureq::Agent,
+    config: &SlackConfig,
+    end: Option<DateTime<Utc>>,
+) -> Result<(), String> {
+    let profile = match end {
+        Some(end) => {
+            let text = config
+                .text
+                .as_deref()
+                .unwrap_or(tr("Focusing until {end}"))
+                .replace(
+                    "{end}",
+                    &end.with_timezone(&Local).format("%H:%M").to_string(),
+                );
+            json!({
+                "status_text": text,
+                "status_emoji": config.emoji(),
+                "status_expiration": end.timestamp(),
+            })
+        }
+        None => json!({"status_text": "", "status_emoji": "", "status_expiration": 0}),
+    };
+    let method = "users.profile.set";
+    check(
+        method,
+        post(agent, config, method).send_json(json!({ "profile": profile })),
+    )?;
+    if config.dnd() {
+        match end {
+            Some(end) => {
+                let method = "dnd.setSnooze";
+                let minutes = (((end - Utc::now()).num_seconds() + 59) / 60)
+                    .max(1)
+                    .to_string();
+                let form = [("num_minutes", minutes.as_str())];
+                check(method, post(agent, config, method).send_form(&form))?
+            }
+            None => {
+                let method = "dnd.endSnooze";
+                check(method, post(agent, config, method).send_form(&[]))?
+            }
+        }
+    }
+    Ok(())
+}
+
+fn post(agent: &ureq::Agent, config: &SlackConfig, method: &str) -> ureq::Request {
+    debug!("Calling Slack {}", method);
+    agent.post(&format!("{}/{}", API, method)).set(
+        "Authorization",
+        &format!("Bearer {}", config.token.as_deref().unwrap_or_default()),
+    )
+}
+
+/// Checks the response of a method of the Slack Web API, errors are reported by Slack in the
+/// `error` field
+fn check(method: &str, response: Result<ureq::Response, ureq::Error>) -> Result<(), String> {
+    let response: serde_json::Value = response
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    match response["ok"].as_bool() {
+        Some(true) => Ok(()),
+        // ending a snooze that already ended is fine
+        _ if response["error"] == "snooze_not_active" => Ok(()),
+        _ => Err(format!(
+            "{} failed: {}",
+            method,
+            response["error"].as_str().unwrap_or("unknown error")
+        )),
+    }
+}
+
+impl Integration for Slack {
+    fn transition(&mut self, _from: PomodoroState, state: &CurrentPomoState) {
+        if state.current_state == PomodoroState::Work && !state.open_ended {
+            let end = Utc::now() + state.duration;
+            self.send(Some(end));
+        } else if self.focusing {
+            self.send(None);
+        }
+    }
+
+    fn cleanup(&mut self) {
+        if self.focusing {
+            self.send(None);
+        }
+        // wait until the status is cleared
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
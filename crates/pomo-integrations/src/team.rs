@@ -0,0 +1,125 @@
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::Integration;
+use chrono::Utc;
+use pomo_core::config::TeamConfig;
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use pomo_core::team::{read_members, write_member, MemberStatus};
+use tracing::{debug, warn};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// The status is published this often even if nothing changed, so the others know the watch is
+/// still running
+const HEARTBEAT: Duration = Duration::from_secs(60);
+
+/// Publishes the status of the pomo to the team folder or the `pomo serve` of the team, from a
+/// separate thread so a slow network drive doesn't hold up watch
+pub struct Team {
+    name: String,
+    share_task: bool,
+    sender: Option<Sender<MemberStatus>>,
+    worker: Option<JoinHandle<()>>,
+    last: Option<MemberStatus>,
+    last_publish: Instant,
+}
+
+impl Team {
+    pub fn new(config: TeamConfig) -> Team {
+        let (sender, receiver) = mpsc::channel::<MemberStatus>();
+        let name = config.name();
+        let share_task = config.share_task;
+        let worker = thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+            for status in receiver {
+                if let Err(e) = publish(&agent, &config, &status) {
+                    warn!("Could not publish the status to the team: {}", e);
+                }
+            }
+        });
+        Team {
+            name,
+            share_task,
+            sender: Some(sender),
+            worker: Some(worker),
+            last: None,
+            last_publish: Instant::now(),
+        }
+    }
+
+    fn send(&mut self, status: MemberStatus) {
+        self.last_publish = Instant::now();
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(status);
+        }
+    }
+}
+
+fn publish(agent: &ureq::Agent, config: &TeamConfig, status: &MemberStatus) -> Result<(), String> {
+    if let Some(dir) = config.dir() {
+        debug!("Writing the team status to {}", dir.display());
+        write_member(&dir, status).map_err(|e| e.message().to_string())?;
+    }
+    if let Some(url) = &config.url {
+        let url = format!("{}/team/{}", url.trim_end_matches('/'), status.name);
+        debug!("Sending the team status to {}", url);
+        let mut request = agent.put(&url);
+        if let Some(token) = &config.token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        request.send_json(status).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// The statuses of the team, from the team folder or the `pomo serve` of the team
+pub fn members(config: &TeamConfig) -> Result<Vec<MemberStatus>, String> {
+    if let Some(dir) = config.dir() {
+        return read_members(&dir).map_err(|e| e.message().to_string());
+    }
+    let Some(url) = &config.url else {
+        return Err("Set 'dir' or 'url' in the [team] section of the config file".to_string());
+    };
+    let url = format!("{}/team", url.trim_end_matches('/'));
+    ureq::AgentBuilder::new()
+        .timeout(TIMEOUT)
+        .build()
+        .get(&url)
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| format!("{}: {}", url, e))
+}
+
+impl Integration for Team {
+    fn transition(&mut self, _from: PomodoroState, _state: &CurrentPomoState) {}
+
+    fn tick(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) {
+        let now = Utc::now();
+        let status = MemberStatus::new(&self.name, pomo, state, now, self.share_task);
+        let changed = self.last.as_ref().is_none_or(|last| {
+            last.state != status.state
+                || last.paused != status.paused
+                // `until` moves by a second now and then as the ticks don't hit the seconds exactly
+                || match (last.until, status.until) {
+                    (Some(a), Some(b)) => (a - b).num_seconds().abs() > 1,
+                    (a, b) => a.is_some() != b.is_some(),
+                }
+        });
+        if !changed && self.last_publish.elapsed() < HEARTBEAT {
+            return;
+        }
+        self.last = Some(status.clone());
+        self.send(status);
+    }
+
+    fn cleanup(&mut self) {
+        let status = MemberStatus::offline(&self.name, Utc::now());
+        self.send(status);
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
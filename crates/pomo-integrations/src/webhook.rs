@@ -0,0 +1,174 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::Integration;
+use chrono::{DateTime, SecondsFormat, Utc};
+use pomo_core::config::WebhookConfig;
+use pomo_core::format::{state_json, FormatOptions};
+use pomo_core::pomo::{CurrentPomoState, Pomodoro, PomodoroState};
+use serde_json::json;
+use tracing::{debug, warn};
+
+const TIMEOUT: Duration = Duration::from_secs(10);
+/// Delay before the first retry, it doubles with every attempt
+const FIRST_RETRY: Duration = Duration::from_secs(1);
+const MAX_RETRY: Duration = Duration::from_secs(60);
+
+/// What the webhooks last saw of the pomo
+#[derive(Clone, Copy, PartialEq)]
+struct Seen {
+    start: DateTime<Utc>,
+    active: bool,
+    state: PomodoroState,
+    pause: bool,
+}
+
+/// POSTs the pomo as JSON to the configured URLs when its state changes or it is paused,
+/// unpaused or stopped. The requests are sent from a separate thread and retried with a growing
+/// delay when they fail.
+pub struct Webhook {
+    sender: Option<Sender<serde_json::Value>>,
+    worker: Option<JoinHandle<()>>,
+    /// set on exit, failed requests are not retried anymore
+    stopping: Arc<AtomicBool>,
+    last: Option<Seen>,
+}
+
+impl Webhook {
+    pub fn new(config: WebhookConfig) -> Webhook {
+        let (sender, receiver) = mpsc::channel::<serde_json::Value>();
+        let stopping = Arc::new(AtomicBool::new(false));
+        let worker_stopping = stopping.clone();
+        let worker = thread::spawn(move || {
+            let agent = ureq::AgentBuilder::new().timeout(TIMEOUT).build();
+            for body in receiver {
+                for url in config.urls.iter() {
+                    if let Err(e) = post(&agent, url, &body, config.retries(), &worker_stopping) {
+                        warn!("Could not call the webhook: {}", e);
+                    }
+                }
+            }
+        });
+        Webhook {
+            sender: Some(sender),
+            worker: Some(worker),
+            stopping,
+            last: None,
+        }
+    }
+}
+
+fn post(
+    agent: &ureq::Agent,
+    url: &str,
+    body: &serde_json::Value,
+    retries: u32,
+    stopping: &AtomicBool,
+) -> Result<(), String> {
+    let mut delay = FIRST_RETRY;
+    let mut attempt = 0;
+    loop {
+        debug!("Calling the webhook {}", url);
+        let error = match agent.post(url).send_json(body) {
+            Ok(_) => return Ok(()),
+            // the webhook refused the request, it won't accept it the next time either
+            Err(ureq::Error::Status(status, _)) if status != 429 && status < 500 => {
+                return Err(format!("{}: status code {}", url, status))
+            }
+            Err(e) => e.to_string(),
+        };
+        if attempt >= retries || !wait(delay, stopping) {
+            return Err(error);
+        }
+        debug!("Retrying the webhook: {}", error);
+        attempt += 1;
+        delay = (delay * 2).min(MAX_RETRY);
+    }
+}
+
+/// Sleeps for `delay`, returns false when watch exits in the meantime
+fn wait(delay: Duration, stopping: &AtomicBool) -> bool {
+    let until = Instant::now() + delay;
+    while Instant::now() < until {
+        if stopping.load(Ordering::Relaxed) {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    !stopping.load(Ordering::Relaxed)
+}
+
+/// The sections after the current one with their planned start and end, the times are only
+/// known while the timer is counting down
+fn schedule(pomo: &Pomodoro, state: &CurrentPomoState, now: DateTime<Utc>) -> serde_json::Value {
+    let sections = pomo.upcoming_sections(now).unwrap_or_default();
+    let mut start = (!state.pause && !state.open_ended && !state.overtime && !state.waiting)
+        .then(|| now + state.duration);
+    let time = |t: Option<DateTime<Utc>>| t.map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true));
+    sections
+        .iter()
+        .map(|section| {
+            let end = start.filter(|_| !section.open).map(|s| s + section.span());
+            let entry = json!({
+                "state": section.state.to_string(),
+                "start": time(start),
+                "end": time(end),
+            });
+            start = end;
+            entry
+        })
+        .collect()
+}
+
+impl Integration for Webhook {
+    fn transition(&mut self, _from: PomodoroState, _state: &CurrentPomoState) {}
+
+    fn tick(&mut self, pomo: &Pomodoro, state: &CurrentPomoState) {
+        let seen = Seen {
+            start: pomo.start,
+            active: pomo.active,
+            state: state.current_state,
+            pause: state.pause,
+        };
+        let last = match self.last.replace(seen) {
+            Some(last) if last != seen => last,
+            // nothing is sent for the state watch finds when it starts
+            _ => return,
+        };
+        let event = if last.active && !seen.active && last.start == seen.start {
+            "stop"
+        } else if last.state != seen.state || last.start != seen.start {
+            "transition"
+        } else if seen.pause {
+            "pause"
+        } else {
+            "unpause"
+        };
+        let now = Utc::now();
+        let body = json!({
+            "event": event,
+            "from": last.state.to_string(),
+            "to": seen.state.to_string(),
+            "time": now.to_rfc3339_opts(SecondsFormat::Secs, true),
+            "task": pomo.task,
+            "tags": pomo.tags,
+            "status": state_json(pomo, state, FormatOptions::default()),
+            "schedule": schedule(pomo, state, now),
+        });
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(body);
+        }
+    }
+
+    fn cleanup(&mut self) {
+        // send what is left without waiting for retries
+        self.stopping.store(true, Ordering::Relaxed);
+        self.sender.take();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}